@@ -2,11 +2,18 @@ use std::sync::OnceLock;
 
 use anyhow::{anyhow, Result};
 use bw_web_api_rs::models::aurora_profile::{ScrMmGameLoading, ScrProfile, ScrToonInfo};
+use bw_web_api_rs::models::matchmaker_game_info::MatchmakerGameInfo;
 use bw_web_api_rs::models::matchmaker_player_info::MatchmakerPlayerInfo;
 use bw_web_api_rs::{ApiClient, ApiConfig};
 
+use crate::apm_stats::{apm_stats, ApmStats};
+use crate::form::FormEntry;
 use crate::history::OpponentRecord;
-use crate::profile_history::{MatchOutcome, ProfileHistoryKey, ProfileHistoryService, StoredMatch};
+use crate::maps::{map_stats, normalize_map_name, MapStat};
+use crate::profile_history::{
+    MatchOutcome, ProfileHistoryKey, ProfileHistoryService, StatsWindow, StoredMatch,
+};
+use crate::rating_stats::{rating_split, RatingSplit};
 
 pub struct ApiHandle {
     client: ApiClient,
@@ -100,6 +107,29 @@ impl ApiHandle {
         Ok(data)
     }
 
+    // One page of a toon's ladder games in `season`, newest first.
+    pub fn get_matchmaker_gameinfo(
+        &self,
+        name: &str,
+        gw_num: u16,
+        season: u32,
+        offset: u32,
+        limit: u32,
+    ) -> Result<MatchmakerGameInfo> {
+        let gw = crate::gateway::map_gateway(gw_num)
+            .ok_or_else(|| anyhow!("Unknown gateway: {}", gw_num))?;
+        let fut = self.client.get_matchmaker_gameinfo(
+            name.to_string(),
+            gw,
+            LADDER_1V1_GAMEMODE,
+            season,
+            Some(offset),
+            Some(limit),
+        );
+        let data: MatchmakerGameInfo = runtime()?.block_on(fut)?;
+        Ok(data)
+    }
+
     pub fn get_matchmaker_player_info(&self, match_id: &str) -> Result<MatchmakerPlayerInfo> {
         let fut = self.client.get_matchmaker_player_info(match_id.to_string());
         let data: MatchmakerPlayerInfo = runtime()?.block_on(fut)?;
@@ -131,14 +161,17 @@ impl ApiHandle {
         self.compute_rating_for_guid(info, guid)
     }
 
-    pub fn profile_stats_last100(
+    pub fn profile_stats(
         &self,
         profile: &ScrProfile,
         main_toon: &str,
         profile_history: Option<&mut ProfileHistoryService>,
         history_key: Option<&ProfileHistoryKey>,
         known_random_opponents: Option<&std::collections::HashMap<String, OpponentRecord>>,
-    ) -> (Option<String>, Vec<String>, Vec<bool>, u32, u32) {
+        window: StatsWindow,
+    ) -> ProfileStats {
+        let season = Some(profile.matchmaked_current_season);
+        let seasons = SeasonSpans::new(profile, main_toon);
         let random_opponents: Option<std::collections::HashSet<String>> = known_random_opponents
             .map(|map| {
                 map.iter()
@@ -198,12 +231,15 @@ impl ApiHandle {
                 opponent_race,
                 main_race: main_player.attributes.race.clone(),
                 result,
+                season: seasons.season_at(ts),
+                map: normalize_map_name(&g.attributes.map_name),
+                ..Default::default()
             });
         }
         matches.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
 
         let combined = if let (Some(history), Some(key)) = (profile_history, history_key) {
-            match history.merge_matches(key, matches.clone()) {
+            match history.merge_matches(key, matches.clone(), window, season) {
                 Ok(merged) => merged,
                 Err(err) => {
                     tracing::error!(error = %err, "failed to merge profile history");
@@ -211,7 +247,8 @@ impl ApiHandle {
                 }
             }
         } else {
-            matches.into_iter().take(100).collect()
+            let limit = window.limit().unwrap_or(matches.len());
+            matches.into_iter().take(limit).collect()
         };
 
         let mut race_counts = std::collections::HashMap::new();
@@ -268,7 +305,7 @@ impl ApiHandle {
             }
         }
 
-        let mut results: Vec<bool> = Vec::new();
+        let mut results: Vec<FormEntry> = Vec::new();
         let mut total_wins: u32 = 0;
         let mut total_games: u32 = 0;
         let mut self_dodged: u32 = 0;
@@ -280,7 +317,10 @@ impl ApiHandle {
                 if is_win {
                     total_wins = total_wins.saturating_add(1);
                 }
-                results.push(is_win);
+                results.push(FormEntry {
+                    win: is_win,
+                    opponent_race: m.opponent_race.clone(),
+                });
             } else if m.result.is_self_dodged() {
                 self_dodged = self_dodged.saturating_add(1);
             } else if m.result.is_opponent_dodged() {
@@ -326,17 +366,108 @@ impl ApiHandle {
             .as_deref()
             .map(|race| crate::race::display_label(race).to_string());
 
-        (
-            main_race_display,
-            lines,
+        ProfileStats {
+            main_race: main_race_display,
+            matchups: lines,
+            maps: map_stats(&combined),
+            rating_split: rating_split(&combined),
+            apm: apm_stats(&combined),
             results,
             self_dodged,
             opponent_dodged,
-        )
+        }
     }
 }
+
+pub struct ProfileStats {
+    pub main_race: Option<String>,
+    pub matchups: Vec<String>,
+    pub maps: Vec<MapStat>,
+    pub rating_split: RatingSplit,
+    pub apm: ApmStats,
+    pub results: Vec<FormEntry>,
+    pub self_dodged: u32,
+    pub opponent_dodged: u32,
+}
 // Minimum games threshold used for displaying a rating
 pub const RATING_MIN_GAMES: u32 = 5;
+// Matchmaker game mode id of the 1v1 ladder.
+const LADDER_1V1_GAMEMODE: u32 = 1;
+
+// Which ladder season each of a toon's games was played in. The API does not
+// say per game, but it does report how many games the toon played in each
+// season: the newest games belong to the current season, the ones before them
+// to the season before, and so on.
+pub struct SeasonSpans {
+    // The toon's 1v1 game start times, newest first.
+    newest_first: Vec<u64>,
+    // (season, games played in it), newest season first.
+    spans: Vec<(u32, usize)>,
+    current: u32,
+}
+
+impl SeasonSpans {
+    pub fn new(profile: &ScrProfile, toon: &str) -> Self {
+        let timestamps = profile
+            .game_results
+            .iter()
+            .filter(|g| {
+                let players: Vec<_> = g
+                    .players
+                    .iter()
+                    .filter(|p| p.attributes.r#type == "player" && !p.toon.trim().is_empty())
+                    .collect();
+                players.len() == 2 && players.iter().any(|p| p.toon.eq_ignore_ascii_case(toon))
+            })
+            .filter_map(|g| g.create_time.parse::<u64>().ok())
+            .collect();
+        let counts = profile
+            .matchmaked_stats
+            .iter()
+            .filter(|s| s.toon.eq_ignore_ascii_case(toon))
+            .map(|s| (s.season_id, (s.wins + s.losses) as usize));
+        Self::from_parts(timestamps, counts, profile.matchmaked_current_season)
+    }
+
+    fn from_parts(
+        mut timestamps: Vec<u64>,
+        counts: impl Iterator<Item = (u32, usize)>,
+        current: u32,
+    ) -> Self {
+        timestamps.sort_by_key(|ts| std::cmp::Reverse(*ts));
+        timestamps.dedup();
+        let mut by_season: std::collections::BTreeMap<u32, usize> = Default::default();
+        for (season, games) in counts.filter(|(season, _)| *season <= current) {
+            *by_season.entry(season).or_default() += games;
+        }
+        Self {
+            newest_first: timestamps,
+            spans: by_season.into_iter().rev().collect(),
+            current,
+        }
+    }
+
+    // Games older than every counted season get no season. Without any counts
+    // the current season is assumed, as before the counts were used.
+    pub fn season_at(&self, timestamp: u64) -> Option<u32> {
+        if self.spans.is_empty() {
+            return Some(self.current);
+        }
+        let newer = self
+            .newest_first
+            .iter()
+            .filter(|&&ts| ts > timestamp)
+            .count();
+        let mut seen = 0;
+        for &(season, games) in &self.spans {
+            seen += games;
+            if newer < seen {
+                return Some(season);
+            }
+        }
+        None
+    }
+}
 
 pub fn find_guid_for_toon(info: &ScrToonInfo, profile_name: &str) -> Option<u32> {
     let season = info.matchmaked_current_season;
@@ -369,3 +500,27 @@ fn runtime() -> Result<&'static tokio::runtime::Runtime> {
         .as_ref()
         .map_err(|msg| anyhow!("failed to build global tokio runtime: {msg}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn season_spans_split_games_by_per_season_counts() {
+        let spans = SeasonSpans::from_parts(
+            vec![100, 500, 400, 300, 200],
+            [(21, 2), (20, 1), (20, 1), (19, 0)].into_iter(),
+            21,
+        );
+        assert_eq!(spans.season_at(500), Some(21));
+        assert_eq!(spans.season_at(400), Some(21));
+        assert_eq!(spans.season_at(300), Some(20));
+        assert_eq!(spans.season_at(200), Some(20));
+        assert_eq!(spans.season_at(100), None);
+        // A game newer than everything listed lands in the current season.
+        assert_eq!(spans.season_at(900), Some(21));
+
+        let unknown = SeasonSpans::from_parts(vec![100], std::iter::empty(), 21);
+        assert_eq!(unknown.season_at(100), Some(21));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::profile_history::StoredMatch;
+
+// Games per point on the APM trend, and how many points are kept.
+pub const APM_TREND_CHUNK: usize = 10;
+pub const APM_TREND_POINTS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchupApm {
+    pub opponent_race: String,
+    pub apm: u32,
+    pub eapm: Option<u32>,
+    pub games: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApmStats {
+    pub matchups: Vec<MatchupApm>,
+    // Average APM per block of `APM_TREND_CHUNK` games, oldest first.
+    pub trend: Vec<u32>,
+}
+
+impl ApmStats {
+    pub fn is_empty(&self) -> bool {
+        self.matchups.is_empty()
+    }
+}
+
+fn average(values: impl IntoIterator<Item = u32>) -> Option<u32> {
+    let (sum, count) = values.into_iter().fold((0u64, 0u64), |(sum, count), v| {
+        (sum + u64::from(v), count + 1)
+    });
+    (count > 0).then(|| (sum as f64 / count as f64).round() as u32)
+}
+
+// Our APM over games that have it from a replay; `matches` are newest first.
+pub fn apm_stats(matches: &[StoredMatch]) -> ApmStats {
+    let with_apm: Vec<&StoredMatch> = matches
+        .iter()
+        .filter(|m| m.result.counts_for_record() && m.apm.is_some())
+        .collect();
+
+    let mut matchups: Vec<MatchupApm> = Vec::new();
+    let mut races: Vec<&str> = with_apm
+        .iter()
+        .map(|m| {
+            m.opponent_race
+                .as_deref()
+                .map(crate::race::initial)
+                .unwrap_or("?")
+        })
+        .collect();
+    races.sort_unstable();
+    races.dedup();
+    for race in races {
+        let games: Vec<&&StoredMatch> = with_apm
+            .iter()
+            .filter(|m| {
+                m.opponent_race
+                    .as_deref()
+                    .map(crate::race::initial)
+                    .unwrap_or("?")
+                    == race
+            })
+            .collect();
+        let Some(apm) = average(games.iter().filter_map(|m| m.apm)) else {
+            continue;
+        };
+        matchups.push(MatchupApm {
+            opponent_race: race.to_string(),
+            apm,
+            eapm: average(games.iter().filter_map(|m| m.eapm)),
+            games: games.len() as u32,
+        });
+    }
+
+    let mut trend: Vec<u32> = with_apm
+        .chunks(APM_TREND_CHUNK)
+        .take(APM_TREND_POINTS)
+        .filter_map(|chunk| average(chunk.iter().filter_map(|m| m.apm)))
+        .collect();
+    trend.reverse();
+
+    ApmStats { matchups, trend }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile_history::MatchOutcome;
+
+    #[test]
+    fn averages_by_matchup_and_trend() {
+        let m = |race: &str, apm: Option<u32>, result| StoredMatch {
+            timestamp: 1,
+            opponent: "x".to_string(),
+            opponent_race: Some(race.to_string()),
+            result,
+            apm,
+            eapm: apm.map(|a| a - 30),
+            ..Default::default()
+        };
+        let mut matches = vec![
+            m("Zerg", Some(200), MatchOutcome::Win),
+            m("Zerg", Some(180), MatchOutcome::Loss),
+            m("Terran", Some(150), MatchOutcome::Win),
+            m("Terran", None, MatchOutcome::Win),
+            m("Protoss", Some(400), MatchOutcome::OpponentDodged),
+        ];
+        matches.extend((0..10).map(|_| m("Zerg", Some(100), MatchOutcome::Win)));
+        let stats = apm_stats(&matches);
+        let races: Vec<_> = stats
+            .matchups
+            .iter()
+            .map(|mu| (mu.opponent_race.as_str(), mu.apm, mu.eapm, mu.games))
+            .collect();
+        assert_eq!(
+            races,
+            vec![("T", 150, Some(120), 1), ("Z", 115, Some(85), 12)]
+        );
+        assert_eq!(stats.trend, vec![100, 123]);
+    }
+}
@@ -0,0 +1,103 @@
+use crossterm::event::KeyCode;
+
+use crate::replay_download::parse_matchup_filter;
+use crate::replay_filter::{parse_date, parse_date_end, parse_duration_range, ReplayFilter};
+
+use super::{App, LibraryAction, LibraryFocus};
+
+impl App {
+    pub(super) fn handle_library_key(&mut self, code: KeyCode) {
+        let view = &mut self.library_view;
+        match code {
+            KeyCode::Tab => view.focus = next_focus(view.focus),
+            KeyCode::BackTab => view.focus = prev_focus(view.focus),
+            KeyCode::Up => view.selected = view.selected.saturating_sub(1),
+            KeyCode::Down => view.selected = view.selected.saturating_add(1),
+            KeyCode::PageUp => view.selected = view.selected.saturating_sub(10),
+            KeyCode::PageDown => view.selected = view.selected.saturating_add(10),
+            KeyCode::Home => view.selected = 0,
+            KeyCode::End => view.selected = usize::MAX,
+            KeyCode::F(2) => view.sort = view.sort.next(),
+            KeyCode::F(3) => view.ascending = !view.ascending,
+            KeyCode::F(5) => {
+                view.pending_action = view.selected_path.clone().map(LibraryAction::CopyPath);
+            }
+            KeyCode::F(6) => {
+                view.pending_action = view.selected_path.clone().map(LibraryAction::Reveal);
+            }
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ')
+                if view.focus == LibraryFocus::Result =>
+            {
+                view.result = view.result.next();
+                view.selected = 0;
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = self.library_input_mut() {
+                    text.pop();
+                    self.library_view.selected = 0;
+                }
+            }
+            KeyCode::Char(c) => {
+                if let Some(text) = self.library_input_mut() {
+                    text.push(c);
+                    self.library_view.selected = 0;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn library_input_mut(&mut self) -> Option<&mut String> {
+        let view = &mut self.library_view;
+        match view.focus {
+            LibraryFocus::Player => Some(&mut view.player),
+            LibraryFocus::Matchup => Some(&mut view.matchup),
+            LibraryFocus::Map => Some(&mut view.map),
+            LibraryFocus::From => Some(&mut view.from),
+            LibraryFocus::To => Some(&mut view.to),
+            LibraryFocus::Duration => Some(&mut view.duration),
+            LibraryFocus::Result => None,
+        }
+    }
+
+    pub fn library_filter(&self) -> ReplayFilter {
+        let view = &self.library_view;
+        let text = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        let (min_secs, max_secs) = parse_duration_range(&view.duration);
+        ReplayFilter {
+            player: text(&view.player),
+            matchup: parse_matchup_filter(&view.matchup),
+            map: text(&view.map),
+            from: parse_date(&view.from),
+            to: parse_date_end(&view.to),
+            min_secs,
+            max_secs,
+            result: view.result,
+            perspective: self.self_profile.name.clone(),
+        }
+    }
+}
+
+fn next_focus(current: LibraryFocus) -> LibraryFocus {
+    match current {
+        LibraryFocus::Player => LibraryFocus::Matchup,
+        LibraryFocus::Matchup => LibraryFocus::Map,
+        LibraryFocus::Map => LibraryFocus::From,
+        LibraryFocus::From => LibraryFocus::To,
+        LibraryFocus::To => LibraryFocus::Duration,
+        LibraryFocus::Duration => LibraryFocus::Result,
+        LibraryFocus::Result => LibraryFocus::Player,
+    }
+}
+
+fn prev_focus(current: LibraryFocus) -> LibraryFocus {
+    match current {
+        LibraryFocus::Player => LibraryFocus::Result,
+        LibraryFocus::Matchup => LibraryFocus::Player,
+        LibraryFocus::Map => LibraryFocus::Matchup,
+        LibraryFocus::From => LibraryFocus::Map,
+        LibraryFocus::To => LibraryFocus::From,
+        LibraryFocus::Duration => LibraryFocus::To,
+        LibraryFocus::Result => LibraryFocus::Duration,
+    }
+}
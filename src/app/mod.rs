@@ -1,5 +1,12 @@
+mod library;
 mod navigation;
+mod notes;
+mod opponents;
 mod replay;
 mod state;
+mod text_input;
 
-pub use state::{App, DodgeCandidate, ReplayFocus, View};
+pub use state::{
+    App, DodgeCandidate, LibraryAction, LibraryFocus, NoteField, ReplayActivity, ReplayCancel,
+    ReplayFocus, SelfProfileState, View,
+};
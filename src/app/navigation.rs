@@ -11,6 +11,18 @@ impl App {
                 self.handle_replay_key(code);
                 return;
             }
+            View::Opponents => {
+                self.handle_opponents_key(code);
+                return;
+            }
+            View::Library => {
+                self.handle_library_key(code);
+                return;
+            }
+            View::Main if self.notes.active => {
+                self.handle_notes_key(code);
+                return;
+            }
             View::Main | View::Debug => {}
         }
 
@@ -23,6 +35,15 @@ impl App {
 fn global_intent(view: View, code: KeyCode) -> Option<Intent> {
     match view {
         View::Debug => debug_intent(code),
+        View::Main => main_intent(code),
+        _ => None,
+    }
+}
+
+fn main_intent(code: KeyCode) -> Option<Intent> {
+    match code {
+        KeyCode::Char('n') => Some(Intent::EditOpponentNotes),
+        KeyCode::Char('w') => Some(Intent::CycleStatsWindow),
         _ => None,
     }
 }
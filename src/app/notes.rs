@@ -0,0 +1,148 @@
+use crossterm::event::KeyCode;
+
+use crate::history::{annotations_for, parse_tags, set_annotations, OpponentAnnotations};
+
+use super::text_input::{
+    backspace_at_cursor, delete_at_cursor, insert_char_at_cursor, move_cursor_left,
+    move_cursor_right,
+};
+use super::{App, NoteField};
+
+impl App {
+    pub fn open_note_editor(&mut self) {
+        let Some(name) = self.opponent.name.clone() else {
+            return;
+        };
+        let current = annotations_for(&self.opponent.history, self.opponent.aurora_id, &name);
+        let editor = &mut self.notes;
+        editor.notes_input = current.notes.unwrap_or_default();
+        editor.notes_cursor = editor.notes_input.chars().count();
+        editor.tags_input = current.tags.join(", ");
+        editor.tags_cursor = editor.tags_input.chars().count();
+        editor.field = NoteField::Notes;
+        editor.active = true;
+    }
+
+    pub(super) fn handle_notes_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Esc => {
+                self.notes.active = false;
+            }
+            KeyCode::Enter => {
+                self.commit_note_editor();
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.notes.field = match self.notes.field {
+                    NoteField::Notes => NoteField::Tags,
+                    NoteField::Tags => NoteField::Notes,
+                };
+            }
+            KeyCode::Left => {
+                let (text, cursor) = self.note_active_text_mut();
+                move_cursor_left(text, cursor);
+            }
+            KeyCode::Right => {
+                let (text, cursor) = self.note_active_text_mut();
+                move_cursor_right(text, cursor);
+            }
+            KeyCode::Home => {
+                let (_text, cursor) = self.note_active_text_mut();
+                *cursor = 0;
+            }
+            KeyCode::End => {
+                let (text, cursor) = self.note_active_text_mut();
+                *cursor = text.chars().count();
+            }
+            KeyCode::Backspace => {
+                let (text, cursor) = self.note_active_text_mut();
+                backspace_at_cursor(text, cursor);
+            }
+            KeyCode::Delete => {
+                let (text, cursor) = self.note_active_text_mut();
+                delete_at_cursor(text, cursor);
+            }
+            KeyCode::Char(c) => {
+                let (text, cursor) = self.note_active_text_mut();
+                insert_char_at_cursor(text, cursor, c);
+            }
+            _ => {}
+        }
+    }
+
+    fn note_active_text_mut(&mut self) -> (&mut String, &mut usize) {
+        match self.notes.field {
+            NoteField::Notes => (&mut self.notes.notes_input, &mut self.notes.notes_cursor),
+            NoteField::Tags => (&mut self.notes.tags_input, &mut self.notes.tags_cursor),
+        }
+    }
+
+    fn commit_note_editor(&mut self) {
+        self.notes.active = false;
+        let Some(name) = self.opponent.name.clone() else {
+            return;
+        };
+        let Some(gateway) = self.opponent.gateway else {
+            self.status.last_profile_text = Some(format!(
+                "Notes not saved: gateway of {name} is not known yet"
+            ));
+            return;
+        };
+        let notes = self.notes.notes_input.trim();
+        let annotations = OpponentAnnotations {
+            notes: (!notes.is_empty()).then(|| notes.to_string()),
+            tags: parse_tags(&self.notes.tags_input),
+        };
+        set_annotations(
+            &mut self.opponent.history,
+            self.opponent.aurora_id,
+            &name,
+            gateway,
+            annotations,
+        );
+        self.notes.should_save = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn committing_editor_stores_annotations_and_requests_save() {
+        let mut app = App::default();
+        app.opponent.name = Some("Bob".to_string());
+        app.opponent.gateway = Some(10);
+        app.open_note_editor();
+        for c in "cheeses on Polypoid".chars() {
+            app.handle_notes_key(KeyCode::Char(c));
+        }
+        app.handle_notes_key(KeyCode::Tab);
+        for c in "Rival, proxy".chars() {
+            app.handle_notes_key(KeyCode::Char(c));
+        }
+        app.handle_notes_key(KeyCode::Enter);
+
+        assert!(!app.notes.active);
+        assert!(app.notes.should_save);
+        let rec = app.opponent.history.get("bob").expect("record created");
+        assert_eq!(rec.notes.as_deref(), Some("cheeses on Polypoid"));
+        assert_eq!(rec.tags, vec!["rival".to_string(), "proxy".to_string()]);
+    }
+
+    #[test]
+    fn committing_without_a_gateway_reports_an_error() {
+        let mut app = App::default();
+        app.opponent.name = Some("Bob".to_string());
+        app.open_note_editor();
+        app.handle_notes_key(KeyCode::Char('x'));
+        app.handle_notes_key(KeyCode::Enter);
+
+        assert!(!app.notes.should_save);
+        assert!(app.opponent.history.is_empty());
+        assert!(app
+            .status
+            .last_profile_text
+            .as_deref()
+            .is_some_and(|text| text.contains("Notes not saved")));
+    }
+}
@@ -0,0 +1,36 @@
+use crossterm::event::KeyCode;
+
+use super::text_input::{
+    backspace_at_cursor, delete_at_cursor, insert_char_at_cursor, move_cursor_left,
+    move_cursor_right,
+};
+use super::App;
+
+impl App {
+    pub(super) fn handle_opponents_key(&mut self, code: KeyCode) {
+        let list = &mut self.opponent_list;
+        match code {
+            KeyCode::Up => list.scroll = list.scroll.saturating_sub(1),
+            KeyCode::Down => list.scroll = list.scroll.saturating_add(1),
+            KeyCode::PageUp => list.scroll = list.scroll.saturating_sub(10),
+            KeyCode::PageDown => list.scroll = list.scroll.saturating_add(10),
+            KeyCode::Left => move_cursor_left(&list.tag_filter, &mut list.tag_cursor),
+            KeyCode::Right => move_cursor_right(&list.tag_filter, &mut list.tag_cursor),
+            KeyCode::Home => list.tag_cursor = 0,
+            KeyCode::End => list.tag_cursor = list.tag_filter.chars().count(),
+            KeyCode::Backspace => {
+                backspace_at_cursor(&mut list.tag_filter, &mut list.tag_cursor);
+                list.scroll = 0;
+            }
+            KeyCode::Delete => {
+                delete_at_cursor(&mut list.tag_filter, &mut list.tag_cursor);
+                list.scroll = 0;
+            }
+            KeyCode::Char(c) => {
+                insert_char_at_cursor(&mut list.tag_filter, &mut list.tag_cursor, c);
+                list.scroll = 0;
+            }
+            _ => {}
+        }
+    }
+}
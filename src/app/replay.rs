@@ -1,7 +1,20 @@
 use crossterm::event::KeyCode;
+use std::collections::VecDeque;
 use std::sync::mpsc::TryRecvError;
 
-use super::{App, ReplayFocus};
+use super::text_input::{
+    backspace_at_cursor, delete_at_cursor, insert_char_at_cursor, move_cursor_left,
+    move_cursor_right,
+};
+use super::{App, ReplayActivity, ReplayCancel, ReplayFocus};
+use crate::replay_download::{ReplayDownloadEvent, ReplayStage, RequestOrigin};
+use crate::replay_filter::ResultFilter;
+use crate::session::now_secs;
+
+// Upper bound for the count field.
+const MAX_REPLAY_DOWNLOADS: u16 = 1000;
+// Replays listed in the live progress list.
+const REPLAY_ACTIVITY_KEPT: usize = 50;
 
 impl App {
     pub(super) fn handle_replay_key(&mut self, code: KeyCode) {
@@ -24,6 +37,10 @@ impl App {
                 ReplayFocus::Count => {
                     self.replay_decrement_count();
                 }
+                ReplayFocus::Result => {
+                    self.replay.filters.result = prev_result(self.replay.filters.result)
+                }
+                _ => {}
             },
             KeyCode::Right => match self.replay.focus {
                 ReplayFocus::Toon | ReplayFocus::Alias | ReplayFocus::Matchup => {
@@ -37,6 +54,10 @@ impl App {
                 ReplayFocus::Count => {
                     self.replay_increment_count();
                 }
+                ReplayFocus::Result => {
+                    self.replay.filters.result = self.replay.filters.result.next()
+                }
+                _ => {}
             },
             KeyCode::Up => {
                 if matches!(self.replay.focus, ReplayFocus::Count) {
@@ -54,7 +75,7 @@ impl App {
                         *cursor = 0;
                     }
                 }
-                ReplayFocus::Gateway | ReplayFocus::Count => {}
+                _ => {}
             },
             KeyCode::End => match self.replay.focus {
                 ReplayFocus::Toon | ReplayFocus::Alias | ReplayFocus::Matchup => {
@@ -62,7 +83,7 @@ impl App {
                         *cursor = text.chars().count();
                     }
                 }
-                ReplayFocus::Gateway | ReplayFocus::Count => {}
+                _ => {}
             },
             KeyCode::Backspace => match self.replay.focus {
                 ReplayFocus::Toon | ReplayFocus::Alias | ReplayFocus::Matchup => {
@@ -70,7 +91,12 @@ impl App {
                         backspace_at_cursor(text, cursor);
                     }
                 }
-                ReplayFocus::Gateway | ReplayFocus::Count => {}
+                ReplayFocus::Count => self.replay.input_count /= 10,
+                _ => {
+                    if let Some(text) = self.replay_filter_text_mut() {
+                        text.pop();
+                    }
+                }
             },
             KeyCode::Delete => match self.replay.focus {
                 ReplayFocus::Toon | ReplayFocus::Alias | ReplayFocus::Matchup => {
@@ -78,7 +104,7 @@ impl App {
                         delete_at_cursor(text, cursor);
                     }
                 }
-                ReplayFocus::Gateway | ReplayFocus::Count => {}
+                _ => {}
             },
             KeyCode::Char(c) => match self.replay.focus {
                 ReplayFocus::Toon | ReplayFocus::Alias | ReplayFocus::Matchup => {
@@ -87,20 +113,51 @@ impl App {
                     }
                 }
                 ReplayFocus::Count => {
-                    if c.is_ascii_digit() {
-                        let digit = c.to_digit(10).unwrap_or(0) as u16;
-                        self.replay.input_count = digit.max(1);
+                    if let Some(digit) = c.to_digit(10) {
+                        let typed = self.replay.input_count as u32 * 10 + digit;
+                        self.replay.input_count = if typed > MAX_REPLAY_DOWNLOADS as u32 {
+                            digit as u16
+                        } else {
+                            typed as u16
+                        };
+                    }
+                }
+                ReplayFocus::Result if c == ' ' => {
+                    self.replay.filters.result = self.replay.filters.result.next();
+                }
+                _ => {
+                    if let Some(text) = self.replay_filter_text_mut() {
+                        text.push(c);
                     }
                 }
-                ReplayFocus::Gateway => {}
             },
             KeyCode::Enter => {
                 self.replay.should_start = true;
             }
+            KeyCode::F(7) => {
+                self.replay.pending_cancel = Some(ReplayCancel::Job);
+            }
+            KeyCode::F(8) => {
+                self.replay.pending_cancel = Some(ReplayCancel::Queue);
+            }
+            KeyCode::F(9) => self.toggle_watch(),
             _ => {}
         }
     }
 
+    // Watches or unwatches the profile, gateway and matchup in the form.
+    fn toggle_watch(&mut self) {
+        let toon = self.replay.toon_input.trim();
+        if toon.is_empty() {
+            self.replay.last_error = Some("Enter a profile name to watch".to_string());
+            return;
+        }
+        let matchup = Some(self.replay.matchup_input.trim()).filter(|m| !m.is_empty());
+        self.watch_list
+            .toggle(toon, self.replay.input_gateway, matchup, now_secs());
+        self.replay.last_error = None;
+    }
+
     fn replay_active_text_mut(&mut self) -> Option<(&mut String, &mut usize)> {
         match self.replay.focus {
             ReplayFocus::Toon => Some((&mut self.replay.toon_input, &mut self.replay.toon_cursor)),
@@ -111,12 +168,25 @@ impl App {
                 &mut self.replay.matchup_input,
                 &mut self.replay.matchup_cursor,
             )),
-            ReplayFocus::Gateway | ReplayFocus::Count => None,
+            _ => None,
+        }
+    }
+
+    fn replay_filter_text_mut(&mut self) -> Option<&mut String> {
+        let filters = &mut self.replay.filters;
+        match self.replay.focus {
+            ReplayFocus::From => Some(&mut filters.from),
+            ReplayFocus::To => Some(&mut filters.to),
+            ReplayFocus::Opponent => Some(&mut filters.opponent),
+            ReplayFocus::Map => Some(&mut filters.map),
+            ReplayFocus::Duration => Some(&mut filters.duration),
+            ReplayFocus::Rating => Some(&mut filters.min_rating),
+            _ => None,
         }
     }
 
     fn replay_increment_count(&mut self) {
-        self.replay.input_count = self.replay.input_count.saturating_add(1);
+        self.replay.input_count = (self.replay.input_count + 1).min(MAX_REPLAY_DOWNLOADS);
     }
 
     fn replay_decrement_count(&mut self) {
@@ -146,25 +216,48 @@ impl App {
         if self.replay.matchup_cursor > len_m {
             self.replay.matchup_cursor = len_m;
         }
-        if self.replay.input_count == 0 {
-            self.replay.input_count = 1;
-        }
     }
 
     pub fn poll_replay_job(&mut self) {
         let mut clear = false;
         if let Some(rx) = self.replay.job_rx.as_ref() {
-            match rx.try_recv() {
-                Ok(summary) => {
-                    self.replay.in_progress = false;
-                    self.replay.last_summary = Some(summary);
-                    clear = true;
-                }
-                Err(TryRecvError::Empty) => {}
-                Err(TryRecvError::Disconnected) => {
-                    self.replay.in_progress = false;
-                    self.replay.last_error = Some("Replay job channel disconnected".to_string());
-                    clear = true;
+            loop {
+                match rx.try_recv() {
+                    Ok(ReplayDownloadEvent::Progress(progress)) => {
+                        self.replay.progress = Some(progress);
+                    }
+                    Ok(ReplayDownloadEvent::Replay { label, stage }) => {
+                        record_activity(&mut self.replay.activity, label, stage);
+                    }
+                    Ok(ReplayDownloadEvent::Finished(mut summary)) => {
+                        self.replay.in_progress = false;
+                        self.replay.progress = None;
+                        self.replay.cancel = None;
+                        for build in std::mem::take(&mut summary.builds) {
+                            self.builds.record(build);
+                        }
+                        if let Some(request) = self
+                            .replay
+                            .last_request
+                            .as_ref()
+                            .filter(|r| r.origin == RequestOrigin::Watch)
+                        {
+                            self.watch_list.record_result(request, &summary);
+                        }
+                        self.replay.last_summary = Some(summary);
+                        clear = true;
+                        break;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.replay.in_progress = false;
+                        self.replay.progress = None;
+                        self.replay.cancel = None;
+                        self.replay.last_error =
+                            Some("Replay job channel disconnected".to_string());
+                        clear = true;
+                        break;
+                    }
                 }
             }
         }
@@ -178,83 +271,56 @@ impl App {
     }
 }
 
+// Updates the replay's row in the live list, newest first.
+fn record_activity(activity: &mut VecDeque<ReplayActivity>, label: String, stage: ReplayStage) {
+    match activity
+        .iter_mut()
+        .find(|a| a.label == label && !a.stage.is_done())
+    {
+        Some(entry) => entry.stage = stage,
+        None => {
+            activity.push_front(ReplayActivity { label, stage });
+            activity.truncate(REPLAY_ACTIVITY_KEPT);
+        }
+    }
+}
+
 fn next_focus(current: ReplayFocus) -> ReplayFocus {
     match current {
         ReplayFocus::Toon => ReplayFocus::Alias,
         ReplayFocus::Alias => ReplayFocus::Gateway,
         ReplayFocus::Gateway => ReplayFocus::Matchup,
         ReplayFocus::Matchup => ReplayFocus::Count,
-        ReplayFocus::Count => ReplayFocus::Toon,
+        ReplayFocus::Count => ReplayFocus::From,
+        ReplayFocus::From => ReplayFocus::To,
+        ReplayFocus::To => ReplayFocus::Result,
+        ReplayFocus::Result => ReplayFocus::Opponent,
+        ReplayFocus::Opponent => ReplayFocus::Map,
+        ReplayFocus::Map => ReplayFocus::Duration,
+        ReplayFocus::Duration => ReplayFocus::Rating,
+        ReplayFocus::Rating => ReplayFocus::Toon,
     }
 }
 
 fn prev_focus(current: ReplayFocus) -> ReplayFocus {
     match current {
-        ReplayFocus::Toon => ReplayFocus::Count,
+        ReplayFocus::Toon => ReplayFocus::Rating,
         ReplayFocus::Alias => ReplayFocus::Toon,
         ReplayFocus::Gateway => ReplayFocus::Alias,
         ReplayFocus::Matchup => ReplayFocus::Gateway,
         ReplayFocus::Count => ReplayFocus::Matchup,
+        ReplayFocus::From => ReplayFocus::Count,
+        ReplayFocus::To => ReplayFocus::From,
+        ReplayFocus::Result => ReplayFocus::To,
+        ReplayFocus::Opponent => ReplayFocus::Result,
+        ReplayFocus::Map => ReplayFocus::Opponent,
+        ReplayFocus::Duration => ReplayFocus::Map,
+        ReplayFocus::Rating => ReplayFocus::Duration,
     }
 }
 
-fn move_cursor_left(text: &str, cursor: &mut usize) {
-    let len = text.chars().count();
-    if *cursor > len {
-        *cursor = len;
-    }
-    *cursor = cursor.saturating_sub(1);
-}
-
-fn move_cursor_right(text: &str, cursor: &mut usize) {
-    let len = text.chars().count();
-    if *cursor < len {
-        *cursor += 1;
-    }
-}
-
-fn insert_char_at_cursor(text: &mut String, cursor: &mut usize, c: char) {
-    let chars: Vec<char> = text.chars().collect();
-    let idx = (*cursor).min(chars.len());
-    let mut updated = String::with_capacity(text.len() + c.len_utf8());
-    updated.extend(chars[..idx].iter().copied());
-    updated.push(c);
-    updated.extend(chars[idx..].iter().copied());
-    *text = updated;
-    *cursor = idx + 1;
-}
-
-fn backspace_at_cursor(text: &mut String, cursor: &mut usize) {
-    if *cursor == 0 {
-        return;
-    }
-
-    let chars: Vec<char> = text.chars().collect();
-    let idx = (*cursor).min(chars.len());
-    if idx == 0 {
-        *cursor = 0;
-        return;
-    }
-
-    let mut updated = String::with_capacity(text.len());
-    updated.extend(chars[..idx - 1].iter().copied());
-    updated.extend(chars[idx..].iter().copied());
-    *text = updated;
-    *cursor = idx - 1;
-}
-
-fn delete_at_cursor(text: &mut String, cursor: &mut usize) {
-    let chars: Vec<char> = text.chars().collect();
-    let idx = (*cursor).min(chars.len());
-    if idx >= chars.len() {
-        return;
-    }
-
-    let mut updated = String::with_capacity(text.len());
-    updated.extend(chars[..idx].iter().copied());
-    updated.extend(chars[idx + 1..].iter().copied());
-    *text = updated;
-    *cursor = idx;
+fn prev_result(current: ResultFilter) -> ResultFilter {
+    current.next().next()
 }
 
 #[cfg(test)]
@@ -276,26 +342,30 @@ mod tests {
     }
 
     #[test]
-    fn insert_backspace_and_delete_work_with_unicode_safe_cursoring() {
-        let mut text = String::from("ab");
-        let mut cursor = 1usize;
-
-        insert_char_at_cursor(&mut text, &mut cursor, 'X');
-        assert_eq!(text, "aXb");
-        assert_eq!(cursor, 2);
-
-        backspace_at_cursor(&mut text, &mut cursor);
-        assert_eq!(text, "ab");
-        assert_eq!(cursor, 1);
-
-        delete_at_cursor(&mut text, &mut cursor);
-        assert_eq!(text, "a");
-        assert_eq!(cursor, 1);
+    fn focus_navigation_cycles_correctly() {
+        assert_eq!(next_focus(ReplayFocus::Rating), ReplayFocus::Toon);
+        assert_eq!(prev_focus(ReplayFocus::Toon), ReplayFocus::Rating);
+        assert_eq!(
+            prev_focus(next_focus(ReplayFocus::Count)),
+            ReplayFocus::Count
+        );
     }
 
     #[test]
-    fn focus_navigation_cycles_correctly() {
-        assert_eq!(next_focus(ReplayFocus::Count), ReplayFocus::Toon);
-        assert_eq!(prev_focus(ReplayFocus::Toon), ReplayFocus::Count);
+    fn activity_updates_running_rows_and_adds_new_ones() {
+        let mut activity = VecDeque::new();
+        record_activity(&mut activity, "a".to_string(), ReplayStage::Downloading);
+        record_activity(
+            &mut activity,
+            "a".to_string(),
+            ReplayStage::Skipped("short game"),
+        );
+        record_activity(&mut activity, "b".to_string(), ReplayStage::FetchingDetail);
+        assert_eq!(activity.len(), 2);
+        assert_eq!(activity[0].label, "b");
+        assert_eq!(activity[1].stage, ReplayStage::Skipped("short game"));
+
+        record_activity(&mut activity, "a".to_string(), ReplayStage::Parsing);
+        assert_eq!(activity.len(), 3);
     }
 }
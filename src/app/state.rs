@@ -1,18 +1,36 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::Receiver;
+use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::Instant;
 
-use crate::api::ApiHandle;
+use crate::api::{ApiHandle, ProfileStats};
+use crate::apm_stats::ApmStats;
+use crate::build_order::BuildOrderLog;
+use crate::form::FormSummary;
 use crate::history::OpponentRecord;
-use crate::profile_history::MatchOutcome;
-use crate::replay_download::{ReplayDownloadRequest, ReplayDownloadSummary, ReplayStorage};
+use crate::maps::MapStat;
+use crate::profile_history::{GameCapture, MatchOutcome, StatsWindow};
+use crate::rating_stats::RatingSplit;
+use crate::replay_download::{
+    DownloadFilterInput, DownloadJournal, ReplayDownloadEvent, ReplayDownloadProgress,
+    ReplayDownloadRequest, ReplayDownloadSummary, ReplayStage, ReplayStorage,
+};
+use crate::replay_filter::{ReplayFilter, ReplaySort, ResultFilter};
+use crate::replay_index::{IndexScan, ReplayIndex};
+use crate::replay_io::ReplaySummary;
+use crate::scout::ScoutState;
+use crate::session::SessionTracker;
+use crate::watch_list::WatchList;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum View {
     Main,
     Debug,
     Replays,
+    Opponents,
+    Library,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,6 +40,26 @@ pub enum ReplayFocus {
     Gateway,
     Matchup,
     Count,
+    From,
+    To,
+    Result,
+    Opponent,
+    Map,
+    Duration,
+    Rating,
+}
+
+// Latest stage of a replay in the running download job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayActivity {
+    pub label: String,
+    pub stage: ReplayStage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayCancel {
+    Job,
+    Queue,
 }
 
 #[derive(Debug)]
@@ -35,12 +73,19 @@ pub struct ReplayState {
     pub alias_cursor: usize,
     pub input_gateway: u16,
     pub input_count: u16,
+    pub filters: DownloadFilterInput,
     pub in_progress: bool,
     pub should_start: bool,
     pub last_summary: Option<ReplayDownloadSummary>,
     pub last_request: Option<ReplayDownloadRequest>,
     pub last_error: Option<String>,
-    pub job_rx: Option<Receiver<ReplayDownloadSummary>>,
+    pub progress: Option<ReplayDownloadProgress>,
+    pub activity: VecDeque<ReplayActivity>,
+    // Downloads waiting for the running job to finish.
+    pub queue: VecDeque<DownloadJournal>,
+    pub pending_cancel: Option<ReplayCancel>,
+    pub cancel: Option<Arc<AtomicBool>>,
+    pub job_rx: Option<Receiver<ReplayDownloadEvent>>,
     pub job_handle: Option<JoinHandle<()>>,
 }
 
@@ -56,17 +101,105 @@ impl Default for ReplayState {
             alias_cursor: 0,
             input_gateway: 10,
             input_count: 5,
+            filters: DownloadFilterInput::default(),
             in_progress: false,
             should_start: false,
             last_summary: None,
             last_request: None,
             last_error: None,
+            progress: None,
+            activity: VecDeque::new(),
+            queue: VecDeque::new(),
+            pending_cancel: None,
+            cancel: None,
             job_rx: None,
             job_handle: None,
         }
     }
 }
 
+#[derive(Debug, Default)]
+pub struct LibraryState {
+    pub index: ReplayIndex,
+    pub last_scan: Option<IndexScan>,
+    pub last_scan_at: Option<Instant>,
+    pub job_rx: Option<Receiver<(ReplayIndex, IndexScan)>>,
+    pub job_handle: Option<JoinHandle<()>>,
+    // Bumped whenever `index` is replaced so the library view re-filters.
+    pub generation: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LibraryFocus {
+    #[default]
+    Player,
+    Matchup,
+    Map,
+    From,
+    To,
+    Duration,
+    Result,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibraryAction {
+    CopyPath(String),
+    Reveal(String),
+}
+
+#[derive(Debug, Default)]
+pub struct LibraryViewState {
+    pub focus: LibraryFocus,
+    pub player: String,
+    pub matchup: String,
+    pub map: String,
+    pub from: String,
+    pub to: String,
+    pub duration: String,
+    pub result: ResultFilter,
+    pub sort: ReplaySort,
+    pub ascending: bool,
+    pub selected: usize,
+    // Set while rendering from the filtered rows; the runtime loads the
+    // detail pane for it.
+    pub selected_path: Option<String>,
+    // Paths of the rows matching `rows_key`: the filter, sort, direction and
+    // index generation they were computed for.
+    pub rows: Vec<String>,
+    pub rows_key: Option<(ReplayFilter, ReplaySort, bool, u64)>,
+    pub detail_path: Option<String>,
+    pub detail: Option<ReplaySummary>,
+    // The detail replay being parsed off the UI thread.
+    pub detail_rx: Option<Receiver<(String, Option<ReplaySummary>)>>,
+    pub pending_action: Option<LibraryAction>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoteField {
+    #[default]
+    Notes,
+    Tags,
+}
+
+#[derive(Debug, Default)]
+pub struct NoteEditorState {
+    pub active: bool,
+    pub field: NoteField,
+    pub notes_input: String,
+    pub notes_cursor: usize,
+    pub tags_input: String,
+    pub tags_cursor: usize,
+    pub should_save: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct OpponentListState {
+    pub tag_filter: String,
+    pub tag_cursor: usize,
+    pub scroll: u16,
+}
+
 #[derive(Debug, Clone)]
 pub struct DodgeCandidate {
     pub opponent: String,
@@ -87,7 +220,7 @@ pub struct DetectionState {
     pub port: Option<u16>,
     pub api: Option<ApiHandle>,
     pub last_port_used: Option<u16>,
-    pub screp_available: bool,
+    pub replay_watch_enabled: bool,
 }
 
 #[derive(Debug, Default)]
@@ -110,6 +243,25 @@ pub struct SelfProfileState {
     pub matchups: Vec<String>,
     pub self_dodged: u32,
     pub opponent_dodged: u32,
+    pub stats_window: StatsWindow,
+    pub form: Option<FormSummary>,
+    pub maps: Vec<MapStat>,
+    pub rating_split: RatingSplit,
+    pub apm: ApmStats,
+    pub pending_rating: Option<GameCapture>,
+}
+
+impl SelfProfileState {
+    pub fn apply_stats(&mut self, stats: ProfileStats) {
+        self.main_race = stats.main_race;
+        self.matchups = stats.matchups;
+        self.self_dodged = stats.self_dodged;
+        self.opponent_dodged = stats.opponent_dodged;
+        self.form = FormSummary::from_results(&stats.results);
+        self.maps = stats.maps;
+        self.rating_split = stats.rating_split;
+        self.apm = stats.apm;
+    }
 }
 
 #[derive(Debug, Default)]
@@ -122,6 +274,8 @@ pub struct OpponentState {
     pub last_observed_at: Option<i64>,
     pub race: Option<String>,
     pub matchups: Vec<String>,
+    pub form: Option<FormSummary>,
+    pub maps: Vec<MapStat>,
     pub history: HashMap<String, OpponentRecord>,
 }
 
@@ -129,6 +283,7 @@ pub struct OpponentState {
 pub struct OverlayState {
     pub rating_last_text: Option<String>,
     pub opponent_last_text: Option<String>,
+    pub session_last_text: Option<String>,
     pub opponent_waiting: bool,
 }
 
@@ -147,7 +302,7 @@ impl std::fmt::Debug for DetectionState {
             .field("port", &self.port)
             .field("has_api", &self.api.is_some())
             .field("last_port_used", &self.last_port_used)
-            .field("screp_available", &self.screp_available)
+            .field("replay_watch_enabled", &self.replay_watch_enabled)
             .finish()
     }
 }
@@ -181,6 +336,14 @@ pub struct App {
     pub replay: ReplayState,
     pub replay_watch: ReplayWatchState,
     pub status: StatusState,
+    pub notes: NoteEditorState,
+    pub opponent_list: OpponentListState,
+    pub session: SessionTracker,
+    pub builds: BuildOrderLog,
+    pub library: LibraryState,
+    pub library_view: LibraryViewState,
+    pub watch_list: WatchList,
+    pub scout: ScoutState,
 }
 
 impl App {
@@ -200,6 +363,9 @@ impl App {
         self.opponent.race = None;
         self.overlays.opponent_last_text = None;
         self.opponent.matchups.clear();
+        self.opponent.form = None;
+        self.opponent.maps.clear();
+        self.notes.active = false;
     }
 
     pub fn is_ready(&self) -> bool {
@@ -224,6 +390,14 @@ impl Default for App {
             replay: ReplayState::default(),
             replay_watch: ReplayWatchState::default(),
             status: StatusState::default(),
+            notes: NoteEditorState::default(),
+            opponent_list: OpponentListState::default(),
+            session: SessionTracker::default(),
+            builds: BuildOrderLog::default(),
+            library: LibraryState::default(),
+            library_view: LibraryViewState::default(),
+            watch_list: WatchList::default(),
+            scout: ScoutState::default(),
         }
     }
 }
@@ -0,0 +1,81 @@
+pub(super) fn move_cursor_left(text: &str, cursor: &mut usize) {
+    let len = text.chars().count();
+    if *cursor > len {
+        *cursor = len;
+    }
+    *cursor = cursor.saturating_sub(1);
+}
+
+pub(super) fn move_cursor_right(text: &str, cursor: &mut usize) {
+    let len = text.chars().count();
+    if *cursor < len {
+        *cursor += 1;
+    }
+}
+
+pub(super) fn insert_char_at_cursor(text: &mut String, cursor: &mut usize, c: char) {
+    let chars: Vec<char> = text.chars().collect();
+    let idx = (*cursor).min(chars.len());
+    let mut updated = String::with_capacity(text.len() + c.len_utf8());
+    updated.extend(chars[..idx].iter().copied());
+    updated.push(c);
+    updated.extend(chars[idx..].iter().copied());
+    *text = updated;
+    *cursor = idx + 1;
+}
+
+pub(super) fn backspace_at_cursor(text: &mut String, cursor: &mut usize) {
+    if *cursor == 0 {
+        return;
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let idx = (*cursor).min(chars.len());
+    if idx == 0 {
+        *cursor = 0;
+        return;
+    }
+
+    let mut updated = String::with_capacity(text.len());
+    updated.extend(chars[..idx - 1].iter().copied());
+    updated.extend(chars[idx..].iter().copied());
+    *text = updated;
+    *cursor = idx - 1;
+}
+
+pub(super) fn delete_at_cursor(text: &mut String, cursor: &mut usize) {
+    let chars: Vec<char> = text.chars().collect();
+    let idx = (*cursor).min(chars.len());
+    if idx >= chars.len() {
+        return;
+    }
+
+    let mut updated = String::with_capacity(text.len());
+    updated.extend(chars[..idx].iter().copied());
+    updated.extend(chars[idx + 1..].iter().copied());
+    *text = updated;
+    *cursor = idx;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_backspace_and_delete_work_with_unicode_safe_cursoring() {
+        let mut text = String::from("ab");
+        let mut cursor = 1usize;
+
+        insert_char_at_cursor(&mut text, &mut cursor, 'X');
+        assert_eq!(text, "aXb");
+        assert_eq!(cursor, 2);
+
+        backspace_at_cursor(&mut text, &mut cursor);
+        assert_eq!(text, "ab");
+        assert_eq!(cursor, 1);
+
+        delete_at_cursor(&mut text, &mut cursor);
+        assert_eq!(text, "a");
+        assert_eq!(cursor, 1);
+    }
+}
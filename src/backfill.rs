@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::history::{OpponentGame, OpponentHistory, OpponentRecord};
+use crate::profile_history::{MatchOutcome, ProfileHistoryKey, ProfileHistoryService, StoredMatch};
+use crate::replay::{classify_short_game_outcome, self_won};
+use crate::replay_index::collect_replays;
+use crate::replay_io::{read_replay_summary, ReplaySummary};
+
+#[derive(Debug, Default)]
+pub struct BackfillReport {
+    pub scanned: usize,
+    pub unreadable: usize,
+    pub games: usize,
+    pub dodges: usize,
+    pub undetermined: usize,
+    pub matches_added: usize,
+    pub matches_updated: usize,
+    pub opponents: usize,
+}
+
+impl std::fmt::Display for BackfillReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Replays: {} scanned, {} unreadable, {} games with our profiles ({} dodges)",
+            self.scanned, self.unreadable, self.games, self.dodges
+        )?;
+        if self.undetermined > 0 {
+            writeln!(
+                f,
+                "Skipped {} games of our profiles with no clear winner",
+                self.undetermined
+            )?;
+        }
+        writeln!(
+            f,
+            "Profile matches: {} added, {} updated",
+            self.matches_added, self.matches_updated
+        )?;
+        write!(f, "Opponents updated: {}", self.opponents)
+    }
+}
+
+// Rebuilds profile and opponent history from every 1v1 replay under `dir`
+// that one of `toons` played in.
+pub fn backfill(
+    cfg: &Config,
+    dir: &Path,
+    toons: &[ProfileHistoryKey],
+    history: &mut OpponentHistory,
+    profiles: &mut ProfileHistoryService,
+) -> Result<BackfillReport> {
+    let mut report = BackfillReport::default();
+    let mut files = Vec::new();
+    collect_replays(dir, &mut files);
+    files.sort();
+
+    let mut by_profile: HashMap<ProfileHistoryKey, Vec<StoredMatch>> = HashMap::new();
+    let mut opponents = std::collections::HashSet::new();
+    for (path, _, mtime) in files {
+        report.scanned += 1;
+        let summary = match read_replay_summary(cfg, &path) {
+            Ok(summary) => summary,
+            Err(err) => {
+                tracing::debug!(error = %err, path = %path.display(), "backfill skipped replay");
+                report.unreadable += 1;
+                continue;
+            }
+        };
+        let timestamp = summary.played_at.unwrap_or(mtime);
+        let Some((key, stored)) = match_from_summary(&summary, toons, timestamp) else {
+            continue;
+        };
+        let Some(mut stored) = stored else {
+            report.undetermined += 1;
+            continue;
+        };
+        stored.replay_path = Some(path.to_string_lossy().into_owned());
+        report.games += 1;
+        if !stored.result.counts_for_record() {
+            report.dodges += 1;
+        }
+
+        let opp_key = crate::race::lower_key(&stored.opponent);
+        let record = history
+            .entry(opp_key.clone())
+            .or_insert_with(|| OpponentRecord::new(stored.opponent.clone(), key.gateway()));
+        record.set_race_if_unknown(stored.opponent_race.as_deref());
+        record.merge_game(OpponentGame {
+            timestamp,
+            self_race: stored.main_race.clone(),
+            opponent_race: stored.opponent_race.clone(),
+            result: stored.result,
+            duration_secs: Some(summary.duration_secs()),
+            map: stored.map.clone(),
+            rating_before: None,
+            rating_after: None,
+            replay_path: stored.replay_path.clone(),
+            provisional: false,
+        });
+        opponents.insert(opp_key);
+
+        by_profile.entry(key).or_default().push(stored);
+    }
+
+    for (key, matches) in by_profile {
+        let merge = profiles.import_matches(&key, matches)?;
+        report.matches_added += merge.added;
+        report.matches_updated += merge.updated;
+    }
+    report.opponents = opponents.len();
+    Ok(report)
+}
+
+// The stored match for a 1v1 replay one of `toons` played in, or no match
+// when the winner can't be told. Games shorter than a minute are classified as
+// dodges, like LastReplay.rep.
+fn match_from_summary(
+    summary: &ReplaySummary,
+    toons: &[ProfileHistoryKey],
+    timestamp: u64,
+) -> Option<(ProfileHistoryKey, Option<StoredMatch>)> {
+    let (key, me, opponent) = toons.iter().find_map(|key| {
+        let (me, opponent) = summary.sides(key.name()).one_vs_one()?;
+        Some((key, me, opponent))
+    })?;
+    let result = summary.winner_team.and_then(|winner_team| {
+        if summary.duration_secs() < 60 {
+            classify_short_game_outcome(winner_team, me.team, opponent.team)
+        } else {
+            self_won(winner_team, me.team, opponent.team).map(|won| {
+                if won {
+                    MatchOutcome::Win
+                } else {
+                    MatchOutcome::Loss
+                }
+            })
+        }
+    });
+    let Some(result) = result else {
+        return Some((key.clone(), None));
+    };
+
+    Some((
+        key.clone(),
+        Some(StoredMatch {
+            timestamp,
+            opponent: opponent.name.clone(),
+            opponent_race: opponent.race.clone(),
+            main_race: me.race.clone(),
+            result,
+            map: summary.map.clone(),
+            apm: me.apm,
+            eapm: me.eapm,
+            opponent_apm: opponent.apm,
+            opponent_eapm: opponent.eapm,
+            ..Default::default()
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_io::ReplayPlayerSummary;
+    use crate::test_support::replay_player;
+
+    fn player(name: &str, race: &str, team: u8) -> ReplayPlayerSummary {
+        replay_player(team, name, Some(race), team)
+    }
+
+    #[test]
+    fn builds_matches_for_own_toons_and_classifies_dodges() {
+        let toons = vec![ProfileHistoryKey::new("Me", 10)];
+        let mut summary = ReplaySummary {
+            frames: 24 * 60 * 12,
+            map: Some("Polypoid".to_string()),
+            played_at: None,
+            players: vec![player("Rival", "Zerg", 1), player("me", "Protoss", 2)],
+            winner_team: Some(2),
+            game_type: 2,
+        };
+
+        let (key, stored) = match_from_summary(&summary, &toons, 100).expect("match");
+        let stored = stored.expect("winner");
+        assert_eq!(key, toons[0]);
+        assert_eq!(stored.opponent, "Rival");
+        assert_eq!(stored.main_race.as_deref(), Some("Protoss"));
+        assert_eq!(stored.result, MatchOutcome::Win);
+
+        summary.frames = 24 * 30;
+        summary.winner_team = Some(1);
+        let (_, stored) = match_from_summary(&summary, &toons, 100).expect("dodge");
+        assert_eq!(stored.expect("winner").result, MatchOutcome::SelfDodged);
+
+        summary.winner_team = None;
+        let (_, stored) = match_from_summary(&summary, &toons, 100).expect("our game");
+        assert!(stored.is_none());
+        summary.winner_team = Some(1);
+
+        summary.players.push(player("Third", "Terran", 3));
+        assert!(match_from_summary(&summary, &toons, 100).is_none());
+        summary.players.pop();
+        assert!(
+            match_from_summary(&summary, &[ProfileHistoryKey::new("Other", 10)], 100).is_none()
+        );
+    }
+}
@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::replay_io::ReplaySummary;
+use crate::replay_parser::{Replay, FRAME_MS};
+
+// Build and train commands kept per player and game.
+pub const BUILD_ORDER_STEPS: usize = 30;
+// Openings shown for the current opponent.
+pub const OPENINGS_SHOWN: usize = 5;
+const MAX_BUILDS_PER_PLAYER: usize = 50;
+// Games kept in the log; the oldest make room for new ones.
+const MAX_MATCHES: usize = 5000;
+const STARTING_SUPPLY: i32 = 4;
+
+const CMD_BUILD: u8 = 0x0C;
+const CMD_TRAIN: u8 = 0x1F;
+const CMD_UNIT_MORPH: u8 = 0x23;
+const CMD_BUILDING_MORPH: u8 = 0x35;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildStep {
+    pub secs: u32,
+    pub supply: u32,
+    pub unit: String,
+}
+
+const WORKERS: [&str; 3] = ["SCV", "Drone", "Probe"];
+
+struct UnitInfo {
+    name: &'static str,
+    // Supply added when the command is issued; morphs count the difference.
+    supply: i32,
+}
+
+const fn unit(name: &'static str, supply: i32) -> Option<UnitInfo> {
+    Some(UnitInfo { name, supply })
+}
+
+fn unit_info(id: u16) -> Option<UnitInfo> {
+    match id {
+        0 => unit("Marine", 1),
+        1 => unit("Ghost", 1),
+        2 => unit("Vulture", 2),
+        3 => unit("Goliath", 2),
+        5 => unit("Siege Tank", 2),
+        7 => unit("SCV", 1),
+        41 => unit("Drone", 1),
+        64 => unit("Probe", 1),
+        8 => unit("Wraith", 2),
+        9 => unit("Science Vessel", 2),
+        11 => unit("Dropship", 2),
+        12 => unit("Battlecruiser", 6),
+        32 => unit("Firebat", 1),
+        34 => unit("Medic", 1),
+        58 => unit("Valkyrie", 3),
+        37 => unit("Zergling", 1),
+        38 => unit("Hydralisk", 1),
+        39 => unit("Ultralisk", 4),
+        42 => unit("Overlord", 0),
+        43 => unit("Mutalisk", 2),
+        44 => unit("Guardian", 0),
+        45 => unit("Queen", 2),
+        46 => unit("Defiler", 2),
+        47 => unit("Scourge", 1),
+        62 => unit("Devourer", 0),
+        103 => unit("Lurker", 1),
+        60 => unit("Corsair", 2),
+        61 => unit("Dark Templar", 2),
+        65 => unit("Zealot", 2),
+        66 => unit("Dragoon", 2),
+        67 => unit("High Templar", 2),
+        69 => unit("Shuttle", 2),
+        70 => unit("Scout", 3),
+        71 => unit("Arbiter", 4),
+        72 => unit("Carrier", 6),
+        83 => unit("Reaver", 4),
+        84 => unit("Observer", 1),
+        106 => unit("Command Center", 0),
+        107 => unit("Comsat Station", 0),
+        108 => unit("Nuclear Silo", 0),
+        109 => unit("Supply Depot", 0),
+        110 => unit("Refinery", 0),
+        111 => unit("Barracks", 0),
+        112 => unit("Academy", 0),
+        113 => unit("Factory", 0),
+        114 => unit("Starport", 0),
+        115 => unit("Control Tower", 0),
+        116 => unit("Science Facility", 0),
+        117 => unit("Covert Ops", 0),
+        118 => unit("Physics Lab", 0),
+        120 => unit("Machine Shop", 0),
+        122 => unit("Engineering Bay", 0),
+        123 => unit("Armory", 0),
+        124 => unit("Missile Turret", 0),
+        125 => unit("Bunker", 0),
+        131 => unit("Hatchery", 0),
+        132 => unit("Lair", 0),
+        133 => unit("Hive", 0),
+        134 => unit("Nydus Canal", 0),
+        135 => unit("Hydralisk Den", 0),
+        136 => unit("Defiler Mound", 0),
+        137 => unit("Greater Spire", 0),
+        138 => unit("Queen's Nest", 0),
+        139 => unit("Evolution Chamber", 0),
+        140 => unit("Ultralisk Cavern", 0),
+        141 => unit("Spire", 0),
+        142 => unit("Spawning Pool", 0),
+        143 => unit("Creep Colony", 0),
+        144 => unit("Spore Colony", 0),
+        146 => unit("Sunken Colony", 0),
+        149 => unit("Extractor", 0),
+        154 => unit("Nexus", 0),
+        155 => unit("Robotics Facility", 0),
+        156 => unit("Pylon", 0),
+        157 => unit("Assimilator", 0),
+        159 => unit("Observatory", 0),
+        160 => unit("Gateway", 0),
+        162 => unit("Photon Cannon", 0),
+        163 => unit("Citadel of Adun", 0),
+        164 => unit("Cybernetics Core", 0),
+        165 => unit("Templar Archives", 0),
+        166 => unit("Forge", 0),
+        167 => unit("Stargate", 0),
+        169 => unit("Fleet Beacon", 0),
+        170 => unit("Arbiter Tribunal", 0),
+        171 => unit("Robotics Support Bay", 0),
+        172 => unit("Shield Battery", 0),
+        _ => None,
+    }
+}
+
+// Turns (frame, command id, unit id) triples for one player into build
+// steps. Supply is estimated from what was queued, ignoring losses, which is
+// accurate for the opening this is meant to describe.
+pub fn build_order_from_actions(
+    race: Option<&str>,
+    actions: impl IntoIterator<Item = (u32, u8, u16)>,
+) -> Vec<BuildStep> {
+    let zerg = race.is_some_and(|r| r.eq_ignore_ascii_case("zerg"));
+    let mut supply = STARTING_SUPPLY;
+    let mut steps = Vec::new();
+    for (frame, code, unit_id) in actions {
+        if !matches!(
+            code,
+            CMD_BUILD | CMD_TRAIN | CMD_UNIT_MORPH | CMD_BUILDING_MORPH
+        ) {
+            continue;
+        }
+        let Some(info) = unit_info(unit_id) else {
+            continue;
+        };
+        steps.push(BuildStep {
+            secs: (u64::from(frame) * FRAME_MS / 1000) as u32,
+            supply: supply.max(0) as u32,
+            unit: info.name.to_string(),
+        });
+        supply += info.supply;
+        // Zerg buildings consume the drone that morphs into them.
+        if zerg && code == CMD_BUILD {
+            supply -= 1;
+        }
+        if steps.len() >= BUILD_ORDER_STEPS {
+            break;
+        }
+    }
+    steps
+}
+
+pub fn build_order_from_replay(
+    replay: &Replay,
+    player_id: u8,
+    race: Option<&str>,
+) -> Vec<BuildStep> {
+    let actions = replay
+        .commands
+        .iter()
+        .filter(|c| c.player_id == player_id)
+        .filter_map(|c| {
+            let unit = match c.code {
+                CMD_BUILD => c.params.get(5..7)?,
+                CMD_TRAIN | CMD_UNIT_MORPH | CMD_BUILDING_MORPH => c.params.get(0..2)?,
+                _ => return None,
+            };
+            Some((c.frame, c.code, u16::from_le_bytes([unit[0], unit[1]])))
+        });
+    build_order_from_actions(race, actions)
+}
+
+// Compact opening label, e.g. "8 Pylon, 10 Gateway, 12 Assimilator".
+pub fn opening_label(steps: &[BuildStep], max_steps: usize) -> String {
+    steps
+        .iter()
+        .filter(|s| !WORKERS.contains(&s.unit.as_str()))
+        .take(max_steps)
+        .map(|s| format!("{} {}", s.supply, s.unit))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerBuild {
+    // The game this build was played in; see `match_id`.
+    #[serde(default)]
+    pub match_id: String,
+    pub player: String,
+    pub race: Option<String>,
+    pub opponent: String,
+    pub opponent_race: Option<String>,
+    pub timestamp: u64,
+    #[serde(default)]
+    pub map: Option<String>,
+    pub steps: Vec<BuildStep>,
+    #[serde(default)]
+    pub apm: Option<u32>,
+    #[serde(default)]
+    pub eapm: Option<u32>,
+}
+
+// Identifies a game by its start time and both players, so the same game
+// read from LastReplay, a download or the library is logged once.
+pub fn match_id(started_at: u64, player: &str, opponent: &str) -> String {
+    let mut names = [player.to_ascii_lowercase(), opponent.to_ascii_lowercase()];
+    names.sort();
+    format!("{started_at}:{}:{}", names[0], names[1])
+}
+
+// Both players' builds from a 1v1 replay played at `timestamp`.
+pub fn builds_from_summary(summary: &ReplaySummary, timestamp: u64) -> Vec<PlayerBuild> {
+    let [a, b] = summary.players.as_slice() else {
+        return Vec::new();
+    };
+    if a.team == b.team || summary.is_custom() {
+        return Vec::new();
+    }
+    let id = match_id(summary.played_at.unwrap_or(timestamp), &a.name, &b.name);
+    [(a, b), (b, a)]
+        .into_iter()
+        .map(|(player, opponent)| PlayerBuild {
+            match_id: id.clone(),
+            player: player.name.clone(),
+            race: player.race.clone(),
+            opponent: opponent.name.clone(),
+            opponent_race: opponent.race.clone(),
+            timestamp,
+            map: summary.map.clone(),
+            steps: player.build_order.clone(),
+            apm: player.apm,
+            eapm: player.eapm,
+        })
+        .collect()
+}
+
+// Build orders and APM seen per game, keyed by match id. Kept on `App` and
+// persisted by `BuildOrderStore`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BuildOrderLog {
+    #[serde(default)]
+    matches: HashMap<String, Vec<PlayerBuild>>,
+    // Logs from before builds were keyed per game; folded into `matches` on
+    // load.
+    #[serde(default, skip_serializing)]
+    players: HashMap<String, Vec<PlayerBuild>>,
+    #[serde(skip)]
+    pub dirty: bool,
+}
+
+impl BuildOrderLog {
+    pub fn record(&mut self, mut build: PlayerBuild) {
+        if build.steps.is_empty() && build.apm.is_none() {
+            return;
+        }
+        if build.match_id.is_empty() {
+            build.match_id = match_id(build.timestamp, &build.player, &build.opponent);
+        }
+        if let Some(builds) = self.matches.get(&build.match_id) {
+            if builds
+                .iter()
+                .any(|b| b.player.eq_ignore_ascii_case(&build.player))
+            {
+                return;
+            }
+        } else if self.matches.len() >= MAX_MATCHES {
+            let Some((oldest, at)) = self
+                .matches
+                .iter()
+                .map(|(id, builds)| (id.clone(), builds[0].timestamp))
+                .min_by_key(|(_, at)| *at)
+            else {
+                return;
+            };
+            if build.timestamp <= at {
+                return;
+            }
+            self.matches.remove(&oldest);
+        }
+        self.matches
+            .entry(build.match_id.clone())
+            .or_default()
+            .push(build);
+        self.dirty = true;
+    }
+
+    fn migrate_legacy(&mut self) {
+        for build in std::mem::take(&mut self.players).into_values().flatten() {
+            self.record(build);
+        }
+    }
+
+    // The latest builds of `player`, newest first.
+    fn player_builds(&self, player: &str) -> Vec<&PlayerBuild> {
+        let mut builds: Vec<&PlayerBuild> = self
+            .matches
+            .values()
+            .flatten()
+            .filter(|b| b.player.eq_ignore_ascii_case(player))
+            .collect();
+        builds.sort_by_key(|b| std::cmp::Reverse(b.timestamp));
+        builds.truncate(MAX_BUILDS_PER_PLAYER);
+        builds
+    }
+
+    // Latest openings by `player` as `race` against `opponent_race`; unknown
+    // races on either side do not filter.
+    pub fn openings(
+        &self,
+        player: &str,
+        race: Option<&str>,
+        opponent_race: Option<&str>,
+        limit: usize,
+    ) -> Vec<&PlayerBuild> {
+        let same = |a: Option<&str>, b: Option<&str>| match (a, b) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            _ => true,
+        };
+        self.player_builds(player)
+            .into_iter()
+            .filter(|b| !b.steps.is_empty())
+            .filter(|b| same(b.race.as_deref(), race))
+            .filter(|b| same(b.opponent_race.as_deref(), opponent_race))
+            .take(limit)
+            .collect()
+    }
+
+    // Average (APM, EAPM, games) over the latest replays we have of `player`.
+    pub fn average_apm(&self, player: &str) -> Option<(u32, Option<u32>, u32)> {
+        let builds = self.player_builds(player);
+        let apms: Vec<u32> = builds.iter().filter_map(|b| b.apm).collect();
+        let eapms: Vec<u32> = builds.iter().filter_map(|b| b.eapm).collect();
+        let games = apms.len() as u32;
+        let apm = average(&apms)?;
+        Some((apm, average(&eapms), games))
+    }
+}
+
+fn average(values: &[u32]) -> Option<u32> {
+    if values.is_empty() {
+        return None;
+    }
+    let sum: u64 = values.iter().map(|v| u64::from(*v)).sum();
+    Some((sum as f64 / values.len() as f64).round() as u32)
+}
+
+pub struct BuildOrderStore {
+    path: PathBuf,
+}
+
+impl BuildOrderStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn load(&self) -> Result<BuildOrderLog> {
+        match fs::read(&self.path) {
+            Ok(bytes) => {
+                let mut log: BuildOrderLog = serde_json::from_slice(&bytes)
+                    .with_context(|| format!("deserialize build orders {}", self.path.display()))?;
+                log.migrate_legacy();
+                Ok(log)
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BuildOrderLog::default()),
+            Err(err) => {
+                Err(anyhow!(err).context(format!("read build orders {}", self.path.display())))
+            }
+        }
+    }
+
+    pub fn save(&self, log: &BuildOrderLog) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create build order directory {}", parent.display()))?;
+        }
+        let data = serde_json::to_vec_pretty(log).context("serialize build orders")?;
+        fs::write(&self.path, data)
+            .with_context(|| format!("write build orders {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_supply_for_zerg_opening() {
+        // Four drones, pool (drone consumed), two more drones, then lings.
+        let frames = |secs: u32| (secs * 1000).div_ceil(FRAME_MS as u32);
+        let actions = vec![
+            (frames(1), CMD_UNIT_MORPH, 41),
+            (frames(12), CMD_UNIT_MORPH, 41),
+            (frames(25), CMD_UNIT_MORPH, 41),
+            (frames(38), CMD_UNIT_MORPH, 41),
+            (frames(40), 0x14, 0),
+            (frames(55), CMD_BUILD, 142),
+            (frames(60), CMD_UNIT_MORPH, 41),
+            (frames(70), CMD_UNIT_MORPH, 41),
+            (frames(110), CMD_UNIT_MORPH, 37),
+        ];
+        let steps = build_order_from_actions(Some("Zerg"), actions);
+        assert_eq!(steps.len(), 8);
+        assert_eq!(steps[4].unit, "Spawning Pool");
+        assert_eq!((steps[4].supply, steps[4].secs), (8, 55));
+        assert_eq!(steps[7].supply, 9);
+        assert_eq!(opening_label(&steps, 3), "8 Spawning Pool, 9 Zergling");
+    }
+
+    #[test]
+    fn openings_filter_by_matchup_newest_first() {
+        let build = |ts: u64, opp_race: &str| PlayerBuild {
+            match_id: String::new(),
+            player: "Bob".to_string(),
+            race: Some("Protoss".to_string()),
+            opponent: "Alice".to_string(),
+            opponent_race: Some(opp_race.to_string()),
+            timestamp: ts,
+            map: None,
+            steps: vec![BuildStep {
+                secs: 20,
+                supply: 8,
+                unit: "Pylon".to_string(),
+            }],
+            apm: Some(200 + ts as u32),
+            eapm: None,
+        };
+        let mut log = BuildOrderLog::default();
+        log.record(build(1, "Terran"));
+        log.record(build(3, "Terran"));
+        log.record(build(2, "Zerg"));
+        log.record(build(3, "Terran"));
+        let vt = log.openings("bob", Some("Protoss"), Some("Terran"), OPENINGS_SHOWN);
+        assert_eq!(
+            vt.iter().map(|b| b.timestamp).collect::<Vec<_>>(),
+            vec![3, 1]
+        );
+        assert!(log.dirty);
+        assert_eq!(log.average_apm("Bob"), Some((202, None, 3)));
+    }
+
+    #[test]
+    fn same_game_from_two_sources_is_logged_once() {
+        use crate::replay_io::ReplayPlayerSummary;
+        use crate::test_support::replay_player;
+        let player = |id: u8, name: &str| ReplayPlayerSummary {
+            apm: Some(150),
+            ..replay_player(id, name, Some("Terran"), id + 1)
+        };
+        let summary = ReplaySummary {
+            played_at: Some(1_000),
+            players: vec![player(0, "Alice"), player(1, "Bob")],
+            ..ReplaySummary::default()
+        };
+        let mut log = BuildOrderLog::default();
+        // LastReplay's approximate end time, then the download's start time.
+        for build in builds_from_summary(&summary, 1_600) {
+            log.record(build);
+        }
+        for build in builds_from_summary(&summary, 1_000) {
+            log.record(build);
+        }
+        assert_eq!(log.matches.len(), 1);
+        assert_eq!(log.average_apm("bob"), Some((150, None, 1)));
+    }
+}
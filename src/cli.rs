@@ -0,0 +1,415 @@
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+
+use anyhow::{bail, Context, Result};
+
+use crate::backfill;
+use crate::cache::CacheReader;
+use crate::config::Config;
+use crate::history::{FileHistorySource, HistoryService};
+use crate::profile_history::{ProfileHistoryKey, ProfileHistoryService};
+use crate::replay_download::{
+    DownloadFilterInput, DownloadJournal, ReplayDownloadEvent, ReplayDownloadJob,
+    ReplayDownloadRequest, ReplayStorage, RequestOrigin,
+};
+use crate::replay_filter::ResultFilter;
+use crate::replay_layout::{self, ReplayLayout};
+use crate::replay_manifest;
+use crate::transfer::{self, ExportFormat};
+
+const USAGE: &str = "usage:
+  bwtools                                   start the TUI
+  bwtools export [--format json|csv] <dir>  export opponent and profile history
+  bwtools import <file-or-dir>              merge an exported history into local history
+  bwtools backfill [--toon NAME[#GW]]... <dir>
+                                            rebuild history from saved replays of our profiles;
+                                            --toon is needed until there is profile history
+  bwtools download [options] NAME#GW        download ladder replays of a toon
+      --count N  --matchup PvZ  --alias NAME  --port PORT
+      --from YYYY-MM-DD  --to YYYY-MM-DD  --result win|loss  --opponent TOON
+      --map NAME  --duration MIN-MAX (minutes)  --min-rating MMR
+  bwtools relayout [--folder TEMPLATE] [--file TEMPLATE] [--toon NAME]... [--dry-run]
+                                            move library replays to a new folder and file layout
+      placeholders: {profile} {matchup} {date} {time} {p1} {r1} {p2} {r2}
+                    {map} {result} {duration} {rating} {gateway}
+  bwtools verify                            re-hash library replays and repair the manifest";
+
+// How far back the client cache is searched for the API port when none is
+// given. The TUI only trusts the last few seconds; a one-off download will
+// take the newest port seen today.
+const PORT_SCAN_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+pub enum Command {
+    Export {
+        dir: PathBuf,
+        format: ExportFormat,
+    },
+    Import {
+        path: PathBuf,
+    },
+    Backfill {
+        dir: PathBuf,
+        toons: Vec<String>,
+    },
+    Download {
+        request: ReplayDownloadRequest,
+        port: Option<u16>,
+    },
+    Relayout {
+        folder: Option<String>,
+        file: Option<String>,
+        toons: Vec<String>,
+        dry_run: bool,
+    },
+    Verify,
+    Help,
+}
+
+pub fn parse(args: &[String]) -> Result<Option<Command>> {
+    let Some((name, rest)) = args.split_first() else {
+        return Ok(None);
+    };
+    let command = match name.as_str() {
+        "export" => {
+            let mut format = ExportFormat::Json;
+            let mut dir: Option<PathBuf> = None;
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--format" => {
+                        let raw = iter.next().context("--format needs a value")?;
+                        format = ExportFormat::parse(raw)
+                            .with_context(|| format!("unknown export format {raw}"))?;
+                    }
+                    other if dir.is_none() => dir = Some(PathBuf::from(other)),
+                    other => bail!("unexpected argument {other}\n{USAGE}"),
+                }
+            }
+            Command::Export {
+                dir: dir.with_context(|| format!("export needs a directory\n{USAGE}"))?,
+                format,
+            }
+        }
+        "import" => match rest {
+            [path] => Command::Import {
+                path: PathBuf::from(path),
+            },
+            _ => bail!("import needs exactly one path\n{USAGE}"),
+        },
+        "backfill" => {
+            let mut toons = Vec::new();
+            let mut dir: Option<PathBuf> = None;
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--toon" => toons.push(iter.next().context("--toon needs a value")?.clone()),
+                    other if dir.is_none() => dir = Some(PathBuf::from(other)),
+                    other => bail!("unexpected argument {other}\n{USAGE}"),
+                }
+            }
+            Command::Backfill {
+                dir: dir.with_context(|| format!("backfill needs a directory\n{USAGE}"))?,
+                toons,
+            }
+        }
+        "download" => parse_download(rest)?,
+        "relayout" => {
+            let (mut folder, mut file, mut toons, mut dry_run) = (None, None, Vec::new(), false);
+            let mut iter = rest.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--folder" => {
+                        folder = Some(iter.next().context("--folder needs a value")?.clone())
+                    }
+                    "--file" => file = Some(iter.next().context("--file needs a value")?.clone()),
+                    "--toon" => toons.push(iter.next().context("--toon needs a value")?.clone()),
+                    "--dry-run" => dry_run = true,
+                    other => bail!("unexpected argument {other}\n{USAGE}"),
+                }
+            }
+            Command::Relayout {
+                folder,
+                file,
+                toons,
+                dry_run,
+            }
+        }
+        "verify" => match rest {
+            [] => Command::Verify,
+            _ => bail!("verify takes no arguments\n{USAGE}"),
+        },
+        "help" | "--help" | "-h" => Command::Help,
+        other => bail!("unknown command {other}\n{USAGE}"),
+    };
+    Ok(Some(command))
+}
+
+pub fn run(cfg: &Config, command: Command) -> Result<()> {
+    let history = HistoryService::new(FileHistorySource::new(cfg.opponent_history_path.clone()));
+    match command {
+        Command::Export { dir, format } => {
+            let hist = history.load()?;
+            let profiles = ProfileHistoryService::new(cfg.profile_history_path.clone())?
+                .with_retention(cfg.history_retention, cfg.history_archive_dir.clone());
+            transfer::export(&dir, format, &hist, &profiles)?;
+            println!("Exported history to {}", dir.display());
+        }
+        Command::Import { path } => {
+            let mut hist = history.load()?;
+            let mut profiles = ProfileHistoryService::new(cfg.profile_history_path.clone())?
+                .with_retention(cfg.history_retention, cfg.history_archive_dir.clone());
+            let report = transfer::import(&path, &mut hist, &mut profiles)?;
+            transfer::save_import(&history, &hist, &profiles)?;
+            println!("{report}");
+        }
+        Command::Backfill { dir, toons } => {
+            let mut hist = history.load()?;
+            let mut profiles = ProfileHistoryService::new(cfg.profile_history_path.clone())?
+                .with_retention(cfg.history_retention, cfg.history_archive_dir.clone());
+            let keys = resolve_toons(&toons, &profiles)?;
+            let report = backfill::backfill(cfg, &dir, &keys, &mut hist, &mut profiles)?;
+            history.save(&hist)?;
+            println!("{report}");
+        }
+        Command::Download { request, port } => {
+            let port = match port {
+                Some(port) => port,
+                None => CacheReader::new(cfg.cache_dir.clone())?
+                    .parse_for_port(PORT_SCAN_WINDOW_SECS)?
+                    .context("no API port in the client cache; start StarCraft or pass --port")?,
+            };
+            let storage = ReplayStorage::new(cfg.replay_library_root.clone());
+            let mut journal = DownloadJournal::create(&storage, request.clone())?;
+            let job =
+                ReplayDownloadJob::new(format!("http://127.0.0.1:{port}"), cfg.clone(), request)?;
+            let summary = job.run(&mut journal, &AtomicBool::new(false), |event| {
+                if let ReplayDownloadEvent::Replay { label, stage } = event
+                    && stage.is_done()
+                {
+                    println!("{label}  {}", stage.label());
+                }
+            });
+            journal.remove();
+            println!("{summary}");
+        }
+        Command::Relayout {
+            folder,
+            file,
+            toons,
+            dry_run,
+        } => {
+            let layout = ReplayLayout::new(
+                folder.as_deref().unwrap_or(cfg.replay_layout.folder()),
+                file.as_deref().unwrap_or(cfg.replay_layout.file()),
+            )?;
+            // Our own toons decide which side of each replay is p1.
+            let mut perspectives = toons;
+            if perspectives.is_empty() {
+                let profiles = ProfileHistoryService::new(cfg.profile_history_path.clone())?;
+                perspectives = profiles
+                    .profiles()
+                    .into_iter()
+                    .map(|(key, _)| key.name().to_string())
+                    .collect();
+            }
+            let storage = ReplayStorage::new(cfg.replay_library_root.clone());
+            let report = replay_layout::relayout(
+                cfg,
+                &storage,
+                &layout,
+                &perspectives,
+                dry_run,
+                |from, to| println!("{} -> {}", from.display(), to.display()),
+            )?;
+            println!("{report}");
+            if dry_run {
+                println!("Dry run: nothing was moved");
+            } else if folder.is_some() || file.is_some() {
+                println!(
+                    "Set replay_folder_template and replay_file_template in config.json to keep \
+                     saving replays in this layout"
+                );
+            }
+        }
+        Command::Verify => {
+            let storage = ReplayStorage::new(cfg.replay_library_root.clone());
+            let report = replay_manifest::verify(cfg, &storage)?;
+            println!("{report}");
+        }
+        Command::Help => println!("{USAGE}"),
+    }
+    Ok(())
+}
+
+fn parse_download(args: &[String]) -> Result<Command> {
+    let mut filters = DownloadFilterInput::default();
+    let mut toon: Option<ProfileHistoryKey> = None;
+    let mut matchup = None;
+    let mut alias = None;
+    let mut limit = 5;
+    let mut port = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with("--") {
+            if toon.is_some() {
+                bail!("unexpected argument {arg}\n{USAGE}");
+            }
+            toon = Some(
+                ProfileHistoryKey::from_storage_key(arg)
+                    .with_context(|| format!("expected NAME#GW, got {arg}"))?,
+            );
+            continue;
+        }
+        let value = iter
+            .next()
+            .with_context(|| format!("{arg} needs a value"))?
+            .clone();
+        match arg.as_str() {
+            "--count" => {
+                limit = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .with_context(|| format!("invalid count {value}"))?;
+            }
+            "--matchup" => matchup = Some(value),
+            "--alias" => alias = Some(value),
+            "--port" => {
+                port = Some(
+                    value
+                        .parse::<u16>()
+                        .with_context(|| format!("invalid port {value}"))?,
+                )
+            }
+            "--from" => filters.from = value,
+            "--to" => filters.to = value,
+            "--result" => {
+                filters.result = ResultFilter::parse(&value)
+                    .with_context(|| format!("invalid result {value}, expected win or loss"))?;
+            }
+            "--opponent" => filters.opponent = value,
+            "--map" => filters.map = value,
+            "--duration" => filters.duration = value,
+            "--min-rating" => filters.min_rating = value,
+            other => bail!("unknown option {other}\n{USAGE}"),
+        }
+    }
+    let toon = toon.with_context(|| format!("download needs a toon\n{USAGE}"))?;
+    Ok(Command::Download {
+        request: ReplayDownloadRequest {
+            toon: toon.name().to_string(),
+            gateway: toon.gateway(),
+            matchup,
+            limit,
+            alias,
+            filter: filters.parse()?,
+            origin: RequestOrigin::Manual,
+        },
+        port,
+    })
+}
+
+// `NAME#GW` is used as given; a bare name picks up the gateways it already has
+// profile history on. With no toons at all every known profile is used.
+fn resolve_toons(
+    toons: &[String],
+    profiles: &ProfileHistoryService,
+) -> Result<Vec<ProfileHistoryKey>> {
+    let known: Vec<ProfileHistoryKey> = profiles
+        .profiles()
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    if toons.is_empty() {
+        if known.is_empty() {
+            bail!(
+                "no profile history yet to pick toons from; name them with --toon NAME#GW\n  \
+                 bwtools backfill --toon NAME#GW [--toon NAME#GW]... <dir>"
+            );
+        }
+        return Ok(known);
+    }
+    let mut keys = Vec::new();
+    for toon in toons {
+        if let Some(key) = ProfileHistoryKey::from_storage_key(toon) {
+            keys.push(key);
+            continue;
+        }
+        let matching: Vec<_> = known
+            .iter()
+            .filter(|key| key.name().eq_ignore_ascii_case(toon))
+            .cloned()
+            .collect();
+        if matching.is_empty() {
+            bail!("unknown gateway for {toon}; use --toon {toon}#GW");
+        }
+        keys.extend(matching);
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_export_with_format() {
+        let cmd = parse(&args(&["export", "--format", "csv", "out"])).expect("parse");
+        match cmd {
+            Some(Command::Export { dir, format }) => {
+                assert_eq!(dir, PathBuf::from("out"));
+                assert_eq!(format, ExportFormat::Csv);
+            }
+            _ => panic!("expected export command"),
+        }
+        assert!(parse(&[]).expect("no args").is_none());
+        assert!(parse(&args(&["import"])).is_err());
+
+        match parse(&args(&["backfill", "--toon", "me#10", "replays"])).expect("parse") {
+            Some(Command::Backfill { dir, toons }) => {
+                assert_eq!(dir, PathBuf::from("replays"));
+                assert_eq!(toons, vec!["me#10".to_string()]);
+            }
+            _ => panic!("expected backfill command"),
+        }
+        assert!(parse(&args(&["backfill", "--toon"])).is_err());
+
+        let download = args(&[
+            "download", "--count", "20", "--result", "loss", "--map", "Polypoid", "me#30",
+        ]);
+        match parse(&download).expect("parse") {
+            Some(Command::Download { request, port }) => {
+                assert_eq!((request.toon.as_str(), request.gateway), ("me", 30));
+                assert_eq!(request.limit, 20);
+                assert_eq!(request.filter.result, ResultFilter::Loss);
+                assert_eq!(request.filter.map.as_deref(), Some("Polypoid"));
+                assert_eq!(port, None);
+            }
+            _ => panic!("expected download command"),
+        }
+        assert!(parse(&args(&["download", "--from", "soon", "me#30"])).is_err());
+        assert!(parse(&args(&["download", "me"])).is_err());
+
+        match parse(&args(&["relayout", "--file", "{date}_{p2}", "--dry-run"])).expect("parse") {
+            Some(Command::Relayout {
+                folder,
+                file,
+                dry_run,
+                ..
+            }) => {
+                assert_eq!((folder, file.as_deref()), (None, Some("{date}_{p2}")));
+                assert!(dry_run);
+            }
+            _ => panic!("expected relayout command"),
+        }
+        assert!(matches!(
+            parse(&args(&["verify"])),
+            Ok(Some(Command::Verify))
+        ));
+        assert!(parse(&args(&["verify", "extra"])).is_err());
+    }
+}
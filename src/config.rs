@@ -1,8 +1,16 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::profile_history::{HistoryRetention, StatsWindow};
+use crate::replay_layout::ReplayLayout;
+use crate::session::SessionStart;
+
 const DEFAULT_USER: &str = "default";
+const CONFIG_FILE: &str = "config.json";
 
 #[derive(Clone)]
 pub struct Config {
@@ -25,6 +33,54 @@ pub struct Config {
     pub rating_retry_max: u8,
     pub rating_retry_interval: Duration,
     pub replay_library_root: PathBuf,
+    pub history_retention: HistoryRetention,
+    pub history_archive_dir: PathBuf,
+    pub stats_window: StatsWindow,
+    pub session_start: SessionStart,
+    pub session_idle_gap: Duration,
+    pub session_history_path: PathBuf,
+    pub session_output_enabled: bool,
+    pub session_output_path: PathBuf,
+    pub build_orders_path: PathBuf,
+    pub archive_replays: bool,
+    pub replay_index_path: PathBuf,
+    pub library_scan_interval: Duration,
+    // Replays fetched at once by a download job.
+    pub download_workers: usize,
+    // Where saved replays go inside the library.
+    pub replay_layout: ReplayLayout,
+    pub watch_list_path: PathBuf,
+    // How often each watched player's replays are checked while the API is up.
+    pub watch_interval: Duration,
+    // Download a detected opponent's recent replays into the scouting folder.
+    pub scout_opponents: bool,
+    pub scout_replays: usize,
+    // An opponent is not scouted again within this long.
+    pub scout_cooldown: Duration,
+}
+
+// Optional overrides read from `config.json` next to the executable. Every key
+// is optional; anything missing keeps its built-in default, and unknown keys
+// are logged and ignored.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    history_retention: Option<HistoryRetention>,
+    history_archive_dir: Option<PathBuf>,
+    stats_window: Option<StatsWindow>,
+    session_start: Option<SessionStart>,
+    session_idle_gap_mins: Option<u64>,
+    session_output_enabled: Option<bool>,
+    archive_replays: Option<bool>,
+    download_workers: Option<usize>,
+    replay_folder_template: Option<String>,
+    replay_file_template: Option<String>,
+    watch_interval_mins: Option<u64>,
+    scout_opponents: Option<bool>,
+    scout_replays: Option<usize>,
+    scout_cooldown_hours: Option<u64>,
+    #[serde(flatten)]
+    unknown: std::collections::BTreeMap<String, serde_json::Value>,
 }
 
 impl Default for Config {
@@ -49,10 +105,111 @@ impl Default for Config {
             rating_retry_max: 3,
             rating_retry_interval: Duration::from_millis(500),
             replay_library_root: default_replay_library_root(),
+            history_retention: HistoryRetention::default(),
+            history_archive_dir: default_history_archive_dir(),
+            stats_window: StatsWindow::default(),
+            session_start: SessionStart::default(),
+            session_idle_gap: Duration::from_secs(30 * 60),
+            session_history_path: default_session_history_path(),
+            build_orders_path: default_build_orders_path(),
+            session_output_enabled: true,
+            session_output_path: default_session_output_path(),
+            archive_replays: true,
+            replay_index_path: default_replay_index_path(),
+            library_scan_interval: Duration::from_secs(60),
+            download_workers: 4,
+            replay_layout: ReplayLayout::default(),
+            watch_list_path: default_watch_list_path(),
+            watch_interval: Duration::from_secs(10 * 60),
+            scout_opponents: false,
+            scout_replays: 5,
+            scout_cooldown: Duration::from_secs(12 * 60 * 60),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Self {
+        let path = bundle_root().join(CONFIG_FILE);
+        let mut cfg = Self::default();
+        match read_config_file(&path) {
+            Ok(Some(file)) => cfg.apply(file),
+            Ok(None) => {}
+            Err(err) => {
+                tracing::warn!(error = ?err, "ignoring invalid config file; using defaults");
+            }
+        }
+        cfg
+    }
+
+    fn apply(&mut self, file: ConfigFile) {
+        for key in file.unknown.keys() {
+            tracing::warn!(key = %key, "ignoring unknown config key");
+        }
+        if let Some(retention) = file.history_retention {
+            self.history_retention = retention;
+        }
+        if let Some(dir) = file.history_archive_dir {
+            self.history_archive_dir = dir;
+        }
+        if let Some(window) = file.stats_window {
+            self.stats_window = window;
+        }
+        if let Some(start) = file.session_start {
+            self.session_start = start;
+        }
+        if let Some(mins) = file.session_idle_gap_mins {
+            self.session_idle_gap = Duration::from_secs(mins * 60);
+        }
+        if let Some(enabled) = file.session_output_enabled {
+            self.session_output_enabled = enabled;
+        }
+        if let Some(enabled) = file.archive_replays {
+            self.archive_replays = enabled;
+        }
+        if let Some(workers) = file.download_workers {
+            self.download_workers = workers.clamp(1, 16);
+        }
+        if let Some(mins) = file.watch_interval_mins {
+            self.watch_interval = Duration::from_secs(mins.max(1) * 60);
+        }
+        if let Some(enabled) = file.scout_opponents {
+            self.scout_opponents = enabled;
+        }
+        if let Some(count) = file.scout_replays {
+            self.scout_replays = count.clamp(1, 50);
+        }
+        if let Some(hours) = file.scout_cooldown_hours {
+            self.scout_cooldown = Duration::from_secs(hours * 60 * 60);
+        }
+        if file.replay_folder_template.is_some() || file.replay_file_template.is_some() {
+            let folder = file
+                .replay_folder_template
+                .unwrap_or_else(|| self.replay_layout.folder().to_string());
+            let file = file
+                .replay_file_template
+                .unwrap_or_else(|| self.replay_layout.file().to_string());
+            match ReplayLayout::new(&folder, &file) {
+                Ok(layout) => self.replay_layout = layout,
+                Err(err) => {
+                    tracing::warn!(error = %err, "ignoring invalid replay layout templates");
+                }
+            }
         }
     }
 }
 
+fn read_config_file(path: &Path) -> Result<Option<ConfigFile>> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("read config {}", path.display())),
+    };
+    let file = serde_json::from_slice(&bytes)
+        .with_context(|| format!("deserialize config {}", path.display()))?;
+    Ok(Some(file))
+}
+
 fn default_cache_dir() -> PathBuf {
     if cfg!(target_os = "windows") {
         return windows_user_profile_dir()
@@ -93,6 +250,30 @@ fn default_opponent_output_path() -> PathBuf {
     bundle_root().join("overlay").join("opponent_info.txt")
 }
 
+fn default_session_history_path() -> PathBuf {
+    bundle_root().join("history").join("sessions.json")
+}
+
+fn default_build_orders_path() -> PathBuf {
+    bundle_root().join("history").join("build_orders.json")
+}
+
+fn default_replay_index_path() -> PathBuf {
+    bundle_root().join("history").join("replay_index.json")
+}
+
+fn default_watch_list_path() -> PathBuf {
+    bundle_root().join("history").join("watch_list.json")
+}
+
+fn default_session_output_path() -> PathBuf {
+    bundle_root().join("overlay").join("session.txt")
+}
+
+fn default_history_archive_dir() -> PathBuf {
+    bundle_root().join("history").join("archive")
+}
+
 fn default_player_list_path() -> PathBuf {
     bundle_root().join("player_list.json")
 }
@@ -158,6 +339,24 @@ fn wine_replay_dir() -> PathBuf {
 mod tests {
     use super::*;
 
+    #[test]
+    fn config_file_overrides_only_given_keys() {
+        let file: ConfigFile =
+            serde_json::from_str(r#"{"history_retention": {"max_age_days": 365}}"#)
+                .expect("parse config");
+        let mut cfg = Config::default();
+        cfg.apply(file);
+        assert_eq!(cfg.history_retention, HistoryRetention::MaxAgeDays(365));
+        assert_eq!(cfg.stats_window, StatsWindow::Last100);
+
+        let file: ConfigFile =
+            serde_json::from_str(r#"{"archive_replay": false, "download_workers": 2}"#)
+                .expect("unknown keys are ignored");
+        cfg.apply(file);
+        assert_eq!(cfg.download_workers, 2);
+        assert!(cfg.archive_replays);
+    }
+
     #[test]
     fn wine_user_root_uses_expected_layout() {
         let root = wine_user_root_from(PathBuf::from("/home/tester"), "sc_user");
@@ -1,8 +1,13 @@
 use crate::app::App;
 use crate::cache::CacheReader;
 use crate::config::Config;
-use crate::history::{derive_wl_and_race, FileHistorySource, HistoryService, OpponentRecord};
+use crate::form::FormSummary;
+use crate::history::{
+    derive_games, FileHistorySource, HistoryService, OpponentGame, OpponentRecord,
+};
+use crate::maps::MapStat;
 use crate::overlay::{OverlayError, OverlayService};
+use crate::profile_history::StatsWindow;
 use thiserror::Error;
 
 pub struct DetectionService;
@@ -124,6 +129,8 @@ struct OpponentOutcome {
     toons: Vec<(String, u16, u32)>,
     race: Option<String>,
     matchups: Vec<String>,
+    form: Option<FormSummary>,
+    maps: Vec<MapStat>,
     last_identity: Option<(String, u16)>,
     history_update: OpponentHistoryUpdate,
 }
@@ -142,6 +149,8 @@ impl OpponentOutcome {
             toons,
             race,
             matchups,
+            form,
+            maps,
             last_identity,
             history_update,
         } = self;
@@ -155,8 +164,12 @@ impl OpponentOutcome {
         app.opponent.toons_data = toons;
         app.opponent.race = race;
         app.opponent.matchups = matchups;
+        app.opponent.form = form;
+        app.opponent.maps = maps;
 
         app.overlays.opponent_waiting = false;
+        app.session
+            .touch(crate::session::now_secs(), app.self_profile.rating);
 
         history_update.apply(app, cfg, history);
     }
@@ -360,21 +373,22 @@ fn build_opponent_outcome(
         }
     };
 
-    let (race, matchups) = match api.get_scr_profile(opp_name, opp_gw) {
+    let (race, matchups, form, maps) = match api.get_scr_profile(opp_name, opp_gw) {
         Ok(profile) => {
-            let (mr, lines, _results, _, _) =
-                api.profile_stats_last100(&profile, opp_name, None, None, None);
+            let stats =
+                api.profile_stats(&profile, opp_name, None, None, None, StatsWindow::Last100);
             tracing::debug!(
                 opponent = %opp_name,
                 gateway = opp_gw,
-                main_race = ?mr,
+                main_race = ?stats.main_race,
                 "opponent profile fetched"
             );
-            (mr, lines)
+            let form = FormSummary::from_results(&stats.results);
+            (stats.main_race, stats.matchups, form, stats.maps)
         }
         Err(err) => {
             tracing::error!(error = %err, "opponent profile fetch failed");
-            (None, Vec::new())
+            (None, Vec::new(), None, Vec::new())
         }
     };
 
@@ -391,6 +405,8 @@ fn build_opponent_outcome(
         toons,
         race,
         matchups,
+        form,
+        maps,
         last_identity: Some((opp_name.to_string(), opp_gw)),
         history_update,
     })
@@ -426,9 +442,7 @@ fn build_history_update(
     let key = crate::race::lower_key(opp_name);
     let existing = app.opponent.history.get(&key);
 
-    let mut wins = existing.map(|r| r.wins).unwrap_or(0);
-    let mut losses = existing.map(|r| r.losses).unwrap_or(0);
-    let mut last_match_ts = existing.and_then(|r| r.last_match_ts);
+    let mut games: Vec<OpponentGame> = Vec::new();
     let mut race = existing.and_then(|r| r.race.clone());
     if let Some(ref hint) = race_hint
         && crate::race::should_replace(race.as_deref(), hint)
@@ -437,18 +451,20 @@ fn build_history_update(
     }
     let previous_rating = existing.and_then(|r| r.current_rating);
 
-    let needs_record = existing.map(|r| r.wins + r.losses == 0).unwrap_or(true);
-    if needs_record
+    // Earlier games are only backfilled from our recent results when the
+    // opponent is first recorded; after that every game is logged as played.
+    let needs_games = existing.is_none();
+    if needs_games
         && let (Some(self_name), Some(self_gw)) = (&app.self_profile.name, app.self_profile.gateway)
     {
         match api.get_scr_profile(self_name, self_gw) {
             Ok(profile) => {
-                let (w, l, ts, race_opt) = derive_wl_and_race(&profile, self_name, opp_name);
-                wins = w;
-                losses = l;
-                last_match_ts = ts;
+                games = derive_games(&profile, self_name, opp_name);
                 if race.is_none() {
-                    race = race_opt.as_deref().map(crate::race::normalize_label);
+                    race = games
+                        .first()
+                        .and_then(|g| g.opponent_race.as_deref())
+                        .map(crate::race::normalize_label);
                 }
             }
             Err(err) => tracing::error!(
@@ -463,9 +479,7 @@ fn build_history_update(
         gateway: opp_gw,
         aurora_id,
         race,
-        last_match_ts,
-        wins,
-        losses,
+        games,
         current_rating: rating,
         previous_rating,
     }
@@ -476,9 +490,7 @@ struct OpponentHistoryUpdate {
     gateway: u16,
     aurora_id: Option<u32>,
     race: Option<String>,
-    last_match_ts: Option<u64>,
-    wins: u32,
-    losses: u32,
+    games: Vec<OpponentGame>,
     current_rating: Option<u32>,
     previous_rating: Option<u32>,
 }
@@ -501,16 +513,14 @@ impl OpponentHistoryUpdate {
         if let Some(aurora_id) = self.aurora_id {
             entry.aurora_id = Some(aurora_id);
         }
-        if let Some(ts) = self.last_match_ts {
-            entry.last_match_ts = Some(ts);
-        }
         if let Some(new_race) = self.race.as_ref() {
             entry.apply_race_observation(new_race);
         }
         entry.previous_rating = self.previous_rating;
         entry.current_rating = self.current_rating;
-        entry.wins = self.wins;
-        entry.losses = self.losses;
+        for game in self.games {
+            entry.merge_game(game);
+        }
 
         if let Err(err) = OverlayService::write_opponent(cfg, app) {
             tracing::error!(error = %err, "failed to update opponent overlay");
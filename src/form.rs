@@ -0,0 +1,128 @@
+// Number of recent games used for per-matchup form.
+pub const FORM_WINDOW: usize = 20;
+// Number of results shown in the compact W/L strip.
+pub const STRIP_LEN: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormEntry {
+    pub win: bool,
+    pub opponent_race: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchupForm {
+    pub opponent_race: String,
+    pub wins: u32,
+    pub games: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormSummary {
+    pub current_streak: Option<(bool, u32)>,
+    pub longest_win_streak: u32,
+    pub longest_loss_streak: u32,
+    pub strip: Vec<bool>,
+    pub matchups: Vec<MatchupForm>,
+}
+
+impl FormSummary {
+    // `results` are ordered newest first, as the stats pipeline produces them.
+    pub fn from_results(results: &[FormEntry]) -> Option<Self> {
+        let first = results.first()?;
+        let current_len = results.iter().take_while(|r| r.win == first.win).count() as u32;
+
+        let mut longest_win_streak = 0;
+        let mut longest_loss_streak = 0;
+        let mut run: Option<(bool, u32)> = None;
+        for entry in results {
+            let len = match run {
+                Some((win, len)) if win == entry.win => len + 1,
+                _ => 1,
+            };
+            run = Some((entry.win, len));
+            if entry.win {
+                longest_win_streak = longest_win_streak.max(len);
+            } else {
+                longest_loss_streak = longest_loss_streak.max(len);
+            }
+        }
+
+        let mut matchups: Vec<MatchupForm> = Vec::new();
+        for entry in results.iter().take(FORM_WINDOW) {
+            let race = entry
+                .opponent_race
+                .as_deref()
+                .map(crate::race::initial)
+                .unwrap_or("?")
+                .to_string();
+            let idx = match matchups.iter().position(|m| m.opponent_race == race) {
+                Some(idx) => idx,
+                None => {
+                    matchups.push(MatchupForm {
+                        opponent_race: race,
+                        wins: 0,
+                        games: 0,
+                    });
+                    matchups.len() - 1
+                }
+            };
+            matchups[idx].games += 1;
+            if entry.win {
+                matchups[idx].wins += 1;
+            }
+        }
+        matchups.sort_by(|a, b| a.opponent_race.cmp(&b.opponent_race));
+
+        Some(Self {
+            current_streak: Some((first.win, current_len)),
+            longest_win_streak,
+            longest_loss_streak,
+            strip: results.iter().take(STRIP_LEN).map(|r| r.win).collect(),
+            matchups,
+        })
+    }
+
+    pub fn streak_label(&self) -> String {
+        match self.current_streak {
+            Some((true, len)) => format!("W{len}"),
+            Some((false, len)) => format!("L{len}"),
+            None => "-".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(win: bool, race: &str) -> FormEntry {
+        FormEntry {
+            win,
+            opponent_race: Some(race.to_string()),
+        }
+    }
+
+    #[test]
+    fn summarizes_streaks_strip_and_matchups() {
+        let results = vec![
+            entry(false, "Zerg"),
+            entry(false, "Terran"),
+            entry(true, "Zerg"),
+            entry(true, "Zerg"),
+            entry(true, "Protoss"),
+            entry(false, "Zerg"),
+        ];
+        let form = FormSummary::from_results(&results).expect("form");
+        assert_eq!(form.streak_label(), "L2");
+        assert_eq!(form.longest_win_streak, 3);
+        assert_eq!(form.longest_loss_streak, 2);
+        assert_eq!(form.strip, vec![false, false, true, true, true, false]);
+        let vz = form
+            .matchups
+            .iter()
+            .find(|m| m.opponent_race == "Z")
+            .expect("vZ form");
+        assert_eq!((vz.wins, vz.games), (2, 4));
+        assert!(FormSummary::from_results(&[]).is_none());
+    }
+}
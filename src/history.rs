@@ -1,6 +1,76 @@
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::maps::normalize_map_name;
+use crate::profile_history::MatchOutcome;
+use crate::staged_write::StagedWrite;
+
+// A replay-derived game is matched to the API game it describes when the API
+// start time falls within this window before the replay was written.
+pub(crate) const GAME_MATCH_WINDOW_SECS: u64 = 3 * 3600;
+pub(crate) const GAME_MATCH_SLACK_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OpponentGame {
+    pub timestamp: u64,
+    pub self_race: Option<String>,
+    pub opponent_race: Option<String>,
+    pub result: MatchOutcome,
+    #[serde(default)]
+    pub duration_secs: Option<u32>,
+    #[serde(default)]
+    pub map: Option<String>,
+    #[serde(default)]
+    pub rating_before: Option<u32>,
+    #[serde(default)]
+    pub rating_after: Option<u32>,
+    #[serde(default)]
+    pub replay_path: Option<String>,
+    // Set for games recorded from LastReplay.rep before the API reported them.
+    #[serde(default)]
+    pub provisional: bool,
+}
+
+impl OpponentGame {
+    fn fill_missing_from(&mut self, other: &OpponentGame) {
+        if self.self_race.is_none() {
+            self.self_race = other.self_race.clone();
+        }
+        if self.opponent_race.is_none() {
+            self.opponent_race = other.opponent_race.clone();
+        }
+        if self.duration_secs.is_none() {
+            self.duration_secs = other.duration_secs;
+        }
+        if self.map.is_none() {
+            self.map = other.map.clone();
+        }
+        if self.rating_before.is_none() {
+            self.rating_before = other.rating_before;
+        }
+        if self.rating_after.is_none() {
+            self.rating_after = other.rating_after;
+        }
+        if self.replay_path.is_none() {
+            self.replay_path = other.replay_path.clone();
+        }
+    }
+
+    pub fn matchup_label(&self) -> String {
+        format!(
+            "{}v{}",
+            self.self_race
+                .as_deref()
+                .map(crate::race::initial)
+                .unwrap_or("?"),
+            self.opponent_race
+                .as_deref()
+                .map(crate::race::initial)
+                .unwrap_or("?")
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct OpponentRecord {
     pub name: String,
@@ -13,6 +83,18 @@ pub struct OpponentRecord {
     pub wins: u32,
     pub losses: u32,
     pub last_match_ts: Option<u64>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub games: Vec<OpponentGame>,
+    // Wins and losses from before games were logged one by one. `wins` and
+    // `losses` are these plus the counts in `games`.
+    #[serde(default)]
+    pub legacy_wins: u32,
+    #[serde(default)]
+    pub legacy_losses: u32,
 }
 
 impl OpponentRecord {
@@ -37,10 +119,112 @@ impl OpponentRecord {
             self.race = Some(crate::race::normalize_label(race));
         }
     }
+
+    // Sets our post-game rating on the newest game near `approx_timestamp` that
+    // does not have one yet.
+    pub fn set_rating_after(&mut self, approx_timestamp: u64, rating: u32) -> bool {
+        let Some(game) = self
+            .games
+            .iter_mut()
+            .filter(|g| {
+                g.rating_after.is_none()
+                    && g.timestamp <= approx_timestamp + GAME_MATCH_SLACK_SECS
+                    && approx_timestamp.saturating_sub(g.timestamp) <= GAME_MATCH_WINDOW_SECS
+            })
+            .max_by_key(|g| g.timestamp)
+        else {
+            return false;
+        };
+        game.rating_after = Some(rating);
+        true
+    }
+
+    pub fn merge_game(&mut self, incoming: OpponentGame) {
+        if incoming.timestamp == 0 {
+            return;
+        }
+        // Records kept before the game log only have totals; those become the
+        // baseline the log counts on top of.
+        if self.games.is_empty() {
+            self.legacy_wins = self.wins;
+            self.legacy_losses = self.losses;
+        }
+        if let Some(existing) = self
+            .games
+            .iter_mut()
+            .find(|g| g.timestamp == incoming.timestamp)
+        {
+            if existing.provisional && !incoming.provisional {
+                existing.result = incoming.result;
+                existing.provisional = false;
+            }
+            existing.fill_missing_from(&incoming);
+        } else if let Some(existing) = self
+            .games
+            .iter_mut()
+            .find(|g| g.provisional != incoming.provisional && same_game(g, &incoming))
+        {
+            if existing.provisional {
+                let mut confirmed = incoming;
+                confirmed.fill_missing_from(existing);
+                *existing = confirmed;
+            } else {
+                existing.fill_missing_from(&incoming);
+            }
+        } else {
+            self.games.push(incoming);
+        }
+
+        self.games.sort_by_key(|g| std::cmp::Reverse(g.timestamp));
+        self.recompute_totals();
+    }
+
+    // Wins and losses counted from the game log alone.
+    fn logged_totals(&self) -> (u32, u32) {
+        let wins = self.games.iter().filter(|g| g.result.is_win()).count() as u32;
+        let losses = self
+            .games
+            .iter()
+            .filter(|g| g.result == MatchOutcome::Loss)
+            .count() as u32;
+        (wins, losses)
+    }
+
+    // Folds whatever the totals hold beyond the game log into the baseline,
+    // for records that arrive with only totals (exports, older files).
+    fn settle_legacy(&mut self) {
+        let (wins, losses) = self.logged_totals();
+        self.legacy_wins = self.wins.saturating_sub(wins);
+        self.legacy_losses = self.losses.saturating_sub(losses);
+    }
+
+    fn recompute_totals(&mut self) {
+        let (wins, losses) = self.logged_totals();
+        self.wins = self.legacy_wins.saturating_add(wins);
+        self.losses = self.legacy_losses.saturating_add(losses);
+        self.last_match_ts = self
+            .last_match_ts
+            .max(self.games.first().map(|g| g.timestamp));
+    }
+
+    pub fn recent_games(&self, limit: usize) -> &[OpponentGame] {
+        &self.games[..self.games.len().min(limit)]
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let wanted = normalize_tag(tag);
+        self.tags.contains(&wanted)
+    }
 }
 
 pub type OpponentHistory = std::collections::HashMap<String, OpponentRecord>;
 
+fn same_game(a: &OpponentGame, b: &OpponentGame) -> bool {
+    let (api, replay) = if a.provisional { (b, a) } else { (a, b) };
+    api.timestamp <= replay.timestamp + GAME_MATCH_SLACK_SECS
+        && replay.timestamp.saturating_sub(api.timestamp) <= GAME_MATCH_WINDOW_SECS
+}
+
 pub fn aggregate_record_for_aurora_id(
     hist: &OpponentHistory,
     aurora_id: u32,
@@ -60,9 +244,209 @@ pub fn aggregate_record_for_aurora_id(
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpponentAnnotations {
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+}
+
+pub fn normalize_tag(raw: &str) -> String {
+    raw.trim().to_ascii_lowercase()
+}
+
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for part in input.split(',') {
+        let tag = normalize_tag(part);
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+// Notes and tags follow the player across toons: when the aurora id is known,
+// every record sharing it contributes, otherwise only the toon's own record.
+pub fn annotations_for(
+    hist: &OpponentHistory,
+    aurora_id: Option<u32>,
+    name: &str,
+) -> OpponentAnnotations {
+    let key = crate::race::lower_key(name);
+    let mut related: Vec<&OpponentRecord> = match aurora_id {
+        Some(id) => hist
+            .iter()
+            .filter(|(k, rec)| rec.aurora_id == Some(id) || **k == key)
+            .map(|(_, rec)| rec)
+            .collect(),
+        None => hist.get(&key).into_iter().collect(),
+    };
+    related.sort_by_key(|rec| std::cmp::Reverse(rec.last_match_ts.unwrap_or(0)));
+
+    let mut out = OpponentAnnotations::default();
+    for rec in related {
+        if out.notes.is_none() {
+            out.notes = rec.notes.clone().filter(|n| !n.trim().is_empty());
+        }
+        for tag in rec.tags.iter() {
+            if !out.tags.contains(tag) {
+                out.tags.push(tag.clone());
+            }
+        }
+    }
+    out
+}
+
+pub fn set_annotations(
+    hist: &mut OpponentHistory,
+    aurora_id: Option<u32>,
+    name: &str,
+    gateway: u16,
+    annotations: OpponentAnnotations,
+) {
+    let key = crate::race::lower_key(name);
+    let entry = hist
+        .entry(key.clone())
+        .or_insert_with(|| OpponentRecord::new(name, gateway));
+    if entry.aurora_id.is_none() {
+        entry.aurora_id = aurora_id;
+    }
+
+    let notes = annotations.notes.filter(|n| !n.trim().is_empty());
+    for (k, rec) in hist.iter_mut() {
+        let related = *k == key || (aurora_id.is_some() && rec.aurora_id == aurora_id);
+        if related {
+            rec.notes = notes.clone();
+            rec.tags = annotations.tags.clone();
+        }
+    }
+}
+
+pub fn records_with_tags<'a>(
+    hist: &'a OpponentHistory,
+    tags: &[String],
+) -> Vec<&'a OpponentRecord> {
+    let mut out: Vec<&OpponentRecord> = hist
+        .values()
+        .filter(|rec| tags.iter().all(|tag| rec.has_tag(tag)))
+        .collect();
+    out.sort_by(|a, b| {
+        b.last_match_ts.cmp(&a.last_match_ts).then_with(|| {
+            a.name
+                .to_ascii_lowercase()
+                .cmp(&b.name.to_ascii_lowercase())
+        })
+    });
+    out
+}
+
+#[derive(Debug, Default)]
+pub struct RecordMerge {
+    pub added: bool,
+    pub merged: bool,
+    pub conflicts: Vec<String>,
+}
+
+// Imported records join the local record sharing their aurora id, or failing
+// that the one with the same name. Game logs are unioned so games both sides
+// logged count once; W-L beyond the logs is summed.
+pub fn merge_imported_record(
+    hist: &mut OpponentHistory,
+    mut incoming: OpponentRecord,
+) -> RecordMerge {
+    incoming.settle_legacy();
+    let mut outcome = RecordMerge::default();
+    let key = crate::race::lower_key(&incoming.name);
+    let by_id = incoming.aurora_id.and_then(|id| {
+        hist.iter()
+            .filter(|(_, rec)| rec.aurora_id == Some(id))
+            .max_by_key(|(k, rec)| (**k == key, rec.last_match_ts.unwrap_or(0)))
+            .map(|(k, _)| k.clone())
+    });
+    let target_key = match (by_id, hist.get(&key)) {
+        (Some(found), _) => Some(found),
+        (None, Some(existing))
+            if existing.aurora_id.is_some()
+                && incoming.aurora_id.is_some()
+                && existing.aurora_id != incoming.aurora_id =>
+        {
+            outcome.conflicts.push(format!(
+                "{}: aurora id {} differs from imported {}; kept local record",
+                existing.name,
+                existing.aurora_id.unwrap_or_default(),
+                incoming.aurora_id.unwrap_or_default()
+            ));
+            return outcome;
+        }
+        (None, Some(_)) => Some(key.clone()),
+        (None, None) => None,
+    };
+
+    let Some(target_key) = target_key else {
+        hist.insert(key, incoming);
+        outcome.added = true;
+        return outcome;
+    };
+    let Some(existing) = hist.get_mut(&target_key) else {
+        return outcome;
+    };
+    outcome.merged = true;
+
+    existing.settle_legacy();
+    let local_ts = existing.last_match_ts;
+    for game in incoming.games.iter().cloned() {
+        existing.merge_game(game);
+    }
+    existing.legacy_wins = existing.legacy_wins.saturating_add(incoming.legacy_wins);
+    existing.legacy_losses = existing
+        .legacy_losses
+        .saturating_add(incoming.legacy_losses);
+    existing.last_match_ts = existing.last_match_ts.max(incoming.last_match_ts);
+    existing.recompute_totals();
+
+    if existing.aurora_id.is_none() {
+        existing.aurora_id = incoming.aurora_id;
+    }
+    match (existing.race.as_deref(), incoming.race.as_deref()) {
+        (None, Some(race)) => existing.race = Some(race.to_string()),
+        (Some(local), Some(imported)) if !local.eq_ignore_ascii_case(imported) => {
+            outcome.conflicts.push(format!(
+                "{}: race {} differs from imported {}; kept local",
+                existing.name, local, imported
+            ));
+        }
+        _ => {}
+    }
+    match (existing.notes.as_deref(), incoming.notes.as_deref()) {
+        (None, Some(notes)) => existing.notes = Some(notes.to_string()),
+        (Some(local), Some(imported)) if local.trim() != imported.trim() => {
+            outcome.conflicts.push(format!(
+                "{}: notes differ from imported; kept local",
+                existing.name
+            ));
+        }
+        _ => {}
+    }
+    for tag in incoming.tags {
+        if !existing.tags.contains(&tag) {
+            existing.tags.push(tag);
+        }
+    }
+    if incoming.last_match_ts > local_ts && incoming.current_rating.is_some() {
+        existing.current_rating = incoming.current_rating;
+        existing.previous_rating = incoming.previous_rating;
+    }
+    outcome
+}
+
 pub trait HistorySource {
     fn load(&self) -> Result<OpponentHistory>;
-    fn save(&self, hist: &OpponentHistory) -> Result<()>;
+    // Writes the history beside its file; committing puts it in place.
+    fn stage(&self, hist: &OpponentHistory) -> Result<StagedWrite>;
+
+    fn save(&self, hist: &OpponentHistory) -> Result<()> {
+        self.stage(hist)?.commit()
+    }
 }
 
 pub struct FileHistorySource {
@@ -89,16 +473,11 @@ impl HistorySource for FileHistorySource {
         }
     }
 
-    fn save(&self, hist: &OpponentHistory) -> Result<()> {
-        if let Some(parent) = self.path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("create history directory {}", parent.display()))?;
-        }
+    fn stage(&self, hist: &OpponentHistory) -> Result<StagedWrite> {
         let data =
             serde_json::to_vec_pretty(hist).context("serialize opponent history for saving")?;
-        std::fs::write(&self.path, data)
-            .with_context(|| format!("write opponent history {}", self.path.display()))?;
-        Ok(())
+        StagedWrite::new(&self.path, &data)
+            .with_context(|| format!("write opponent history {}", self.path.display()))
     }
 }
 
@@ -118,19 +497,20 @@ impl<S: HistorySource> HistoryService<S> {
     pub fn save(&self, hist: &OpponentHistory) -> Result<()> {
         self.source.save(hist)
     }
+
+    pub fn stage(&self, hist: &OpponentHistory) -> Result<StagedWrite> {
+        self.source.stage(hist)
+    }
 }
 
-// Derive win/loss vs a specific opponent from a self profile. Also returns the
-// latest match timestamp and the opponent race if available.
-pub fn derive_wl_and_race(
+// Collect the games against a specific opponent from a self profile's recent
+// results, newest first.
+pub fn derive_games(
     profile: &bw_web_api_rs::models::aurora_profile::ScrProfile,
     self_name: &str,
     opp_name: &str,
-) -> (u32, u32, Option<u64>, Option<String>) {
-    let mut wins: u32 = 0;
-    let mut losses: u32 = 0;
-    let mut last_ts: u64 = 0;
-    let mut last_race: Option<String> = None;
+) -> Vec<OpponentGame> {
+    let mut games: Vec<OpponentGame> = Vec::new();
 
     for g in profile.game_results.iter() {
         let players: Vec<&bw_web_api_rs::models::common::Player> = g
@@ -156,21 +536,184 @@ pub fn derive_wl_and_race(
             continue;
         }
 
-        let ts = g.create_time.parse::<u64>().unwrap_or(0);
-        let res = players[mi].result.to_ascii_lowercase();
-        if res == "win" {
-            wins = wins.saturating_add(1);
-        }
-        if res == "loss" {
-            losses = losses.saturating_add(1);
-        }
+        let result = match players[mi].result.to_ascii_lowercase().as_str() {
+            "win" => MatchOutcome::Win,
+            "loss" => MatchOutcome::Loss,
+            _ => continue,
+        };
+        games.push(OpponentGame {
+            timestamp: g.create_time.parse::<u64>().unwrap_or(0),
+            self_race: players[mi].attributes.race.clone(),
+            opponent_race: players[oi].attributes.race.clone(),
+            result,
+            duration_secs: None,
+            map: normalize_map_name(&g.attributes.map_name),
+            rating_before: None,
+            rating_after: None,
+            replay_path: None,
+            provisional: false,
+        });
+    }
+
+    games.sort_by_key(|g| std::cmp::Reverse(g.timestamp));
+    games
+}
 
-        if ts > last_ts {
-            last_ts = ts;
-            last_race = players[oi].attributes.race.clone();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, aurora_id: Option<u32>) -> OpponentRecord {
+        let mut rec = OpponentRecord::new(name, 10);
+        rec.aurora_id = aurora_id;
+        rec
+    }
+
+    fn game(timestamp: u64, result: MatchOutcome, provisional: bool) -> OpponentGame {
+        OpponentGame {
+            timestamp,
+            self_race: None,
+            opponent_race: None,
+            result,
+            duration_secs: None,
+            map: None,
+            rating_before: None,
+            rating_after: None,
+            replay_path: None,
+            provisional,
         }
     }
 
-    let last_ts_opt = if last_ts > 0 { Some(last_ts) } else { None };
-    (wins, losses, last_ts_opt, last_race)
+    #[test]
+    fn merge_game_dedups_and_recomputes_totals() {
+        let mut rec = record("Bob", None);
+        rec.merge_game(game(100, MatchOutcome::Win, false));
+        rec.merge_game(game(100, MatchOutcome::Win, false));
+        rec.merge_game(game(5_000, MatchOutcome::Loss, false));
+        rec.merge_game(game(9_000, MatchOutcome::SelfDodged, false));
+
+        assert_eq!(rec.games.len(), 3);
+        assert_eq!((rec.wins, rec.losses), (1, 1));
+        assert_eq!(rec.last_match_ts, Some(9_000));
+        assert_eq!(rec.games[0].timestamp, 9_000);
+    }
+
+    #[test]
+    fn legacy_totals_survive_the_first_logged_game() {
+        let mut rec: OpponentRecord = serde_json::from_str(
+            r#"{"name":"Bob","gateway":10,"race":"Zerg","current_rating":null,
+                "previous_rating":null,"wins":5,"losses":3,"last_match_ts":100}"#,
+        )
+        .expect("parse legacy record");
+        assert!(rec.games.is_empty());
+
+        rec.merge_game(game(5_000, MatchOutcome::Win, false));
+        assert_eq!((rec.legacy_wins, rec.legacy_losses), (5, 3));
+        assert_eq!((rec.wins, rec.losses), (6, 3));
+        rec.merge_game(game(6_000, MatchOutcome::Loss, false));
+        assert_eq!((rec.wins, rec.losses), (6, 4));
+        assert_eq!(rec.last_match_ts, Some(6_000));
+    }
+
+    #[test]
+    fn merge_game_confirms_provisional_replay_entry() {
+        let mut rec = record("Bob", None);
+        let mut from_replay = game(10_900, MatchOutcome::Win, true);
+        from_replay.duration_secs = Some(840);
+        from_replay.rating_before = Some(2000);
+        rec.merge_game(from_replay);
+
+        let mut from_api = game(10_000, MatchOutcome::Win, false);
+        from_api.map = Some("Polypoid".to_string());
+        rec.merge_game(from_api);
+
+        assert_eq!(rec.games.len(), 1);
+        let merged = &rec.games[0];
+        assert_eq!(merged.timestamp, 10_000);
+        assert!(!merged.provisional);
+        assert_eq!(merged.duration_secs, Some(840));
+        assert_eq!(merged.rating_before, Some(2000));
+        assert_eq!(merged.map.as_deref(), Some("Polypoid"));
+    }
+
+    #[test]
+    fn imports_sum_totals_and_count_shared_games_once() {
+        let mut hist = OpponentHistory::new();
+        let mut local = record("Bob", Some(7));
+        local.wins = 4;
+        local.losses = 2;
+        local.merge_game(game(5_000, MatchOutcome::Win, false));
+        hist.insert("bob".to_string(), local);
+
+        // A teammate's record of the same player under another toon: three
+        // totals-only games plus the logged game both sides played.
+        let mut teammate = record("BobSmurf", Some(7));
+        teammate.wins = 2;
+        teammate.losses = 1;
+        teammate.merge_game(game(5_000, MatchOutcome::Win, false));
+        let merge = merge_imported_record(&mut hist, teammate);
+        assert!(merge.merged);
+        let rec = &hist["bob"];
+        assert_eq!(rec.games.len(), 1);
+        assert_eq!((rec.legacy_wins, rec.legacy_losses), (6, 3));
+        assert_eq!((rec.wins, rec.losses), (7, 3));
+
+        hist.get_mut("bob")
+            .expect("record")
+            .merge_game(game(6_000, MatchOutcome::Loss, false));
+        assert_eq!((hist["bob"].wins, hist["bob"].losses), (7, 4));
+    }
+
+    #[test]
+    fn parse_tags_normalizes_and_dedups() {
+        assert_eq!(
+            parse_tags(" Rival, cheese ,rival,, "),
+            vec!["rival".to_string(), "cheese".to_string()]
+        );
+        assert!(parse_tags("").is_empty());
+    }
+
+    #[test]
+    fn annotations_follow_aurora_id_across_toons() {
+        let mut hist = OpponentHistory::new();
+        hist.insert("main".to_string(), record("Main", Some(7)));
+        hist.insert("smurf".to_string(), record("Smurf", Some(7)));
+        hist.insert("other".to_string(), record("Other", Some(8)));
+
+        set_annotations(
+            &mut hist,
+            Some(7),
+            "Main",
+            10,
+            OpponentAnnotations {
+                notes: Some("always 2-hatch muta".to_string()),
+                tags: vec!["rival".to_string()],
+            },
+        );
+
+        let smurf = annotations_for(&hist, Some(7), "Smurf");
+        assert_eq!(smurf.notes.as_deref(), Some("always 2-hatch muta"));
+        assert_eq!(smurf.tags, vec!["rival".to_string()]);
+        assert_eq!(
+            annotations_for(&hist, Some(8), "Other"),
+            OpponentAnnotations::default()
+        );
+    }
+
+    #[test]
+    fn records_with_tags_requires_every_tag() {
+        let mut hist = OpponentHistory::new();
+        let mut a = record("Alpha", None);
+        a.tags = vec!["rival".to_string(), "cheese".to_string()];
+        let mut b = record("Beta", None);
+        b.tags = vec!["rival".to_string()];
+        hist.insert("alpha".to_string(), a);
+        hist.insert("beta".to_string(), b);
+
+        let rivals = records_with_tags(&hist, &["Rival".to_string()]);
+        assert_eq!(rivals.len(), 2);
+        let both = records_with_tags(&hist, &["rival".to_string(), "cheese".to_string()]);
+        assert_eq!(both.len(), 1);
+        assert_eq!(both[0].name, "Alpha");
+    }
 }
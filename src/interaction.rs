@@ -6,6 +6,10 @@ pub enum Intent {
     ToggleDebug,
     ShowMain,
     ShowReplays,
+    ShowOpponents,
+    ShowLibrary,
+    EditOpponentNotes,
+    CycleStatsWindow,
     AdjustDebugScroll { delta: i32 },
     SetDebugScroll { value: i32 },
 }
@@ -42,6 +46,24 @@ impl Intent {
                 app.replay.focus = crate::app::ReplayFocus::Toon;
                 app.replay.last_error = None;
             }
+            Intent::ShowOpponents => {
+                app.view = View::Opponents;
+                app.opponent_list.scroll = 0;
+            }
+            Intent::ShowLibrary => {
+                app.view = View::Library;
+                app.library_view.message = None;
+            }
+            Intent::EditOpponentNotes => {
+                if app.view == View::Main {
+                    app.open_note_editor();
+                }
+            }
+            Intent::CycleStatsWindow => {
+                app.self_profile.stats_window = app.self_profile.stats_window.next();
+                // Refetching recomputes the matchup lines over the new window.
+                app.self_profile.profile_fetched = false;
+            }
             Intent::AdjustDebugScroll { delta } => {
                 if app.view == View::Debug {
                     let current = app.debug.scroll as i32;
@@ -1,23 +1,44 @@
 mod api;
+mod apm_stats;
 mod app;
+mod backfill;
+mod build_order;
 mod cache;
+mod cli;
 mod config;
+mod desktop;
 mod detect;
 mod error;
+mod form;
 mod gateway;
 mod history;
 mod interaction;
+mod maps;
 mod overlay;
 mod player_list;
 mod profile;
+mod profile_archive;
 mod profile_history;
 mod race;
+mod rating_stats;
 mod replay;
 mod replay_download;
+mod replay_filter;
+mod replay_index;
 mod replay_io;
+mod replay_layout;
+mod replay_manifest;
+mod replay_parser;
 mod runtime;
+mod scout;
+mod session;
+mod staged_write;
+#[cfg(test)]
+mod test_support;
+mod transfer;
 mod tui;
 mod ui;
+mod watch_list;
 
 use std::sync::OnceLock;
 
@@ -55,9 +76,16 @@ fn init_logging() {
 
 fn main() -> Result<(), AppError> {
     init_logging();
-    crate::tui::install_panic_hook();
 
-    let cfg: crate::config::Config = Default::default();
+    let cfg = crate::config::Config::load();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) =
+        cli::parse(&args).map_err(|err| AppError::runtime("invalid arguments", err))?
+    {
+        return cli::run(&cfg, command).map_err(|err| AppError::runtime("command failed", err));
+    }
+
+    crate::tui::install_panic_hook();
     let mut runtime = AppRuntime::new(cfg)?;
     let result = runtime.run();
     if let Err(err) = runtime.shutdown() {
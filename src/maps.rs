@@ -0,0 +1,162 @@
+use crate::profile_history::StoredMatch;
+
+// Strips StarCraft color/control codes, a leading player-count prefix such as
+// "(4)" and a trailing version suffix such as "1.3" or "v2.1a", so different
+// releases of the same map group together.
+pub fn normalize_map_name(raw: &str) -> Option<String> {
+    let cleaned: String = raw.chars().filter(|c| !c.is_control()).collect();
+    let mut name = cleaned.trim();
+
+    if let Some(rest) = name.strip_prefix('(')
+        && let Some((count, tail)) = rest.split_once(')')
+        && !count.is_empty()
+        && count.chars().all(|c| c.is_ascii_digit())
+    {
+        name = tail.trim_start();
+    }
+
+    let mut words: Vec<&str> = name.split_whitespace().collect();
+    while words.len() > 1 && words.last().is_some_and(|w| is_version_token(w)) {
+        words.pop();
+    }
+    let normalized = words.join(" ");
+    if normalized.is_empty() {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+fn is_version_token(word: &str) -> bool {
+    let word = word.trim_start_matches(['v', 'V']);
+    let word = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    !word.is_empty()
+        && word.chars().next().is_some_and(|c| c.is_ascii_digit())
+        && word.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && word.contains('.')
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapMatchup {
+    pub opponent_race: String,
+    pub wins: u32,
+    pub games: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapStat {
+    pub map: String,
+    pub wins: u32,
+    pub games: u32,
+    pub matchups: Vec<MapMatchup>,
+}
+
+impl MapStat {
+    pub fn win_pct(&self) -> u32 {
+        if self.games == 0 {
+            0
+        } else {
+            ((self.wins as f32 / self.games as f32) * 100.0).round() as u32
+        }
+    }
+}
+
+// Per-map results over decided games, most played first.
+pub fn map_stats(matches: &[StoredMatch]) -> Vec<MapStat> {
+    let mut stats: Vec<MapStat> = Vec::new();
+    for m in matches.iter().filter(|m| m.result.counts_for_record()) {
+        let Some(map) = m.map.as_deref() else {
+            continue;
+        };
+        let win = m.result.is_win();
+        let idx = match stats.iter().position(|s| s.map.eq_ignore_ascii_case(map)) {
+            Some(idx) => idx,
+            None => {
+                stats.push(MapStat {
+                    map: map.to_string(),
+                    wins: 0,
+                    games: 0,
+                    matchups: Vec::new(),
+                });
+                stats.len() - 1
+            }
+        };
+        let stat = &mut stats[idx];
+        stat.games += 1;
+        stat.wins += u32::from(win);
+
+        let race = m
+            .opponent_race
+            .as_deref()
+            .map(crate::race::initial)
+            .unwrap_or("?");
+        match stat.matchups.iter_mut().find(|mu| mu.opponent_race == race) {
+            Some(mu) => {
+                mu.games += 1;
+                mu.wins += u32::from(win);
+            }
+            None => stat.matchups.push(MapMatchup {
+                opponent_race: race.to_string(),
+                wins: u32::from(win),
+                games: 1,
+            }),
+        }
+    }
+    for stat in stats.iter_mut() {
+        stat.matchups
+            .sort_by(|a, b| a.opponent_race.cmp(&b.opponent_race));
+    }
+    stats.sort_by(|a, b| b.games.cmp(&a.games).then_with(|| a.map.cmp(&b.map)));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile_history::MatchOutcome;
+
+    #[test]
+    fn normalize_strips_codes_prefix_and_version() {
+        assert_eq!(
+            normalize_map_name("\u{3}Polypoid \u{4}1.65").as_deref(),
+            Some("Polypoid")
+        );
+        assert_eq!(
+            normalize_map_name("(4)Fighting Spirit 1.3").as_deref(),
+            Some("Fighting Spirit")
+        );
+        assert_eq!(
+            normalize_map_name("Eclipse v1.2a").as_deref(),
+            Some("Eclipse")
+        );
+        assert_eq!(
+            normalize_map_name("Good Night").as_deref(),
+            Some("Good Night")
+        );
+        assert_eq!(normalize_map_name("  "), None);
+    }
+
+    #[test]
+    fn map_stats_group_by_map_and_matchup() {
+        let m = |map: &str, race: &str, result| StoredMatch {
+            timestamp: 1,
+            opponent: "x".to_string(),
+            opponent_race: Some(race.to_string()),
+            result,
+            map: Some(map.to_string()),
+            ..Default::default()
+        };
+        let stats = map_stats(&[
+            m("Polypoid", "Zerg", MatchOutcome::Win),
+            m("Polypoid", "Zerg", MatchOutcome::Loss),
+            m("Polypoid", "Terran", MatchOutcome::Win),
+            m("Eclipse", "Zerg", MatchOutcome::Win),
+            m("Eclipse", "Zerg", MatchOutcome::SelfDodged),
+        ]);
+        assert_eq!(stats[0].map, "Polypoid");
+        assert_eq!((stats[0].wins, stats[0].games), (2, 3));
+        assert_eq!(stats[0].win_pct(), 67);
+        assert_eq!(stats[0].matchups[1].opponent_race, "Z");
+        assert_eq!((stats[1].wins, stats[1].games), (1, 1));
+    }
+}
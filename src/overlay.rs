@@ -1,7 +1,8 @@
 use crate::app::App;
 use crate::config::Config;
-use crate::history::aggregate_record_for_aurora_id;
+use crate::history::{aggregate_record_for_aurora_id, annotations_for};
 use crate::player_list::display_name_for_opponent;
+use crate::session::now_secs;
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 use thiserror::Error;
@@ -40,6 +41,21 @@ impl OverlayService {
         )
     }
 
+    pub fn write_session(cfg: &Config, app: &mut App) -> Result<(), OverlayError> {
+        if !cfg.session_output_enabled {
+            return Ok(());
+        }
+        let text = match app.session.current.as_ref() {
+            Some(session) => format!("Session: {}", session.summary(now_secs())),
+            None => "No active session".to_string(),
+        };
+        write_if_changed(
+            &cfg.session_output_path,
+            &mut app.overlays.session_last_text,
+            text,
+        )
+    }
+
     pub fn write_opponent(cfg: &Config, app: &mut App) -> Result<(), OverlayError> {
         if !cfg.opponent_output_enabled {
             return Ok(());
@@ -81,7 +97,20 @@ impl OverlayService {
                     .map(|rec| format!(" • W-L {}-{}", rec.wins, rec.losses))
                     .unwrap_or_default()
             };
-            format!("{} • {} • {}{}", display_name, race, rating_text, wl_text)
+            let annotations = annotations_for(&app.opponent.history, app.opponent.aurora_id, &name);
+            let tags_text = if annotations.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" • [{}]", annotations.tags.join(", "))
+            };
+            let notes_text = annotations
+                .notes
+                .map(|notes| format!("\n{}", notes))
+                .unwrap_or_default();
+            format!(
+                "{} • {} • {}{}{}{}",
+                display_name, race, rating_text, wl_text, tags_text, notes_text
+            )
         };
         write_if_changed(
             &cfg.opponent_output_path,
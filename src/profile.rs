@@ -4,6 +4,7 @@ use std::path::Path;
 
 use crate::app::App;
 use crate::config::Config;
+use crate::maps::normalize_map_name;
 use crate::overlay::{OverlayError, OverlayService};
 use crate::profile_history::{MatchOutcome, ProfileHistoryKey, ProfileHistoryService, StoredMatch};
 use anyhow::{Context, Result as AnyhowResult};
@@ -11,8 +12,7 @@ use reqwest::blocking::Client;
 use thiserror::Error;
 use tracing::info;
 
-use crate::replay::parse_screp_duration_seconds;
-use crate::replay_io::{download_replay, run_screp_overview, sanitize_identifier};
+use crate::replay_io::{download_replay, read_replay_summary, sanitize_identifier};
 
 pub struct ProfileService;
 
@@ -66,17 +66,15 @@ impl ProfileService {
                 tracing::warn!(error = %err, "failed to seed profile history");
             }
 
-            let (mr, lines, _results, self_dodged, opp_dodged) = api.profile_stats_last100(
+            let stats = api.profile_stats(
                 &profile,
                 &name,
                 profile_history,
                 Some(&history_key),
                 Some(&app.opponent.history),
+                app.self_profile.stats_window,
             );
-            app.self_profile.main_race = mr;
-            app.self_profile.matchups = lines;
-            app.self_profile.self_dodged = self_dodged;
-            app.self_profile.opponent_dodged = opp_dodged;
+            app.self_profile.apply_stats(stats);
         }
         app.self_profile.last_rating_poll = Some(std::time::Instant::now());
         app.self_profile.profile_fetched = true;
@@ -89,7 +87,7 @@ impl ProfileService {
         cfg: &Config,
         mut profile_history: Option<&mut ProfileHistoryService>,
     ) -> Result<(), ProfileError> {
-        if app.detection.screp_available {
+        if app.detection.replay_watch_enabled {
             return Ok(());
         }
         let due = app
@@ -120,17 +118,15 @@ impl ProfileService {
                 tracing::warn!(error = %err, "failed to seed profile history");
             }
 
-            let (mr, lines, _results, self_dodged, opp_dodged) = api.profile_stats_last100(
+            let stats = api.profile_stats(
                 &profile,
                 &name,
                 profile_history,
                 Some(&history_key),
                 Some(&app.opponent.history),
+                app.self_profile.stats_window,
             );
-            app.self_profile.main_race = mr;
-            app.self_profile.matchups = lines;
-            app.self_profile.self_dodged = self_dodged;
-            app.self_profile.opponent_dodged = opp_dodged;
+            app.self_profile.apply_stats(stats);
         }
         OverlayService::write_rating(cfg, app)?;
         Ok(())
@@ -146,6 +142,7 @@ fn seed_profile_history(
     history: &mut ProfileHistoryService,
 ) -> AnyhowResult<()> {
     let history_key = ProfileHistoryKey::new(main_name, gateway);
+    let seasons = crate::api::SeasonSpans::new(profile, main_name);
     if history.has_matches(&history_key) {
         return Ok(());
     }
@@ -185,7 +182,15 @@ fn seed_profile_history(
             }
         }
 
-        match seed_single_replay(main_name, history, &history_key, replay, &lookup, was_dodge) {
+        match seed_single_replay(
+            main_name,
+            history,
+            &history_key,
+            replay,
+            &lookup,
+            was_dodge,
+            &seasons,
+        ) {
             Ok(true) => {
                 processed += 1;
                 if was_dodge {
@@ -214,6 +219,7 @@ fn seed_single_replay(
     replay: &bw_web_api_rs::models::common::Replay,
     lookup: &GameLookup,
     was_dodge: bool,
+    seasons: &crate::api::SeasonSpans,
 ) -> AnyhowResult<bool> {
     let Some(game) = lookup.find(replay) else {
         tracing::debug!(replay_link = %replay.link, "seeding skipped: no matching game result");
@@ -266,6 +272,9 @@ fn seed_single_replay(
         opponent_race: opp_player.attributes.race.clone(),
         main_race: main_player.attributes.race.clone(),
         result: outcome,
+        season: seasons.season_at(timestamp),
+        map: normalize_map_name(&game.attributes.map_name),
+        ..Default::default()
     };
 
     history.upsert_match(history_key, stored)?;
@@ -306,10 +315,10 @@ fn fetch_replay_duration(
 
     download_replay(client, &best.url, &tmp_path)
         .with_context(|| format!("download replay {}", best.url))?;
-    let overview = run_screp_overview(cfg, &tmp_path)?;
+    let overview = read_replay_summary(cfg, &tmp_path);
     let _ = fs::remove_file(&tmp_path);
 
-    Ok(parse_screp_duration_seconds(&overview))
+    Ok(Some(overview?.duration_secs()))
 }
 
 struct SeedWorkspace {
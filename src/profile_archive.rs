@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::profile_history::{ProfileHistoryKey, StoredMatch};
+
+const ARCHIVE_SUFFIX: &str = ".json.gz";
+
+pub fn archive_path(dir: &Path, key: &ProfileHistoryKey, season: Option<u32>) -> PathBuf {
+    let file = match season {
+        Some(season) => format!("season-{season}{ARCHIVE_SUFFIX}"),
+        None => format!("season-unknown{ARCHIVE_SUFFIX}"),
+    };
+    dir.join(key.storage_key()).join(file)
+}
+
+// Matches are grouped by season and merged into the existing archive files,
+// skipping any already archived for the same timestamp and opponent.
+pub fn append(dir: &Path, key: &ProfileHistoryKey, matches: Vec<StoredMatch>) -> Result<()> {
+    let mut by_season: BTreeMap<Option<u32>, Vec<StoredMatch>> = BTreeMap::new();
+    for m in matches {
+        by_season.entry(m.season).or_default().push(m);
+    }
+    for (season, incoming) in by_season {
+        let path = archive_path(dir, key, season);
+        let mut archived = read(&path)?;
+        for m in incoming {
+            let exists = archived.iter().any(|a| {
+                a.timestamp == m.timestamp && a.opponent.eq_ignore_ascii_case(&m.opponent)
+            });
+            if !exists {
+                archived.push(m);
+            }
+        }
+        archived.sort_by_key(|m| std::cmp::Reverse(m.timestamp));
+        write(&path, &archived)?;
+    }
+    Ok(())
+}
+
+pub fn load_all(dir: &Path, key: &ProfileHistoryKey) -> Result<Vec<StoredMatch>> {
+    let profile_dir = dir.join(key.storage_key());
+    let entries = match fs::read_dir(&profile_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("read archive dir {}", profile_dir.display()));
+        }
+    };
+    let mut out = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_archive = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.ends_with(ARCHIVE_SUFFIX));
+        if is_archive {
+            out.extend(read(&path)?);
+        }
+    }
+    out.sort_by_key(|m| std::cmp::Reverse(m.timestamp));
+    Ok(out)
+}
+
+fn read(path: &Path) -> Result<Vec<StoredMatch>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("open archive {}", path.display())),
+    };
+    let mut json = Vec::new();
+    GzDecoder::new(file)
+        .read_to_end(&mut json)
+        .with_context(|| format!("decompress archive {}", path.display()))?;
+    serde_json::from_slice(&json).with_context(|| format!("deserialize archive {}", path.display()))
+}
+
+fn write(path: &Path, matches: &[StoredMatch]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("create archive directory {}", parent.display()))?;
+    }
+    let json = serde_json::to_vec(matches).context("serialize archive")?;
+    let tmp = path.with_extension("tmp");
+    let file =
+        fs::File::create(&tmp).with_context(|| format!("create archive {}", tmp.display()))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder
+        .write_all(&json)
+        .and_then(|_| encoder.finish().map(|_| ()))
+        .with_context(|| format!("write archive {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("replace archive {}", path.display()))?;
+    Ok(())
+}
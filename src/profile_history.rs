@@ -1,14 +1,74 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 
-const MAX_STORED_MATCHES: usize = 500;
+use crate::history::{GAME_MATCH_SLACK_SECS, GAME_MATCH_WINDOW_SECS};
+use crate::staged_write::StagedWrite;
+
+const DEFAULT_STORED_MATCHES: usize = 500;
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryRetention {
+    Count(usize),
+    MaxAgeDays(u64),
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self::Count(DEFAULT_STORED_MATCHES)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsWindow {
+    Last50,
+    #[default]
+    Last100,
+    Last500,
+    Season,
+    AllTime,
+}
+
+impl StatsWindow {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Last50 => Self::Last100,
+            Self::Last100 => Self::Last500,
+            Self::Last500 => Self::Season,
+            Self::Season => Self::AllTime,
+            Self::AllTime => Self::Last50,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Last50 => "Last 50",
+            Self::Last100 => "Last 100",
+            Self::Last500 => "Last 500",
+            Self::Season => "Season",
+            Self::AllTime => "All-time",
+        }
+    }
+
+    pub fn limit(self) -> Option<usize> {
+        match self {
+            Self::Last50 => Some(50),
+            Self::Last100 => Some(100),
+            Self::Last500 => Some(500),
+            Self::Season | Self::AllTime => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MatchOutcome {
+    #[default]
     Win,
     Loss,
     SelfDodged,
@@ -33,13 +93,140 @@ impl MatchOutcome {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StoredMatch {
     pub timestamp: u64,
     pub opponent: String,
     pub opponent_race: Option<String>,
     pub main_race: Option<String>,
     pub result: MatchOutcome,
+    #[serde(default)]
+    pub season: Option<u32>,
+    #[serde(default)]
+    pub map: Option<String>,
+    #[serde(default)]
+    pub rating_before: Option<u32>,
+    #[serde(default)]
+    pub rating_after: Option<u32>,
+    #[serde(default)]
+    pub rating_delta: Option<i32>,
+    #[serde(default)]
+    pub opponent_rating: Option<u32>,
+    #[serde(default)]
+    pub apm: Option<u32>,
+    #[serde(default)]
+    pub eapm: Option<u32>,
+    #[serde(default)]
+    pub opponent_apm: Option<u32>,
+    #[serde(default)]
+    pub opponent_eapm: Option<u32>,
+    // Where the game's replay was archived in the replay library.
+    #[serde(default)]
+    pub replay_path: Option<String>,
+}
+
+// Both players' APM and EAPM as read from the game's replay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameApm {
+    pub apm: Option<u32>,
+    pub eapm: Option<u32>,
+    pub opponent_apm: Option<u32>,
+    pub opponent_eapm: Option<u32>,
+}
+
+impl StoredMatch {
+    fn game_apm(&self) -> GameApm {
+        GameApm {
+            apm: self.apm,
+            eapm: self.eapm,
+            opponent_apm: self.opponent_apm,
+            opponent_eapm: self.opponent_eapm,
+        }
+    }
+
+    fn fill_apm(&mut self, other: GameApm) -> bool {
+        let mut updated = false;
+        for (field, value) in [
+            (&mut self.apm, other.apm),
+            (&mut self.eapm, other.eapm),
+            (&mut self.opponent_apm, other.opponent_apm),
+            (&mut self.opponent_eapm, other.opponent_eapm),
+        ] {
+            if field.is_none() && value.is_some() {
+                *field = value;
+                updated = true;
+            }
+        }
+        updated
+    }
+
+    fn fill_replay_path(&mut self, path: Option<&String>) -> bool {
+        if self.replay_path.is_none() && path.is_some() {
+            self.replay_path = path.cloned();
+            return true;
+        }
+        false
+    }
+
+    fn fill_ratings_from(&mut self, other: &StoredMatch) -> bool {
+        self.fill_ratings(
+            other.rating_before,
+            other.rating_after,
+            other.opponent_rating,
+        )
+    }
+
+    fn fill_ratings(
+        &mut self,
+        before: Option<u32>,
+        after: Option<u32>,
+        opponent: Option<u32>,
+    ) -> bool {
+        let mut updated = false;
+        if self.rating_before.is_none() && before.is_some() {
+            self.rating_before = before;
+            updated = true;
+        }
+        if self.rating_after.is_none() && after.is_some() {
+            self.rating_after = after;
+            updated = true;
+        }
+        if self.opponent_rating.is_none() && opponent.is_some() {
+            self.opponent_rating = opponent;
+            updated = true;
+        }
+        if self.rating_delta.is_none()
+            && let (Some(before), Some(after)) = (self.rating_before, self.rating_after)
+        {
+            self.rating_delta = Some(after as i32 - before as i32);
+            updated = true;
+        }
+        updated
+    }
+}
+
+// What we learn about a finished game outside the API: our rating around it,
+// the opponent's rating at game time, APM from the replay and where the replay
+// was archived. The game's
+// timestamp comes from the replay, so it only approximates the API's create
+// time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameCapture {
+    pub opponent: String,
+    pub approx_timestamp: u64,
+    pub rating_before: Option<u32>,
+    pub rating_after: Option<u32>,
+    pub opponent_rating: Option<u32>,
+    pub apm: GameApm,
+    pub replay_path: Option<String>,
+}
+
+impl GameCapture {
+    fn matches(&self, m: &StoredMatch) -> bool {
+        m.opponent.eq_ignore_ascii_case(&self.opponent)
+            && m.timestamp <= self.approx_timestamp + GAME_MATCH_SLACK_SECS
+            && self.approx_timestamp.saturating_sub(m.timestamp) <= GAME_MATCH_WINDOW_SECS
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -59,21 +246,45 @@ impl ProfileHistoryKey {
     pub fn storage_key(&self) -> String {
         format!("{}#{}", self.name, self.gateway)
     }
+
+    pub fn from_storage_key(raw: &str) -> Option<Self> {
+        let (name, gateway) = raw.rsplit_once('#')?;
+        Some(Self::new(name, gateway.parse().ok()?))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn gateway(&self) -> u16 {
+        self.gateway
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct ProfileHistoryData {
     profiles: HashMap<String, Vec<StoredMatch>>,
+    // Captures waiting for the API to list their game, keyed by profile.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pending_captures: Vec<(String, GameCapture)>,
+    // The last game's capture while our post-game rating is still unknown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    awaiting_rating: Option<(String, GameCapture)>,
 }
 
 pub struct ProfileHistoryService {
     path: PathBuf,
     data: ProfileHistoryData,
+    retention: HistoryRetention,
+    archive_dir: Option<PathBuf>,
+    // Archived matches per storage key, read on first use; dropped whenever
+    // the key's archive is appended to.
+    archived: HashMap<String, Vec<StoredMatch>>,
 }
 
 impl ProfileHistoryService {
     pub fn new(path: PathBuf) -> Result<Self> {
-        let data = match fs::read(&path) {
+        let mut data = match fs::read(&path) {
             Ok(bytes) => serde_json::from_slice::<ProfileHistoryData>(&bytes)
                 .with_context(|| format!("deserialize profile history {}", path.display()))?,
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => ProfileHistoryData::default(),
@@ -83,88 +294,265 @@ impl ProfileHistoryService {
                 );
             }
         };
-        Ok(Self { path, data })
+        // Our rating is polled fresh on start, so the change the last run was
+        // waiting for can no longer be told apart from later games.
+        if let Some((key, capture)) = data.awaiting_rating.take() {
+            tracing::info!(
+                profile = %key,
+                opponent = %capture.opponent,
+                "dropping post-game rating capture left unfinished by the last run"
+            );
+        }
+        Ok(Self {
+            path,
+            data,
+            retention: HistoryRetention::default(),
+            archive_dir: None,
+            archived: HashMap::new(),
+        })
     }
 
     pub fn empty(path: PathBuf) -> Self {
         Self {
             path,
             data: ProfileHistoryData::default(),
+            retention: HistoryRetention::default(),
+            archive_dir: None,
+            archived: HashMap::new(),
         }
     }
 
+    // Matches pushed out by the retention policy are moved to per-season
+    // archives under `archive_dir` instead of being dropped.
+    pub fn with_retention(mut self, retention: HistoryRetention, archive_dir: PathBuf) -> Self {
+        self.retention = retention;
+        self.archive_dir = Some(archive_dir);
+        self
+    }
+
     pub fn merge_matches(
         &mut self,
         key: &ProfileHistoryKey,
-        mut incoming: Vec<StoredMatch>,
+        incoming: Vec<StoredMatch>,
+        window: StatsWindow,
+        current_season: Option<u32>,
     ) -> Result<Vec<StoredMatch>> {
-        incoming.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        let storage_key = key.storage_key();
-        let mut changed = false;
+        let summary = self.import_matches(key, incoming)?;
+        if !summary.conflicts.is_empty() {
+            tracing::debug!(conflicts = ?summary.conflicts, "kept stored match results");
+        }
+        self.window_matches(key, window, current_season)
+    }
 
+    pub fn window_matches(
+        &mut self,
+        key: &ProfileHistoryKey,
+        window: StatsWindow,
+        current_season: Option<u32>,
+    ) -> Result<Vec<StoredMatch>> {
+        let storage_key = key.storage_key();
+        if matches!(window, StatsWindow::AllTime | StatsWindow::Season)
+            && let Some(dir) = self.archive_dir.as_deref()
+            && !self.archived.contains_key(&storage_key)
         {
-            let entry = self.data.profiles.entry(storage_key.clone()).or_default();
-
-            for m in incoming.into_iter() {
-                if m.timestamp == 0 {
-                    continue;
-                }
-                if let Some(existing) = entry.iter_mut().find(|existing| {
-                    existing.timestamp == m.timestamp
-                        && existing.opponent.eq_ignore_ascii_case(m.opponent.as_str())
-                }) {
-                    if existing.main_race.is_none() && m.main_race.is_some() {
-                        existing.main_race = m.main_race.clone();
-                        changed = true;
-                    }
-                    if existing.opponent_race.is_none() && m.opponent_race.is_some() {
-                        existing.opponent_race = m.opponent_race.clone();
-                        changed = true;
+            let archived = crate::profile_archive::load_all(dir, key)?;
+            self.archived.insert(storage_key.clone(), archived);
+        }
+        let stored = self
+            .data
+            .profiles
+            .get(&storage_key)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut output: Vec<StoredMatch> = match window {
+            StatsWindow::AllTime | StatsWindow::Season => {
+                let mut all = stored.to_vec();
+                let mut seen: HashSet<(u64, String)> = all
+                    .iter()
+                    .map(|m| (m.timestamp, m.opponent.to_ascii_lowercase()))
+                    .collect();
+                for m in self.archived.get(&storage_key).into_iter().flatten() {
+                    if seen.insert((m.timestamp, m.opponent.to_ascii_lowercase())) {
+                        all.push(m.clone());
                     }
-                    continue;
                 }
-                entry.push(m);
-                changed = true;
+                all.sort_by_key(|m| std::cmp::Reverse(m.timestamp));
+                all
             }
+            _ => stored.to_vec(),
+        };
+        if window == StatsWindow::Season {
+            output.retain(|m| current_season.is_some() && m.season == current_season);
+        }
+        if let Some(limit) = window.limit() {
+            output.truncate(limit);
+        }
+        Ok(output)
+    }
 
-            if changed {
-                entry.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-                if entry.len() > MAX_STORED_MATCHES {
-                    entry.truncate(MAX_STORED_MATCHES);
+    fn enforce_retention(&mut self, storage_key: &str) -> Result<()> {
+        let Some(entry) = self.data.profiles.get_mut(storage_key) else {
+            return Ok(());
+        };
+        entry.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        let expired: Vec<StoredMatch> = match self.retention {
+            HistoryRetention::Count(max) => {
+                if entry.len() > max {
+                    entry.split_off(max)
+                } else {
+                    Vec::new()
                 }
             }
+            HistoryRetention::MaxAgeDays(days) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                let cutoff = now.saturating_sub(days.saturating_mul(SECS_PER_DAY));
+                let keep = entry.iter().take_while(|m| m.timestamp >= cutoff).count();
+                entry.split_off(keep)
+            }
+        };
+        if expired.is_empty() {
+            return Ok(());
+        }
+        if let (Some(dir), Some(key)) = (
+            self.archive_dir.as_deref(),
+            ProfileHistoryKey::from_storage_key(storage_key),
+        ) {
+            self.archived.remove(storage_key);
+            crate::profile_archive::append(dir, &key, expired)
+                .with_context(|| format!("archive profile history for {storage_key}"))?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        self.stage()?.commit()
+    }
+
+    // Writes the history beside its file; committing puts it in place.
+    pub fn stage(&self) -> Result<StagedWrite> {
+        let data = serde_json::to_vec_pretty(&self.data).context("serialize profile history")?;
+        StagedWrite::new(&self.path, &data)
+            .with_context(|| format!("write profile history {}", self.path.display()))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MatchMerge {
+    pub added: usize,
+    pub updated: usize,
+    pub conflicts: Vec<String>,
+}
+
+fn merge_into(entry: &mut Vec<StoredMatch>, incoming: Vec<StoredMatch>) -> MatchMerge {
+    let mut summary = MatchMerge::default();
+    for m in incoming.into_iter() {
+        if m.timestamp == 0 {
+            continue;
+        }
+        if let Some(existing) = entry.iter_mut().find(|existing| {
+            existing.timestamp == m.timestamp
+                && existing.opponent.eq_ignore_ascii_case(m.opponent.as_str())
+        }) {
+            let mut updated = false;
+            if existing.main_race.is_none() && m.main_race.is_some() {
+                existing.main_race = m.main_race.clone();
+                updated = true;
+            }
+            if existing.opponent_race.is_none() && m.opponent_race.is_some() {
+                existing.opponent_race = m.opponent_race.clone();
+                updated = true;
+            }
+            if existing.season.is_none() && m.season.is_some() {
+                existing.season = m.season;
+                updated = true;
+            }
+            if existing.map.is_none() && m.map.is_some() {
+                existing.map = m.map.clone();
+                updated = true;
+            }
+            if existing.fill_ratings_from(&m) {
+                updated = true;
+            }
+            if existing.fill_apm(m.game_apm()) {
+                updated = true;
+            }
+            if existing.fill_replay_path(m.replay_path.as_ref()) {
+                updated = true;
+            }
+            if existing.result != m.result {
+                summary.conflicts.push(format!(
+                    "{} at {}: result {:?} differs from imported {:?}; kept local",
+                    existing.opponent, existing.timestamp, existing.result, m.result
+                ));
+            }
+            if updated {
+                summary.updated += 1;
+            }
+            continue;
         }
+        entry.push(m);
+        summary.added += 1;
+    }
+    summary
+}
 
+impl ProfileHistoryService {
+    pub fn import_matches(
+        &mut self,
+        key: &ProfileHistoryKey,
+        incoming: Vec<StoredMatch>,
+    ) -> Result<MatchMerge> {
+        let (summary, changed) = self.merge_without_saving(key, incoming)?;
         if changed {
             self.save()?;
         }
+        Ok(summary)
+    }
 
-        let output = self
-            .data
-            .profiles
-            .get(&storage_key)
-            .map(|entry| {
-                let limit = entry.len().min(100);
-                entry.iter().take(limit).cloned().collect()
-            })
-            .unwrap_or_default();
-        Ok(output)
+    // Like `import_matches`, but leaves writing the history to the caller.
+    pub fn merge_imported_matches(
+        &mut self,
+        key: &ProfileHistoryKey,
+        incoming: Vec<StoredMatch>,
+    ) -> Result<MatchMerge> {
+        self.merge_without_saving(key, incoming)
+            .map(|(summary, _)| summary)
     }
 
-    fn save(&self) -> Result<()> {
-        if let Some(parent) = self.path.parent() {
-            fs::create_dir_all(parent).with_context(|| {
-                format!("create profile history directory {}", parent.display())
-            })?;
+    fn merge_without_saving(
+        &mut self,
+        key: &ProfileHistoryKey,
+        mut incoming: Vec<StoredMatch>,
+    ) -> Result<(MatchMerge, bool)> {
+        incoming.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        let storage_key = key.storage_key();
+        let entry = self.data.profiles.entry(storage_key.clone()).or_default();
+        let summary = merge_into(entry, incoming);
+        let rated = self.apply_pending_captures(&storage_key);
+        let changed = summary.added + summary.updated > 0 || rated;
+        if changed {
+            self.enforce_retention(&storage_key)?;
         }
-        let data = serde_json::to_vec_pretty(&self.data).context("serialize profile history")?;
-        fs::write(&self.path, data)
-            .with_context(|| format!("write profile history {}", self.path.display()))?;
-        Ok(())
+        Ok((summary, changed))
+    }
+
+    pub fn profiles(&self) -> Vec<(ProfileHistoryKey, &[StoredMatch])> {
+        let mut out: Vec<(ProfileHistoryKey, &[StoredMatch])> = self
+            .data
+            .profiles
+            .iter()
+            .filter_map(|(storage_key, matches)| {
+                ProfileHistoryKey::from_storage_key(storage_key)
+                    .map(|key| (key, matches.as_slice()))
+            })
+            .collect();
+        out.sort_by_key(|(key, _)| key.storage_key());
+        out
     }
-}
 
-impl ProfileHistoryService {
     pub fn upsert_match(&mut self, key: &ProfileHistoryKey, new_match: StoredMatch) -> Result<()> {
         let storage_key = key.storage_key();
         let entry = self.data.profiles.entry(storage_key.clone()).or_default();
@@ -177,10 +565,16 @@ impl ProfileHistoryService {
                     .eq_ignore_ascii_case(new_match.opponent.as_str())
         }) {
             if existing.result != new_match.result
+                || existing.season != new_match.season
+                || existing.map != new_match.map
                 || existing.main_race != new_match.main_race
                 || existing.opponent_race != new_match.opponent_race
                 || existing.opponent != new_match.opponent
             {
+                let mut new_match = new_match;
+                new_match.fill_ratings_from(existing);
+                new_match.fill_apm(existing.game_apm());
+                new_match.fill_replay_path(existing.replay_path.as_ref());
                 *existing = new_match;
                 changed = true;
             }
@@ -190,16 +584,75 @@ impl ProfileHistoryService {
         }
 
         if changed {
-            entry.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-            if entry.len() > MAX_STORED_MATCHES {
-                entry.truncate(MAX_STORED_MATCHES);
-            }
+            self.enforce_retention(&storage_key)?;
             self.save()?;
         }
 
         Ok(())
     }
 
+    // Attaches a game's rating change and APM to its stored match. The API may
+    // not list the game yet, in which case the capture waits for the next
+    // merge.
+    pub fn record_game_capture(
+        &mut self,
+        key: &ProfileHistoryKey,
+        capture: GameCapture,
+    ) -> Result<bool> {
+        let storage_key = key.storage_key();
+        self.data.pending_captures.retain(|(k, pending)| {
+            !(k == &storage_key
+                && pending.approx_timestamp == capture.approx_timestamp
+                && pending.opponent.eq_ignore_ascii_case(&capture.opponent))
+        });
+        self.data
+            .pending_captures
+            .push((storage_key.clone(), capture));
+        let rated = self.apply_pending_captures(&storage_key);
+        // Saved either way so a capture still waiting survives a restart.
+        self.save()?;
+        Ok(rated)
+    }
+
+    // Remembers the capture waiting for our post-game rating; it is written
+    // with the next save.
+    pub fn set_awaiting_rating(&mut self, awaiting: Option<(&ProfileHistoryKey, &GameCapture)>) {
+        self.data.awaiting_rating =
+            awaiting.map(|(key, capture)| (key.storage_key(), capture.clone()));
+    }
+
+    fn apply_pending_captures(&mut self, storage_key: &str) -> bool {
+        let Some(entry) = self.data.profiles.get_mut(storage_key) else {
+            return false;
+        };
+        let newest = entry.iter().map(|m| m.timestamp).max().unwrap_or_default();
+        let mut updated = false;
+        self.data.pending_captures.retain(|(key, capture)| {
+            if key != storage_key {
+                return true;
+            }
+            match entry
+                .iter_mut()
+                .filter(|m| capture.matches(m))
+                .max_by_key(|m| m.timestamp)
+            {
+                Some(m) => {
+                    updated |= m.fill_ratings(
+                        capture.rating_before,
+                        capture.rating_after,
+                        capture.opponent_rating,
+                    );
+                    updated |= m.fill_apm(capture.apm);
+                    updated |= m.fill_replay_path(capture.replay_path.as_ref());
+                    false
+                }
+                // Later games are already listed, so this one never will be.
+                None => newest <= capture.approx_timestamp + GAME_MATCH_SLACK_SECS,
+            }
+        });
+        updated
+    }
+
     pub fn has_matches(&self, key: &ProfileHistoryKey) -> bool {
         let storage_key = key.storage_key();
         self.data
@@ -213,13 +666,10 @@ impl ProfileHistoryService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::unique_temp_path;
 
     fn unique_test_path(name: &str) -> PathBuf {
-        let nanos = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_nanos())
-            .unwrap_or_default();
-        std::env::temp_dir().join(format!("bwtools-{name}-{nanos}.json"))
+        unique_temp_path(name).with_extension("json")
     }
 
     #[test]
@@ -228,6 +678,14 @@ mod tests {
         assert_eq!(key.storage_key(), "alice#10");
     }
 
+    #[test]
+    fn storage_key_round_trips() {
+        let key = ProfileHistoryKey::from_storage_key("some#name#30").expect("parse key");
+        assert_eq!(key.name(), "some#name");
+        assert_eq!(key.gateway(), 30);
+        assert!(ProfileHistoryKey::from_storage_key("nogateway").is_none());
+    }
+
     #[test]
     fn match_outcome_classifiers_are_consistent() {
         assert!(MatchOutcome::Win.is_win());
@@ -238,6 +696,53 @@ mod tests {
         assert!(!MatchOutcome::SelfDodged.counts_for_record());
     }
 
+    #[test]
+    fn rating_capture_waits_for_the_api_match() {
+        let path = unique_test_path("rating-capture");
+        let mut service = ProfileHistoryService::empty(path.clone());
+        let key = ProfileHistoryKey::new("Alice", 10);
+        let capture = GameCapture {
+            opponent: "Bob".to_string(),
+            approx_timestamp: 10_000,
+            rating_before: Some(2000),
+            rating_after: Some(2017),
+            opponent_rating: Some(2100),
+            apm: GameApm {
+                apm: Some(180),
+                ..GameApm::default()
+            },
+            replay_path: Some("bwtools/Alice/TvZ/game.rep".to_string()),
+        };
+        service.set_awaiting_rating(Some((&key, &capture)));
+        assert!(!service.record_game_capture(&key, capture).expect("record"));
+
+        // A restart keeps the capture queued but drops the rating wait.
+        let mut service = ProfileHistoryService::new(path.clone()).expect("reload");
+        assert_eq!(service.data.pending_captures.len(), 1);
+        assert!(service.data.awaiting_rating.is_none());
+
+        let api_match = StoredMatch {
+            timestamp: 9_100,
+            opponent: "bob".to_string(),
+            opponent_race: Some("Zerg".to_string()),
+            main_race: Some("Terran".to_string()),
+            result: MatchOutcome::Win,
+            ..Default::default()
+        };
+        let merged = service
+            .merge_matches(&key, vec![api_match], StatsWindow::Last100, None)
+            .expect("merge");
+        assert_eq!(merged[0].rating_delta, Some(17));
+        assert_eq!(merged[0].opponent_rating, Some(2100));
+        assert_eq!(merged[0].apm, Some(180));
+        assert_eq!(
+            merged[0].replay_path.as_deref(),
+            Some("bwtools/Alice/TvZ/game.rep")
+        );
+        assert!(service.data.pending_captures.is_empty());
+        let _ = fs::remove_file(path);
+    }
+
     #[test]
     fn merge_matches_updates_missing_races_without_duplicate_entries() {
         let path = unique_test_path("merge");
@@ -250,9 +755,8 @@ mod tests {
                 StoredMatch {
                     timestamp: 100,
                     opponent: "Bob".to_string(),
-                    opponent_race: None,
-                    main_race: None,
                     result: MatchOutcome::Win,
+                    ..Default::default()
                 },
             )
             .expect("seed base match");
@@ -266,14 +770,76 @@ mod tests {
                     opponent_race: Some("Terran".to_string()),
                     main_race: Some("Protoss".to_string()),
                     result: MatchOutcome::Win,
+                    season: Some(20),
+                    ..Default::default()
                 }],
+                StatsWindow::Last100,
+                Some(20),
             )
             .expect("merge history");
 
         assert_eq!(merged.len(), 1);
         assert_eq!(merged[0].opponent_race.as_deref(), Some("Terran"));
         assert_eq!(merged[0].main_race.as_deref(), Some("Protoss"));
+        assert_eq!(merged[0].season, Some(20));
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn retention_moves_overflow_to_season_archives() {
+        let path = unique_test_path("retention");
+        let archive_dir = path.with_extension("archive");
+        let mut service = ProfileHistoryService::empty(path.clone())
+            .with_retention(HistoryRetention::Count(2), archive_dir.clone());
+        let key = ProfileHistoryKey::new("Alice", 10);
+        let matches = (1..=3)
+            .map(|i| StoredMatch {
+                timestamp: i * 100,
+                opponent: format!("Opp{i}"),
+                result: MatchOutcome::Win,
+                season: Some(if i == 1 { 19 } else { 20 }),
+                ..Default::default()
+            })
+            .collect();
+        service
+            .import_matches(&key, matches)
+            .expect("import matches");
+
+        let recent = service
+            .window_matches(&key, StatsWindow::Last50, Some(20))
+            .expect("recent window");
+        assert_eq!(recent.len(), 2);
+        let season = service
+            .window_matches(&key, StatsWindow::Season, Some(20))
+            .expect("season window");
+        assert_eq!(season.len(), 2);
+        let all = service
+            .window_matches(&key, StatsWindow::AllTime, Some(20))
+            .expect("all-time window");
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].opponent, "Opp1");
+        assert!(crate::profile_archive::archive_path(&archive_dir, &key, Some(19)).is_file());
+
+        // The cached archive is read again once more matches are moved there.
+        service
+            .import_matches(
+                &key,
+                vec![StoredMatch {
+                    timestamp: 400,
+                    opponent: "Opp4".to_string(),
+                    result: MatchOutcome::Loss,
+                    season: Some(20),
+                    ..Default::default()
+                }],
+            )
+            .expect("import another match");
+        let all = service
+            .window_matches(&key, StatsWindow::AllTime, Some(20))
+            .expect("all-time window");
+        assert_eq!(all.len(), 4);
 
         let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_dir_all(archive_dir);
     }
 }
@@ -0,0 +1,90 @@
+use crate::profile_history::StoredMatch;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RatingBucket {
+    pub games: u32,
+    pub wins: u32,
+    pub points: i64,
+}
+
+impl RatingBucket {
+    fn add(&mut self, win: bool, delta: i32) {
+        self.games += 1;
+        self.wins += u32::from(win);
+        self.points += i64::from(delta);
+    }
+}
+
+// Rating gained or lost against opponents rated above and below us at game
+// time. Only matches with a captured delta and opponent rating count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RatingSplit {
+    pub vs_higher: RatingBucket,
+    pub vs_lower: RatingBucket,
+}
+
+impl RatingSplit {
+    pub fn is_empty(&self) -> bool {
+        self.vs_higher.games == 0 && self.vs_lower.games == 0
+    }
+}
+
+pub fn rating_split(matches: &[StoredMatch]) -> RatingSplit {
+    let mut split = RatingSplit::default();
+    for m in matches.iter().filter(|m| m.result.counts_for_record()) {
+        let (Some(before), Some(delta), Some(opponent)) =
+            (m.rating_before, m.rating_delta, m.opponent_rating)
+        else {
+            continue;
+        };
+        let bucket = if opponent > before {
+            &mut split.vs_higher
+        } else {
+            &mut split.vs_lower
+        };
+        bucket.add(m.result.is_win(), delta);
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile_history::MatchOutcome;
+
+    #[test]
+    fn splits_points_by_opponent_rating() {
+        let m = |before: u32, after: u32, opponent: Option<u32>, result| StoredMatch {
+            timestamp: 1,
+            opponent: "x".to_string(),
+            result,
+            rating_before: Some(before),
+            rating_after: Some(after),
+            rating_delta: Some(after as i32 - before as i32),
+            opponent_rating: opponent,
+            ..Default::default()
+        };
+        let split = rating_split(&[
+            m(2000, 2024, Some(2150), MatchOutcome::Win),
+            m(2024, 2010, Some(2100), MatchOutcome::Loss),
+            m(2010, 2022, Some(1900), MatchOutcome::Win),
+            m(2022, 2030, None, MatchOutcome::Win),
+        ]);
+        assert_eq!(
+            split.vs_higher,
+            RatingBucket {
+                games: 2,
+                wins: 1,
+                points: 10
+            }
+        );
+        assert_eq!(
+            split.vs_lower,
+            RatingBucket {
+                games: 1,
+                wins: 1,
+                points: 12
+            }
+        );
+    }
+}
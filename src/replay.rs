@@ -1,10 +1,10 @@
 use std::time::SystemTime;
 
-use crate::app::App;
+use crate::app::{App, SelfProfileState};
 use crate::config::Config;
 use crate::history::{FileHistorySource, HistoryService};
 use crate::overlay::OverlayError;
-use crate::profile_history::{MatchOutcome, ProfileHistoryService};
+use crate::profile_history::{GameCapture, MatchOutcome, ProfileHistoryKey, ProfileHistoryService};
 use thiserror::Error;
 
 pub struct ReplayService;
@@ -17,10 +17,6 @@ pub enum ReplayError {
     History(#[source] anyhow::Error),
 }
 
-type ScrepPlayers = Vec<(u8, Option<String>, String)>;
-type ScrepParsed = (Option<String>, ScrepPlayers);
-type ScrepOverview = (Option<String>, ScrepPlayers, Option<u32>);
-
 impl ReplayService {
     pub fn tick(
         app: &mut App,
@@ -28,16 +24,39 @@ impl ReplayService {
         history: Option<&HistoryService<FileHistorySource>>,
         profile_history: &mut ProfileHistoryService,
     ) -> Result<(), ReplayError> {
-        rating_retry::run(app, cfg)?;
+        rating_retry::run(app, cfg, history, profile_history)?;
         screp_watch::run(app, cfg, history, profile_history)?;
         Ok(())
     }
 }
+
+// Completes the last game's rating capture once our rating has moved and
+// stores it with the matching profile history entry.
+fn finish_rating_capture(
+    self_profile: &mut SelfProfileState,
+    profile_history: &mut ProfileHistoryService,
+    rating_after: Option<u32>,
+) -> Option<GameCapture> {
+    let mut capture = self_profile.pending_rating.take()?;
+    let (Some(name), Some(gw)) = (self_profile.name.as_deref(), self_profile.gateway) else {
+        return None;
+    };
+    capture.rating_after = rating_after;
+    profile_history.set_awaiting_rating(None);
+    if let Err(err) =
+        profile_history.record_game_capture(&ProfileHistoryKey::new(name, gw), capture.clone())
+    {
+        tracing::error!(error = %err, "failed to record rating change");
+    }
+    Some(capture)
+}
 mod rating_retry {
-    use super::ReplayError;
+    use super::{finish_rating_capture, ReplayError};
     use crate::app::App;
     use crate::config::Config;
+    use crate::history::{FileHistorySource, HistoryService};
     use crate::overlay::OverlayService;
+    use crate::profile_history::ProfileHistoryService;
 
     fn schedule_retry(app: &mut App, cfg: &Config) {
         let retry = &mut app.self_profile.rating_retry;
@@ -56,7 +75,12 @@ mod rating_retry {
         retry.baseline = None;
     }
 
-    pub(super) fn run(app: &mut App, cfg: &Config) -> Result<(), ReplayError> {
+    pub(super) fn run(
+        app: &mut App,
+        cfg: &Config,
+        history: Option<&HistoryService<FileHistorySource>>,
+        profile_history: &mut ProfileHistoryService,
+    ) -> Result<(), ReplayError> {
         let retry = &mut app.self_profile.rating_retry;
         if retry.retries == 0 {
             return Ok(());
@@ -79,6 +103,20 @@ mod rating_retry {
                         app.self_profile.rating = new;
                         reset_retry(app);
                         OverlayService::write_rating(cfg, app)?;
+                        if let Some(capture) =
+                            finish_rating_capture(&mut app.self_profile, profile_history, new)
+                            && let Some(after) = new
+                            && let Some(record) = app
+                                .opponent
+                                .history
+                                .get_mut(&crate::race::lower_key(&capture.opponent))
+                            && record.set_rating_after(capture.approx_timestamp, after)
+                            && let Some(service) = history
+                        {
+                            service
+                                .save(&app.opponent.history)
+                                .map_err(ReplayError::History)?;
+                        }
                     } else {
                         schedule_retry(app, cfg);
                     }
@@ -96,17 +134,23 @@ mod rating_retry {
 
 mod screp_watch {
     use super::{
-        classify_short_game_outcome, parse_screp_duration_seconds, parse_screp_overview,
-        system_time_secs, ReplayError, ScrepOverview,
+        classify_short_game_outcome, finish_rating_capture, self_won, system_time_secs, ReplayError,
     };
+    use crate::api::ProfileStats;
     use crate::app::{App, DodgeCandidate};
+    use crate::build_order::builds_from_summary;
     use crate::config::Config;
-    use crate::history::{derive_wl_and_race, FileHistorySource, HistoryService, OpponentRecord};
+    use crate::history::{
+        derive_games, FileHistorySource, HistoryService, OpponentGame, OpponentRecord,
+    };
+    use crate::maps::normalize_map_name;
     use crate::overlay::OverlayService;
     use crate::profile_history::{
-        MatchOutcome, ProfileHistoryKey, ProfileHistoryService, StoredMatch,
+        GameApm, GameCapture, MatchOutcome, ProfileHistoryKey, ProfileHistoryService, StoredMatch,
     };
-    use crate::replay_io::run_screp_overview;
+    use crate::replay_download::{archive_replay, ReplayStorage};
+    use crate::replay_io::{read_replay_summary, ReplaySummary};
+    use crate::replay_layout::ReplayNameFields;
 
     pub(super) fn run(
         app: &mut App,
@@ -114,7 +158,7 @@ mod screp_watch {
         history: Option<&HistoryService<FileHistorySource>>,
         profile_history: &mut ProfileHistoryService,
     ) -> Result<(), ReplayError> {
-        if !app.detection.screp_available {
+        if !app.detection.replay_watch_enabled {
             return Ok(());
         }
 
@@ -152,36 +196,115 @@ mod screp_watch {
         app.overlays.opponent_waiting = true;
         OverlayService::write_opponent(cfg, app)?;
 
-        let Some((winner, players, duration)) = load_latest_overview(cfg)? else {
+        let Some(summary) = load_latest_summary(cfg) else {
             return Ok(());
         };
+        let duration_secs = summary.duration_secs();
+        let approx_timestamp = app.replay_watch.last_mtime.and_then(system_time_secs);
+        // Every finished game is archived, whatever its kind or result.
+        let archived_path = if cfg.archive_replays {
+            archive_last_replay(app, cfg, &summary, approx_timestamp)
+        } else {
+            None
+        };
         app.replay_watch.last_dodge_candidate = None;
-        if let Some(self_name) = &app.self_profile.name
-            && let Some(resolved) = resolve_opponent(&players, self_name)
+        if let Some(self_name) = app.self_profile.name.clone()
+            && let Some(resolved) = resolve_opponent(&summary, &self_name)
         {
-            if let Some(duration_secs) = duration
-                && duration_secs < 60
-            {
-                let outcome_guess = winner.as_deref().and_then(|winner_label| {
+            let mut replay_result: Option<MatchOutcome> = None;
+            if duration_secs < 60 {
+                let outcome_guess = summary.winner_team.and_then(|winner_team| {
                     classify_short_game_outcome(
-                        winner_label,
-                        self_name,
+                        winner_team,
                         resolved.self_team,
-                        &resolved.opponent_name,
                         resolved.opponent_team,
                     )
                 });
+                replay_result = outcome_guess;
                 app.replay_watch.last_dodge_candidate = Some(DodgeCandidate {
                     opponent: resolved.opponent_name.clone(),
                     outcome: outcome_guess,
-                    approx_timestamp: app.replay_watch.last_mtime.and_then(system_time_secs),
+                    approx_timestamp,
+                });
+            } else if let Some(won) = summary.winner_team.and_then(|winner_team| {
+                self_won(winner_team, resolved.self_team, resolved.opponent_team)
+            }) {
+                replay_result = Some(if won {
+                    MatchOutcome::Win
+                } else {
+                    MatchOutcome::Loss
                 });
             }
+
+            if let Some(timestamp) = approx_timestamp {
+                for build in builds_from_summary(&summary, timestamp) {
+                    app.builds.record(build);
+                }
+            }
+
+            if let Some(result) = replay_result {
+                app.session.record_game(
+                    approx_timestamp.unwrap_or_else(crate::session::now_secs),
+                    result,
+                    app.self_profile.rating,
+                );
+            }
+
+            if let (Some(_), Some(timestamp)) = (replay_result, approx_timestamp) {
+                let opponent_rating = app
+                    .opponent
+                    .toons_data
+                    .iter()
+                    .find(|(toon, _, _)| toon.eq_ignore_ascii_case(&resolved.opponent_name))
+                    .map(|(_, _, rating)| *rating);
+                let me = summary.player(&self_name);
+                let opponent = summary.player(&resolved.opponent_name);
+                let capture = GameCapture {
+                    opponent: resolved.opponent_name.clone(),
+                    approx_timestamp: timestamp,
+                    rating_before: app.self_profile.rating,
+                    rating_after: None,
+                    opponent_rating,
+                    apm: GameApm {
+                        apm: me.and_then(|p| p.apm),
+                        eapm: me.and_then(|p| p.eapm),
+                        opponent_apm: opponent.and_then(|p| p.apm),
+                        opponent_eapm: opponent.and_then(|p| p.eapm),
+                    },
+                    replay_path: archived_path.clone(),
+                };
+                // Stored now so APM is kept even if our rating never moves.
+                if let Some(gw) = app.self_profile.gateway {
+                    let key = ProfileHistoryKey::new(&self_name, gw);
+                    profile_history.set_awaiting_rating(Some((&key, &capture)));
+                    if let Err(err) = profile_history.record_game_capture(&key, capture.clone()) {
+                        tracing::error!(error = %err, "failed to record replay stats");
+                    }
+                }
+                app.self_profile.pending_rating = Some(capture);
+            }
+
+            let replay_game = match (replay_result, approx_timestamp) {
+                (Some(result), Some(timestamp)) => Some(OpponentGame {
+                    timestamp,
+                    self_race: resolved.self_race.clone(),
+                    opponent_race: resolved.opponent_race.clone(),
+                    result,
+                    duration_secs: Some(duration_secs),
+                    map: summary.map.clone(),
+                    rating_before: app.self_profile.rating,
+                    rating_after: None,
+                    replay_path: archived_path,
+                    provisional: true,
+                }),
+                _ => None,
+            };
             update_opponent_history(
                 app,
                 cfg,
                 &resolved.opponent_name,
                 resolved.opponent_race,
+                replay_game,
                 history,
                 profile_history,
             )?;
@@ -190,18 +313,51 @@ mod screp_watch {
         Ok(())
     }
 
-    fn load_latest_overview(cfg: &Config) -> Result<Option<ScrepOverview>, ReplayError> {
-        let text = match run_screp_overview(cfg, &cfg.last_replay_path) {
-            Ok(text) => text,
+    // LastReplay.rep is overwritten by the next game, so keep a copy in the
+    // replay library. Games we did not play in are named from the first
+    // player's side.
+    fn archive_last_replay(
+        app: &App,
+        cfg: &Config,
+        summary: &ReplaySummary,
+        approx_timestamp: Option<u64>,
+    ) -> Option<String> {
+        let self_name = app
+            .self_profile
+            .name
+            .as_deref()
+            .filter(|name| summary.player(name).is_some());
+        let perspective = self_name.or_else(|| summary.players.first().map(|p| p.name.as_str()))?;
+        let mut fields = ReplayNameFields::from_summary(summary, perspective, approx_timestamp)?;
+        if self_name.is_some() {
+            fields.gateway = app.self_profile.gateway;
+            fields.rating = app.self_profile.rating.and_then(|r| i32::try_from(r).ok());
+        }
+        let storage = ReplayStorage::new(cfg.replay_library_root.clone());
+        match archive_replay(
+            &storage,
+            &cfg.replay_layout,
+            &cfg.last_replay_path,
+            summary,
+            perspective,
+            &fields,
+        ) {
+            Ok(path) => Some(path.to_string_lossy().into_owned()),
             Err(err) => {
-                tracing::error!(error = %err, "failed to run screp on last replay");
-                return Ok(None);
+                tracing::error!(error = %err, "failed to archive last replay");
+                None
             }
-        };
+        }
+    }
 
-        let parsed = parse_screp_overview(&text);
-        let duration = parse_screp_duration_seconds(&text);
-        Ok(Some((parsed.0, parsed.1, duration)))
+    fn load_latest_summary(cfg: &Config) -> Option<ReplaySummary> {
+        match read_replay_summary(cfg, &cfg.last_replay_path) {
+            Ok(summary) => Some(summary),
+            Err(err) => {
+                tracing::error!(error = %err, "failed to read last replay");
+                None
+            }
+        }
     }
 
     struct ResolvedOpponent {
@@ -209,26 +365,28 @@ mod screp_watch {
         opponent_race: Option<String>,
         opponent_team: u8,
         self_team: u8,
+        self_race: Option<String>,
     }
 
-    fn resolve_opponent(
-        players: &[(u8, Option<String>, String)],
-        self_name: &str,
-    ) -> Option<ResolvedOpponent> {
-        let self_team = players
-            .iter()
-            .find(|(_, _, name)| name.eq_ignore_ascii_case(self_name))?
-            .0;
-
-        let (team, race, name) = players
-            .iter()
-            .find(|(team, _, _)| *team != self_team && *team != 0)?;
+    // Only 1v1 games feed opponent records, so team, observed and custom
+    // games resolve to no opponent.
+    fn resolve_opponent(summary: &ReplaySummary, self_name: &str) -> Option<ResolvedOpponent> {
+        let sides = summary.sides(self_name);
+        let Some((me, opponent)) = sides.one_vs_one() else {
+            tracing::info!(
+                kind = sides.kind.label(),
+                players = summary.players.len(),
+                "last replay is not a 1v1; skipping opponent history"
+            );
+            return None;
+        };
 
         Some(ResolvedOpponent {
-            opponent_name: name.clone(),
-            opponent_race: race.clone(),
-            opponent_team: *team,
-            self_team,
+            opponent_name: opponent.name.clone(),
+            opponent_race: opponent.race.clone(),
+            opponent_team: opponent.team,
+            self_team: me.team,
+            self_race: me.race.clone(),
         })
     }
 
@@ -237,6 +395,7 @@ mod screp_watch {
         cfg: &Config,
         opp_name: &str,
         opp_race: Option<String>,
+        mut replay_game: Option<OpponentGame>,
         history: Option<&HistoryService<FileHistorySource>>,
         profile_history: &mut ProfileHistoryService,
     ) -> Result<(), ReplayError> {
@@ -267,7 +426,7 @@ mod screp_watch {
             entry.set_race_if_unknown(opp_race.as_deref());
         }
 
-        let mut history_update: Option<(u32, u32, Option<u64>, Option<String>)> = None;
+        let mut api_games: Vec<OpponentGame> = Vec::new();
         let mut refresh_rating_overlay = false;
 
         enum RatingRetryUpdate {
@@ -277,9 +436,7 @@ mod screp_watch {
 
         let mut new_profile_rating: Option<Option<u32>> = None;
         let mut rating_retry_update: Option<RatingRetryUpdate> = None;
-        let mut main_race_update: Option<Option<String>> = None;
-        let mut matchup_update: Option<Vec<String>> = None;
-        let mut dodge_counts_update: Option<(u32, u32)> = None;
+        let mut stats_update: Option<ProfileStats> = None;
         let mut clear_dodge_candidate = false;
         let dodge_candidate = app.replay_watch.last_dodge_candidate.clone();
 
@@ -294,10 +451,13 @@ mod screp_watch {
                     let new = api.compute_rating_for_name(&info, &name);
                     new_profile_rating = Some(new);
                     refresh_rating_overlay = true;
+                    if new != old {
+                        finish_rating_capture(&mut app.self_profile, profile_history, new);
+                    }
 
                     match api.get_scr_profile(&name, gw) {
                         Ok(profile) => {
-                            history_update = Some(derive_wl_and_race(&profile, &name, opp_name));
+                            api_games = derive_games(&profile, &name, opp_name);
 
                             let history_key = ProfileHistoryKey::new(&name, gw);
 
@@ -316,17 +476,14 @@ mod screp_watch {
                                 }
                             }
 
-                            let (mr, lines, _results, self_dodged, opp_dodged) = api
-                                .profile_stats_last100(
-                                    &profile,
-                                    &name,
-                                    Some(profile_history),
-                                    Some(&history_key),
-                                    Some(&app.opponent.history),
-                                );
-                            main_race_update = Some(mr);
-                            matchup_update = Some(lines);
-                            dodge_counts_update = Some((self_dodged, opp_dodged));
+                            stats_update = Some(api.profile_stats(
+                                &profile,
+                                &name,
+                                Some(profile_history),
+                                Some(&history_key),
+                                Some(&app.opponent.history),
+                                app.self_profile.stats_window,
+                            ));
                         }
                         Err(err) => {
                             tracing::error!(
@@ -336,6 +493,12 @@ mod screp_watch {
                         }
                     }
 
+                    if new != old
+                        && let Some(game) = replay_game.as_mut()
+                    {
+                        game.rating_after = new;
+                    }
+
                     if new == old {
                         rating_retry_update = Some(RatingRetryUpdate::Schedule { baseline: old });
                     } else {
@@ -377,30 +540,21 @@ mod screp_watch {
             }
         }
 
-        if let Some(mr) = main_race_update {
-            app.self_profile.main_race = mr;
-        }
-
-        if let Some(lines) = matchup_update {
-            app.self_profile.matchups = lines;
-        }
-
-        if let Some((self_dodged, opp_dodged)) = dodge_counts_update {
-            app.self_profile.self_dodged = self_dodged;
-            app.self_profile.opponent_dodged = opp_dodged;
+        if let Some(stats) = stats_update {
+            app.self_profile.apply_stats(stats);
         }
 
         if clear_dodge_candidate {
             app.replay_watch.last_dodge_candidate = None;
         }
 
-        if let Some((wins, losses, ts, race)) = history_update
-            && let Some(entry) = app.opponent.history.get_mut(&key)
-        {
-            entry.wins = wins;
-            entry.losses = losses;
-            if let Some(latest) = ts {
-                entry.last_match_ts = Some(latest);
+        if let Some(entry) = app.opponent.history.get_mut(&key) {
+            let race = api_games.first().and_then(|g| g.opponent_race.clone());
+            for game in api_games {
+                entry.merge_game(game);
+            }
+            if let Some(game) = replay_game {
+                entry.merge_game(game);
             }
             entry.set_race_if_unknown(race.as_deref());
         }
@@ -487,6 +641,9 @@ mod screp_watch {
                     opponent_race: actual[oi].attributes.race.clone(),
                     main_race: actual[mi].attributes.race.clone(),
                     result: resolved_outcome,
+                    season: crate::api::SeasonSpans::new(profile, self_name).season_at(ts),
+                    map: normalize_map_name(&g.attributes.map_name),
+                    ..Default::default()
                 },
                 resolved_outcome,
             ));
@@ -496,134 +653,34 @@ mod screp_watch {
     }
 }
 
-pub fn parse_screp_overview(text: &str) -> ScrepParsed {
-    let mut winner: Option<String> = None;
-    let mut in_players = false;
-    let mut players: Vec<(u8, Option<String>, String)> = Vec::new();
-    for line in text.lines() {
-        let l = line.trim_end();
-        if l.to_ascii_lowercase().starts_with("winner")
-            && let Some((_, v)) = l.split_once(':')
-        {
-            winner = Some(v.trim().to_string());
-        }
-        if l.starts_with("Team  R  APM") {
-            in_players = true;
-            continue;
-        }
-        if in_players {
-            if l.trim().is_empty() {
-                continue;
-            }
-            let parts: Vec<&str> = l.split_whitespace().collect();
-            if parts.len() >= 6 {
-                let Ok(team) = parts[0].parse::<u8>() else {
-                    continue;
-                };
-                let r = parts[1];
-                let race = match r.to_ascii_uppercase().as_str() {
-                    "P" => Some("Protoss".to_string()),
-                    "T" => Some("Terran".to_string()),
-                    "Z" => Some("Zerg".to_string()),
-                    _ => None,
-                };
-                let name = parts[5..].join(" ");
-                players.push((team, race, name));
-            }
-        }
-    }
-    (winner, players)
-}
-
-pub fn parse_screp_duration_seconds(text: &str) -> Option<u32> {
-    for line in text.lines() {
-        let lower = line.to_ascii_lowercase();
-        if !lower.contains("length") {
-            continue;
-        }
-        let value = line.split_once(':').map(|(_, rest)| rest).unwrap_or(line);
-        let trimmed = value.trim();
-        let parts: Vec<&str> = trimmed.split(':').collect();
-        if parts.len() < 2 {
-            continue;
-        }
-        let hours_offset = if parts.len() == 3 { 1 } else { 0 };
-        let minutes_idx = if parts.len() == 3 { 1 } else { 0 };
-        let seconds_idx = if parts.len() == 3 { 2 } else { 1 };
-        let hours = if hours_offset == 1 {
-            parts[0].trim().parse::<u32>().ok().unwrap_or(0)
-        } else {
-            0
-        };
-        if let (Ok(minutes), Ok(seconds)) = (
-            parts[minutes_idx].trim().parse::<u32>(),
-            parts[seconds_idx]
-                .split_whitespace()
-                .next()
-                .unwrap_or("0")
-                .parse::<u32>(),
-        ) {
-            return Some(hours * 3600 + minutes * 60 + seconds);
-        }
-    }
-    None
-}
-
 pub fn system_time_secs(st: SystemTime) -> Option<u64> {
     st.duration_since(std::time::UNIX_EPOCH)
         .ok()
         .map(|d| d.as_secs())
 }
 
-fn classify_short_game_outcome(
-    winner_label: &str,
-    self_name: &str,
+pub(crate) fn classify_short_game_outcome(
+    winner_team: u8,
     self_team: u8,
-    opponent_name: &str,
     opponent_team: u8,
 ) -> Option<MatchOutcome> {
-    let trimmed = winner_label.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
-
-    let winner_lower = trimmed.to_ascii_lowercase();
-    let self_lower = self_name.trim().to_ascii_lowercase();
-    let opp_lower = opponent_name.trim().to_ascii_lowercase();
-
-    if !self_lower.is_empty() && winner_lower.contains(&self_lower) {
-        return Some(MatchOutcome::OpponentDodged);
-    }
-    if !opp_lower.is_empty() && winner_lower.contains(&opp_lower) {
-        return Some(MatchOutcome::SelfDodged);
-    }
-
-    if let Some(team) = winner_team_number(&winner_lower) {
-        if team == self_team {
-            return Some(MatchOutcome::OpponentDodged);
-        }
-        if team == opponent_team {
-            return Some(MatchOutcome::SelfDodged);
+    self_won(winner_team, self_team, opponent_team).map(|won| {
+        if won {
+            MatchOutcome::OpponentDodged
+        } else {
+            MatchOutcome::SelfDodged
         }
-    }
-
-    None
+    })
 }
 
-fn winner_team_number(label: &str) -> Option<u8> {
-    let mut lower = label.to_ascii_lowercase();
-    if let Some(idx) = lower.find("team") {
-        lower.drain(..idx + 4);
-        let digits: String = lower
-            .chars()
-            .skip_while(|c| !c.is_ascii_digit())
-            .take_while(|c| c.is_ascii_digit())
-            .collect();
-        if !digits.is_empty() {
-            return digits.parse().ok();
-        }
+pub(crate) fn self_won(winner_team: u8, self_team: u8, opponent_team: u8) -> Option<bool> {
+    if winner_team == self_team {
+        Some(true)
+    } else if winner_team == opponent_team {
+        Some(false)
+    } else {
+        None
     }
-    None
 }
 
 #[cfg(test)]
@@ -631,66 +688,12 @@ mod tests {
     use super::*;
 
     #[test]
-    fn parse_screp_overview_extracts_winner_and_players() {
-        let input = "Winner: Alice\nTeam  R  APM\n1 P 100 0 0 Alice\n2 T 120 0 0 Bob\n";
-
-        let (winner, players) = parse_screp_overview(input);
-
-        assert_eq!(winner.as_deref(), Some("Alice"));
-        assert_eq!(players.len(), 2);
-        assert_eq!(
-            players[0],
-            (1, Some("Protoss".to_string()), "Alice".to_string())
-        );
-        assert_eq!(
-            players[1],
-            (2, Some("Terran".to_string()), "Bob".to_string())
-        );
-    }
-
-    #[test]
-    fn parse_screp_overview_skips_invalid_team_rows() {
-        let input = "Winner: Alice\nTeam  R  APM\nX P 100 0 0 Alice\n2 Z 120 0 0 Bob\n";
-
-        let (_winner, players) = parse_screp_overview(input);
-
-        assert_eq!(players.len(), 1);
-        assert_eq!(players[0], (2, Some("Zerg".to_string()), "Bob".to_string()));
-    }
-
-    #[test]
-    fn parse_screp_duration_supports_mm_ss_and_hh_mm_ss() {
-        assert_eq!(parse_screp_duration_seconds("Length: 1:23"), Some(83));
-        assert_eq!(parse_screp_duration_seconds("Length: 0:05:12"), Some(312));
-    }
-
-    #[test]
-    fn parse_screp_duration_ignores_invalid_lines() {
-        assert_eq!(parse_screp_duration_seconds("Length: invalid"), None);
-        assert_eq!(parse_screp_duration_seconds("Something else"), None);
-    }
-
-    #[test]
-    fn classify_short_game_outcome_prefers_name_matches() {
-        let self_win = classify_short_game_outcome("Alice wins", "Alice", 1, "Bob", 2);
-        let opp_win = classify_short_game_outcome("Bob wins", "Alice", 1, "Bob", 2);
-
-        assert_eq!(self_win, Some(MatchOutcome::OpponentDodged));
-        assert_eq!(opp_win, Some(MatchOutcome::SelfDodged));
-    }
-
-    #[test]
-    fn classify_short_game_outcome_falls_back_to_team_number() {
-        let self_team = classify_short_game_outcome("Team 1", "Alice", 1, "Bob", 2);
-        let opp_team = classify_short_game_outcome("Team 2", "Alice", 1, "Bob", 2);
+    fn classify_short_game_outcome_uses_winner_team() {
+        let self_team = classify_short_game_outcome(1, 1, 2);
+        let opp_team = classify_short_game_outcome(2, 1, 2);
 
         assert_eq!(self_team, Some(MatchOutcome::OpponentDodged));
         assert_eq!(opp_team, Some(MatchOutcome::SelfDodged));
-    }
-
-    #[test]
-    fn winner_team_number_extracts_digits_after_team_keyword() {
-        assert_eq!(winner_team_number("Team 12 wins"), Some(12));
-        assert_eq!(winner_team_number("no team info"), None);
+        assert_eq!(classify_short_game_outcome(3, 1, 2), None);
     }
 }
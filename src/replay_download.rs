@@ -1,21 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use chrono::{DateTime, Utc};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::api::ApiHandle;
+use crate::build_order::{builds_from_summary, PlayerBuild};
 use crate::config::Config;
 use crate::error::render_error_message;
-use crate::replay_io::{download_replay, run_screp_overview, sanitize_component};
+use crate::replay_filter::{
+    contains_ignore_case, parse_date, parse_date_end, parse_duration_range, ResultFilter,
+};
+use crate::replay_io::{
+    download_replay, is_network_error, read_replay_summary, sanitize_component, ReplaySummary,
+};
+use crate::replay_layout::{ReplayLayout, ReplayNameFields};
+use crate::replay_manifest::{content_hash, ManifestEntry, ReplayManifest};
+
+// Library folder opponent scouting downloads are kept under.
+pub const SCOUTING_FOLDER: &str = "Scouting";
 
 pub struct ReplayStorage {
     root: PathBuf,
@@ -34,12 +47,17 @@ impl ReplayStorage {
         self.bwtools_root().join(".meta").join("manifest.json")
     }
 
-    pub fn profile_dir(&self, profile: &str) -> PathBuf {
-        self.bwtools_root().join(profile)
+    pub fn jobs_dir(&self) -> PathBuf {
+        self.bwtools_root().join(".meta").join("jobs")
     }
 
-    pub fn matchup_dir(&self, profile: &str, matchup: &str) -> PathBuf {
-        self.profile_dir(profile).join(matchup)
+    pub fn scouting_dir(&self) -> PathBuf {
+        self.bwtools_root().join(SCOUTING_FOLDER)
+    }
+
+    // Where downloads are written until they are parsed and named.
+    pub fn partial_dir(&self) -> PathBuf {
+        self.bwtools_root().join(".meta").join("partial")
     }
 
     pub fn ensure_base_dirs(&self) -> io::Result<()> {
@@ -48,65 +66,603 @@ impl ReplayStorage {
         fs::create_dir_all(meta_dir)
     }
 
-    pub fn ensure_matchup_dir(&self, profile: &str, matchup: &str) -> io::Result<PathBuf> {
-        let dir = self.matchup_dir(profile, matchup);
-        fs::create_dir_all(&dir)?;
-        Ok(dir)
+    // Creates the folders for `relative` (as laid out by a `ReplayLayout`) and
+    // returns a free path for it.
+    pub fn prepare_path(&self, relative: &Path) -> io::Result<PathBuf> {
+        let path = self.bwtools_root().join(relative);
+        let dir = path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir)?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(unique_path(dir, &file_name))
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplayDownloadRequest {
     pub toon: String,
     pub gateway: u16,
     pub matchup: Option<String>,
     pub limit: usize,
     pub alias: Option<String>,
+    #[serde(default)]
+    pub filter: DownloadFilter,
+    #[serde(default)]
+    pub origin: RequestOrigin,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
-pub struct ReplayManifest {
-    pub entries: HashMap<String, ManifestEntry>,
+// What queued a download, so its results can be routed back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RequestOrigin {
+    #[default]
+    Manual,
+    Watch,
+    Scout,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ManifestEntry {
-    pub path: String,
-    pub saved_at: u64,
+// Replays this short are dropped unless the filter sets its own minimum.
+const SHORT_GAME_SECS: u32 = 120;
+
+// Which candidates a download keeps. Unset fields keep everything; the result
+// is judged for the requested toon.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadFilter {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub result: ResultFilter,
+    pub opponent: Option<String>,
+    pub map: Option<String>,
+    pub min_secs: Option<u32>,
+    pub max_secs: Option<u32>,
+    pub min_opponent_rating: Option<i32>,
 }
 
-impl ReplayManifest {
-    pub fn load(path: &Path) -> Self {
-        fs::read(path)
-            .ok()
-            .and_then(|bytes| serde_json::from_slice::<ReplayManifest>(&bytes).ok())
-            .unwrap_or_default()
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FilterReason {
+    OutsideDates,
+    Opponent,
+    Map,
+    OpponentRating,
+    TooShort,
+    TooLong,
+    Result,
+}
+
+impl FilterReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            FilterReason::OutsideDates => "outside date range",
+            FilterReason::Opponent => "other opponent",
+            FilterReason::Map => "other map",
+            FilterReason::OpponentRating => "opponent rating too low",
+            FilterReason::TooShort => "too short",
+            FilterReason::TooLong => "too long",
+            FilterReason::Result => "other result",
+        }
     }
+}
 
-    pub fn save(&self, path: &Path) -> io::Result<()> {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+impl DownloadFilter {
+    // A `played_at` of 0 is unknown and passes; the replay is checked again
+    // once downloaded.
+    fn check_date(&self, played_at: u64) -> Result<(), FilterReason> {
+        if played_at == 0 {
+            return Ok(());
+        }
+        if self.from.is_some_and(|from| played_at < from)
+            || self.to.is_some_and(|to| played_at >= to)
+        {
+            return Err(FilterReason::OutsideDates);
         }
-        let data = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
-        fs::write(path, data)
+        Ok(())
+    }
+
+    // Checks the match detail allows before the replay is downloaded.
+    // `opponents` pairs each opponent toon with its rating going in.
+    fn check_listing(
+        &self,
+        map: Option<&str>,
+        opponents: &[(String, i32)],
+    ) -> Result<(), FilterReason> {
+        if !opponents.is_empty() && !self.opponent_matches(opponents.iter().map(|(n, _)| n)) {
+            return Err(FilterReason::Opponent);
+        }
+        if let Some(map) = map
+            && !self.map_matches(Some(map))
+        {
+            return Err(FilterReason::Map);
+        }
+        if let Some(min) = self.min_opponent_rating
+            && !opponents.iter().any(|(_, rating)| *rating >= min)
+        {
+            return Err(FilterReason::OpponentRating);
+        }
+        Ok(())
+    }
+
+    fn check_replay(&self, summary: &ReplaySummary, toon: &str) -> Result<(), FilterReason> {
+        let duration = summary.duration_secs();
+        let too_short = match self.min_secs {
+            Some(min) => duration < min,
+            None => duration <= SHORT_GAME_SECS,
+        };
+        if too_short {
+            return Err(FilterReason::TooShort);
+        }
+        if self.max_secs.is_some_and(|max| duration > max) {
+            return Err(FilterReason::TooLong);
+        }
+        let sides = summary.sides(toon);
+        if !self.opponent_matches(sides.opponents.iter().map(|p| &p.name)) {
+            return Err(FilterReason::Opponent);
+        }
+        if !self.map_matches(summary.map.as_deref()) {
+            return Err(FilterReason::Map);
+        }
+        if self.result != ResultFilter::Any {
+            let won = sides
+                .me
+                .zip(summary.winner_team)
+                .map(|(me, winner)| me.team == winner);
+            if won != Some(self.result == ResultFilter::Win) {
+                return Err(FilterReason::Result);
+            }
+        }
+        Ok(())
+    }
+
+    fn opponent_matches<'a>(&self, mut names: impl Iterator<Item = &'a String>) -> bool {
+        match self.opponent.as_deref() {
+            Some(wanted) => names.any(|name| name.trim().eq_ignore_ascii_case(wanted.trim())),
+            None => true,
+        }
+    }
+
+    fn map_matches(&self, map: Option<&str>) -> bool {
+        match self.map.as_deref() {
+            Some(wanted) => map.is_some_and(|map| contains_ignore_case(map, wanted)),
+            None => true,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
     }
 }
 
+// Filter fields as typed in the download form or passed on the command line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadFilterInput {
+    pub from: String,
+    pub to: String,
+    pub result: ResultFilter,
+    pub opponent: String,
+    pub map: String,
+    // Minutes, as "10-20", "10-" or "-20".
+    pub duration: String,
+    pub min_rating: String,
+}
+
+impl DownloadFilterInput {
+    pub fn parse(&self) -> Result<DownloadFilter> {
+        let text = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        let date = |raw: &str, parse: fn(&str) -> Option<u64>| match text(raw) {
+            Some(raw) => parse(&raw)
+                .map(Some)
+                .with_context(|| format!("invalid date {raw}, expected YYYY-MM-DD")),
+            None => Ok(None),
+        };
+        let (min_secs, max_secs) = match text(&self.duration) {
+            Some(raw) => match parse_duration_range(&raw) {
+                (None, None) => bail!("invalid duration {raw}, expected minutes like 10-20"),
+                range => range,
+            },
+            None => (None, None),
+        };
+        let min_opponent_rating = match text(&self.min_rating) {
+            Some(raw) => Some(
+                raw.parse::<i32>()
+                    .with_context(|| format!("invalid rating {raw}"))?,
+            ),
+            None => None,
+        };
+        Ok(DownloadFilter {
+            from: date(&self.from, parse_date)?,
+            to: date(&self.to, parse_date_end)?,
+            result: self.result,
+            opponent: text(&self.opponent),
+            map: text(&self.map),
+            min_secs,
+            max_secs,
+            min_opponent_rating,
+        })
+    }
+}
+
+// On-disk record of a queued or running download, kept under
+// `.meta/jobs/` until the job ends so an interrupted job resumes on the next
+// start. Only the runtime writes it while queued and only the job once running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadJournal {
+    pub request: ReplayDownloadRequest,
+    #[serde(default)]
+    pub saved: usize,
+    // Links of candidates already handled, so a resumed job skips them.
+    #[serde(default)]
+    pub done: Vec<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl DownloadJournal {
+    pub fn create(storage: &ReplayStorage, request: ReplayDownloadRequest) -> Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let journal = Self {
+            request,
+            saved: 0,
+            done: Vec::new(),
+            path: storage.jobs_dir().join(format!("{nanos}.json")),
+        };
+        journal.save()?;
+        Ok(journal)
+    }
+
+    // Journals left by jobs that never finished, oldest first.
+    pub fn load_all(storage: &ReplayStorage) -> Vec<Self> {
+        let Ok(read_dir) = fs::read_dir(storage.jobs_dir()) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = read_dir
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let bytes = fs::read(&path).ok()?;
+                match serde_json::from_slice::<DownloadJournal>(&bytes) {
+                    Ok(mut journal) => {
+                        journal.path = path;
+                        Some(journal)
+                    }
+                    Err(err) => {
+                        tracing::warn!(error = %err, path = %path.display(), "dropping unreadable download journal");
+                        let _ = fs::remove_file(&path);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create journal directory {}", parent.display()))?;
+        }
+        let data = serde_json::to_vec_pretty(self).context("serialize download journal")?;
+        fs::write(&self.path, data)
+            .with_context(|| format!("write download journal {}", self.path.display()))
+    }
+
+    pub fn remove(&self) {
+        if let Err(err) = fs::remove_file(&self.path)
+            && err.kind() != io::ErrorKind::NotFound
+        {
+            tracing::warn!(error = %err, path = %self.path.display(), "failed to remove download journal");
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.request.limit.saturating_sub(self.saved)
+    }
+}
+
+// Removes partial `.tmp-*.rep` downloads under `dir`, returning how many went.
+pub fn clean_temp_files(dir: &Path) -> usize {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            removed += clean_temp_files(&path);
+        } else if name.starts_with(".tmp-")
+            && name.ends_with(".rep")
+            && fs::remove_file(&path).is_ok()
+        {
+            removed += 1;
+        }
+    }
+    removed
+}
+
 #[derive(Debug, Default)]
 pub struct ReplayDownloadSummary {
     pub requested: usize,
     pub attempted: usize,
     pub saved: usize,
     pub skipped_existing: usize,
-    pub filtered_short: usize,
+    pub filtered: BTreeMap<FilterReason, usize>,
     pub errors: Vec<String>,
     pub saved_paths: Vec<PathBuf>,
+    pub builds: Vec<PlayerBuild>,
+    pub cancelled: bool,
+    // Start time of the newest game handled without an error.
+    pub newest_played_at: Option<u64>,
+    // The job ran out of history to look at rather than stopping at its
+    // limit.
+    pub reached_end: bool,
+    // The job stopped before paging any history; its journal is kept so it
+    // runs again on the next start.
+    pub setup_failed: bool,
 }
 
 impl ReplayDownloadSummary {
     fn record_error(&mut self, err: anyhow::Error) {
         self.errors.push(render_error_message(&err));
     }
+
+    fn note_played_at(&mut self, played_at: u64) {
+        self.newest_played_at = self.newest_played_at.max(Some(played_at));
+    }
+
+    pub fn filtered_total(&self) -> usize {
+        self.filtered.values().sum()
+    }
+
+    // "3 too short, 1 other map", or "none".
+    pub fn filtered_label(&self) -> String {
+        if self.filtered.is_empty() {
+            return "none".to_string();
+        }
+        self.filtered
+            .iter()
+            .map(|(reason, count)| format!("{count} {}", reason.label()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl std::fmt::Display for ReplayDownloadSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Requested: {}  Saved: {}  Skipped existing: {}",
+            self.requested, self.saved, self.skipped_existing
+        )?;
+        write!(f, "Filtered: {}", self.filtered_label())?;
+        for err in &self.errors {
+            write!(f, "\nError: {err}")?;
+        }
+        if self.cancelled {
+            write!(f, "\nCancelled")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayDownloadProgress {
+    pub target: usize,
+    pub attempted: usize,
+    pub saved: usize,
+    // History pages fetched so far and the season being paged.
+    pub pages: usize,
+    pub season: Option<u32>,
+}
+
+// Where a single replay is in the download pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayStage {
+    FetchingDetail,
+    Downloading,
+    Parsing,
+    Saved(PathBuf),
+    Skipped(&'static str),
+    Failed(String),
+}
+
+impl ReplayStage {
+    pub fn label(&self) -> String {
+        match self {
+            ReplayStage::FetchingDetail => "fetching detail".to_string(),
+            ReplayStage::Downloading => "downloading".to_string(),
+            ReplayStage::Parsing => "parsing".to_string(),
+            ReplayStage::Saved(path) => format!(
+                "saved {}",
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            ),
+            ReplayStage::Skipped(reason) => format!("skipped ({reason})"),
+            ReplayStage::Failed(err) => format!("failed: {err}"),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(
+            self,
+            ReplayStage::Saved(_) | ReplayStage::Skipped(_) | ReplayStage::Failed(_)
+        )
+    }
+}
+
+pub enum ReplayDownloadEvent {
+    Progress(ReplayDownloadProgress),
+    Replay { label: String, stage: ReplayStage },
+    Finished(ReplayDownloadSummary),
+}
+
+// Matches requested per page of matchmaker history.
+const HISTORY_PAGE_SIZE: u32 = 50;
+
+// A ladder game whose replay may be downloaded, either embedded in the
+// profile or found while paging through matchmaker history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReplayCandidate {
+    link: String,
+    game_id: String,
+    create_time: u64,
+    // Comma separated race names, as in the profile's replay attributes.
+    races: String,
+}
+
+impl ReplayCandidate {
+    fn label(&self) -> String {
+        let date = DateTime::<Utc>::from_timestamp(self.create_time as i64, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| self.link.clone());
+        format!("{date} {}", self.races)
+    }
+}
+
+impl From<bw_web_api_rs::models::common::Replay> for ReplayCandidate {
+    fn from(replay: bw_web_api_rs::models::common::Replay) -> Self {
+        Self {
+            link: replay.link,
+            game_id: replay.attributes.game_id,
+            create_time: u64::from(replay.create_time),
+            races: replay.attributes.replay_player_races,
+        }
+    }
+}
+
+// Candidates for a toon, newest first: the replays embedded in its profile,
+// then its older ladder games paged season by season.
+struct ReplayPager {
+    toon: String,
+    gateway: u16,
+    matchup: Option<(char, char)>,
+    season: u32,
+    offset: u32,
+    pages: usize,
+    // Paging stops after a history page played entirely before this.
+    from: Option<u64>,
+    buffered: VecDeque<ReplayCandidate>,
+    seen: HashSet<String>,
+    // Links a resumed job already handled; they still count as history.
+    done: HashSet<String>,
+}
+
+impl ReplayPager {
+    fn new(request: &ReplayDownloadRequest, current_season: u32) -> Self {
+        Self {
+            toon: request.toon.clone(),
+            gateway: request.gateway,
+            matchup: request.matchup.as_deref().and_then(parse_matchup_filter),
+            season: current_season,
+            offset: 0,
+            pages: 0,
+            from: request.filter.from,
+            buffered: VecDeque::new(),
+            seen: HashSet::new(),
+            done: HashSet::new(),
+        }
+    }
+
+    // Queues candidates that pass the matchup filter and were neither seen
+    // by this run nor done by an earlier one, returning how many were new to
+    // this run.
+    fn push(&mut self, mut candidates: Vec<ReplayCandidate>) -> usize {
+        candidates.sort_by(|a, b| b.create_time.cmp(&a.create_time));
+        let mut fresh = 0;
+        for candidate in candidates {
+            if !self.seen.insert(candidate.link.clone()) {
+                continue;
+            }
+            fresh += 1;
+            if !self.done.contains(&candidate.link)
+                && self
+                    .matchup
+                    .is_none_or(|filter| replay_matches(&candidate.races, filter))
+            {
+                self.buffered.push_back(candidate);
+            }
+        }
+        fresh
+    }
+
+    fn next(&mut self, api: &ApiHandle) -> Result<Option<ReplayCandidate>> {
+        loop {
+            if let Some(candidate) = self.buffered.pop_front() {
+                return Ok(Some(candidate));
+            }
+            if self.season == 0 {
+                return Ok(None);
+            }
+            let page = api
+                .get_matchmaker_gameinfo(
+                    &self.toon,
+                    self.gateway,
+                    self.season,
+                    self.offset,
+                    HISTORY_PAGE_SIZE,
+                )
+                .with_context(|| {
+                    format!(
+                        "failed to load season {} match history for {}",
+                        self.season, self.toon
+                    )
+                })?;
+            self.pages += 1;
+            let candidates: Vec<ReplayCandidate> = page
+                .0
+                .into_iter()
+                .flatten()
+                .map(|(link, game)| candidate_from_match(link, &game))
+                .collect();
+            let count = candidates.len() as u32;
+            // History is paged newest first, so once a whole page is older
+            // than `from` nothing further can match. Its games are still
+            // queued so they are counted as filtered.
+            let too_old = self.from.is_some_and(|from| {
+                count > 0
+                    && candidates
+                        .iter()
+                        .all(|c| c.create_time != 0 && c.create_time < from)
+            });
+            if too_old {
+                self.season = 0;
+                self.push(candidates);
+            } else if self.push(candidates) == 0 {
+                // An empty page, or one with nothing new, ends the season.
+                self.season -= 1;
+                self.offset = 0;
+            } else {
+                self.offset += count;
+            }
+        }
+    }
+}
+
+fn candidate_from_match(
+    link: String,
+    game: &bw_web_api_rs::models::matchmaker_game_info::Match,
+) -> ReplayCandidate {
+    let races = game
+        .players
+        .iter()
+        .flat_map(|players| players.values())
+        .map(|player| player.info_attributes.race.clone())
+        .collect::<Vec<_>>()
+        .join(",");
+    ReplayCandidate {
+        game_id: String::new(),
+        create_time: game.match_created.trim().parse().unwrap_or_default(),
+        races,
+        link,
+    }
 }
 
 pub struct ReplayDownloadJob {
@@ -128,62 +684,190 @@ impl ReplayDownloadJob {
         })
     }
 
-    pub fn run(self) -> ReplayDownloadSummary {
+    // Downloads until the requested count is saved, history runs out or
+    // `cancel` is set, reporting each step through `emit`. Up to
+    // `download_workers` replays are fetched at once while this thread pages
+    // history and records progress in `journal`.
+    pub fn run(
+        self,
+        journal: &mut DownloadJournal,
+        cancel: &AtomicBool,
+        mut emit: impl FnMut(ReplayDownloadEvent),
+    ) -> ReplayDownloadSummary {
         let mut summary = ReplayDownloadSummary::default();
         if let Err(err) = self.storage.ensure_base_dirs() {
             summary.record_error(anyhow!(err).context("failed to ensure replay directories"));
+            summary.setup_failed = true;
             return summary;
         }
 
         let manifest_path = self.storage.manifest_path();
-        let mut manifest = ReplayManifest::load(&manifest_path);
+        let manifest = Mutex::new(ReplayManifest::load(&manifest_path));
 
         let profile = match self.load_profile() {
             Ok(profile) => profile,
             Err(err) => {
                 summary.record_error(err);
+                summary.setup_failed = true;
                 return summary;
             }
         };
-
-        let filtered = self.filtered_replays(profile);
-
-        summary.requested = filtered.len();
-        if summary.requested == 0 {
-            return summary;
-        }
-
         let ctx = match self.prepare_context() {
             Ok(ctx) => ctx,
             Err(err) => {
                 summary.record_error(err);
+                summary.setup_failed = true;
                 return summary;
             }
         };
 
-        for replay in filtered {
-            summary.attempted += 1;
-            match self.process_replay(&ctx, &mut manifest, &replay) {
-                Ok(Some(path)) => {
-                    summary.saved += 1;
-                    summary.saved_paths.push(path);
+        let mut pager = ReplayPager::new(&self.request, profile.matchmaked_current_season);
+        pager.done.extend(journal.done.iter().cloned());
+        pager.push(
+            profile
+                .replays
+                .into_iter()
+                .map(ReplayCandidate::from)
+                .collect(),
+        );
+
+        let target = journal.remaining();
+        summary.requested = target;
+        let mut progress = ReplayDownloadProgress {
+            target,
+            ..ReplayDownloadProgress::default()
+        };
+        let workers = self.cfg.download_workers.max(1);
+        let filter = &self.request.filter;
+        let (work_tx, work_rx) = mpsc::channel::<ReplayCandidate>();
+        let work_rx = Mutex::new(work_rx);
+        let (done_tx, done_rx) = mpsc::channel::<WorkerMessage>();
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                let done_tx = done_tx.clone();
+                let (work_rx, ctx, manifest, job) = (&work_rx, &ctx, &manifest, &self);
+                scope.spawn(move || loop {
+                    let Ok(replay) = lock(work_rx).recv() else {
+                        break;
+                    };
+                    let label = replay.label();
+                    let mut stage = |stage: ReplayStage| {
+                        let _ = done_tx.send(WorkerMessage::Stage(label.clone(), stage));
+                    };
+                    let result = job.process_replay(ctx, manifest, &replay, &mut stage);
+                    let _ = done_tx.send(WorkerMessage::Done(replay, result));
+                });
+            }
+            drop(done_tx);
+
+            let mut in_flight = 0;
+            let mut exhausted = false;
+            loop {
+                if cancel.load(Ordering::Relaxed) && !summary.cancelled {
+                    summary.cancelled = true;
                 }
-                Ok(None) => {
-                    summary.filtered_short += 1;
+                while !summary.cancelled
+                    && !exhausted
+                    && in_flight < workers
+                    && summary.saved + in_flight < target
+                {
+                    match pager.next(&self.api) {
+                        Ok(Some(replay)) => {
+                            if let Err(reason) = filter.check_date(replay.create_time) {
+                                summary.attempted += 1;
+                                summary.note_played_at(replay.create_time);
+                                *summary.filtered.entry(reason).or_default() += 1;
+                                emit(ReplayDownloadEvent::Replay {
+                                    label: replay.label(),
+                                    stage: ReplayStage::Skipped(reason.label()),
+                                });
+                                journal.done.push(replay.link);
+                            } else if work_tx.send(replay).is_err() {
+                                exhausted = true;
+                            } else {
+                                in_flight += 1;
+                            }
+                        }
+                        Ok(None) => {
+                            summary.reached_end = true;
+                            exhausted = true;
+                        }
+                        Err(err) => {
+                            summary.record_error(err);
+                            exhausted = true;
+                        }
+                    }
                 }
-                Err(ReplayProcessError::AlreadyExists) => {
-                    summary.skipped_existing += 1;
+                if in_flight == 0 {
+                    break;
                 }
-                Err(ReplayProcessError::Other(err)) => {
+                let Ok(message) = done_rx.recv() else {
+                    break;
+                };
+                let (replay, result) = match message {
+                    WorkerMessage::Stage(label, stage) => {
+                        emit(ReplayDownloadEvent::Replay { label, stage });
+                        continue;
+                    }
+                    WorkerMessage::Done(replay, result) => (replay, result),
+                };
+                in_flight -= 1;
+                summary.attempted += 1;
+                let mut retry = false;
+                let stage = match result {
+                    Ok((path, builds)) => {
+                        summary.saved += 1;
+                        journal.saved += 1;
+                        summary.saved_paths.push(path.clone());
+                        summary.builds.extend(builds);
+                        ReplayStage::Saved(path)
+                    }
+                    Err(ReplayProcessError::Filtered(reason)) => {
+                        *summary.filtered.entry(reason).or_default() += 1;
+                        ReplayStage::Skipped(reason.label())
+                    }
+                    Err(ReplayProcessError::AlreadyExists) => {
+                        summary.skipped_existing += 1;
+                        ReplayStage::Skipped("already downloaded")
+                    }
+                    Err(ReplayProcessError::Other(err)) => {
+                        let message = render_error_message(&err);
+                        summary.record_error(err);
+                        retry = true;
+                        ReplayStage::Failed(message)
+                    }
+                };
+                emit(ReplayDownloadEvent::Replay {
+                    label: replay.label(),
+                    stage,
+                });
+
+                // Failed replays are tried again if an interrupted job resumes.
+                if !retry {
+                    summary.note_played_at(replay.create_time);
+                    journal.done.push(replay.link);
+                }
+                if let Err(err) = journal.save() {
                     summary.record_error(err);
                 }
-            }
-        }
 
-        if let Err(err) = manifest.save(&manifest_path) {
-            summary.record_error(anyhow!(err).context("failed to write replay manifest"));
+                progress.attempted = summary.attempted;
+                progress.saved = summary.saved;
+                progress.pages = pager.pages;
+                progress.season = (pager.pages > 0).then_some(pager.season);
+                emit(ReplayDownloadEvent::Progress(progress.clone()));
+            }
+            drop(work_tx);
+        });
+
+        // A cancelled job resumes from its partial files; a finished one
+        // will not retry them.
+        if !summary.cancelled {
+            for path in lock(&ctx.kept_partials).drain(..) {
+                let _ = fs::remove_file(path);
+            }
         }
-
         summary
     }
 
@@ -193,71 +877,79 @@ impl ReplayDownloadJob {
             .with_context(|| format!("failed to load profile for {}", self.request.toon))
     }
 
-    fn filtered_replays(
-        &self,
-        profile: bw_web_api_rs::models::aurora_profile::ScrProfile,
-    ) -> Vec<bw_web_api_rs::models::common::Replay> {
-        let mut candidates: Vec<_> = profile.replays.into_iter().collect();
-        candidates.sort_by(|a, b| b.create_time.cmp(&a.create_time));
-
-        let matchup_filter = self
-            .request
-            .matchup
-            .as_deref()
-            .and_then(parse_matchup_filter);
-
-        candidates
-            .into_iter()
-            .filter(|replay| match &matchup_filter {
-                Some((a, b)) => replay_matches(&replay.attributes.replay_player_races, (*a, *b)),
-                None => true,
-            })
-            .take(self.request.limit.min(20))
-            .collect()
-    }
-
     fn prepare_context(&self) -> Result<DownloadContext> {
         let client = Client::builder()
             .build()
             .context("failed to create http client")?;
 
-        let storage_profile = self
+        let profile = self
             .request
             .alias
             .as_deref()
             .filter(|s| !s.trim().is_empty())
-            .unwrap_or(&self.request.toon);
-        let sanitized_profile = sanitize_component(storage_profile);
-        let matchup_label = self
+            .unwrap_or(&self.request.toon)
+            .to_string();
+        let matchup = self
             .request
             .matchup
             .as_deref()
             .map(|s| s.to_string())
             .unwrap_or_else(|| "All".to_string());
-        let sanitized_matchup = sanitize_component(&matchup_label);
 
-        let target_dir = self
-            .storage
-            .ensure_matchup_dir(&sanitized_profile, &sanitized_matchup)
-            .map_err(|e| anyhow!(e))
-            .with_context(|| {
-                format!("failed to prepare replay directory for {}", storage_profile)
-            })?;
+        let folder = match self.request.origin {
+            RequestOrigin::Scout => PathBuf::from(SCOUTING_FOLDER),
+            RequestOrigin::Manual | RequestOrigin::Watch => PathBuf::new(),
+        };
 
-        Ok(DownloadContext { client, target_dir })
+        let partial_dir = self.storage.partial_dir();
+        fs::create_dir_all(&partial_dir)
+            .with_context(|| format!("failed to create {}", partial_dir.display()))?;
+
+        Ok(DownloadContext {
+            client,
+            partial_dir,
+            kept_partials: Mutex::new(Vec::new()),
+            folder,
+            profile,
+            matchup,
+        })
     }
 
     fn process_replay(
         &self,
         ctx: &DownloadContext,
-        manifest: &mut ReplayManifest,
-        replay: &bw_web_api_rs::models::common::Replay,
-    ) -> Result<Option<PathBuf>, ReplayProcessError> {
+        manifest: &Mutex<ReplayManifest>,
+        replay: &ReplayCandidate,
+        stage: &mut dyn FnMut(ReplayStage),
+    ) -> Result<(PathBuf, Vec<PlayerBuild>), ReplayProcessError> {
+        stage(ReplayStage::FetchingDetail);
         let detail = self
             .api
             .get_matchmaker_player_info(&replay.link)
             .map_err(|e| ReplayProcessError::Other(e.context("failed matchmaker detail")))?;
 
+        let map = detail
+            .players
+            .values()
+            .map(|p| p.game_info.attributes.map_name.trim())
+            .find(|name| !name.is_empty());
+        let is_target = |name: &str| name.trim().eq_ignore_ascii_case(self.request.toon.trim());
+        let opponents: Vec<(String, i32)> = detail
+            .players
+            .values()
+            .filter(|p| !is_target(&p.name))
+            .map(|p| (p.name.clone(), p.score.base))
+            .collect();
+        let rating = detail
+            .players
+            .values()
+            .find(|p| is_target(&p.name))
+            .map(|p| p.score.base);
+        self.request
+            .filter
+            .check_listing(map, &opponents)
+            .map_err(ReplayProcessError::Filtered)?;
+
         let best = detail
             .replays
             .into_iter()
@@ -268,13 +960,13 @@ impl ReplayDownloadJob {
 
         let identifier = if !best.md5.is_empty() {
             best.md5.clone()
-        } else if !replay.attributes.game_id.is_empty() {
-            replay.attributes.game_id.clone()
+        } else if !replay.game_id.is_empty() {
+            replay.game_id.clone()
         } else {
             replay.link.clone()
         };
 
-        if manifest.entries.contains_key(&identifier) {
+        if lock(manifest).contains_file(&identifier) {
             return Err(ReplayProcessError::AlreadyExists);
         }
 
@@ -283,86 +975,178 @@ impl ReplayDownloadJob {
         }
 
         let tmp_path = ctx
-            .target_dir
+            .partial_dir
             .join(format!(".tmp-{}.rep", truncate_identifier(&identifier)));
+        stage(ReplayStage::Downloading);
         if let Err(err) = download_replay(&ctx.client, &best.url, &tmp_path) {
-            let _ = fs::remove_file(&tmp_path);
+            // Kept so a retry continues where the connection dropped.
+            if is_network_error(&err) {
+                lock(&ctx.kept_partials).push(tmp_path);
+            } else {
+                let _ = fs::remove_file(&tmp_path);
+            }
             return Err(ReplayProcessError::Other(err));
         }
 
-        let overview = match run_screp_overview(&self.cfg, &tmp_path) {
-            Ok(text) => text,
+        stage(ReplayStage::Parsing);
+        let summary = match read_replay_summary(&self.cfg, &tmp_path) {
+            Ok(summary) => summary,
             Err(err) => {
                 let _ = fs::remove_file(&tmp_path);
                 return Err(ReplayProcessError::Other(err));
             }
         };
 
-        if let Some(duration) = crate::replay::parse_screp_duration_seconds(&overview)
-            && duration <= 120
-        {
+        // Games listed without a start time are dated from the replay.
+        let dated = match summary.played_at {
+            Some(played_at) if replay.create_time == 0 => self.request.filter.check_date(played_at),
+            _ => Ok(()),
+        };
+        if let Err(reason) = dated.and_then(|()| {
+            self.request
+                .filter
+                .check_replay(&summary, &self.request.toon)
+        }) {
             let _ = fs::remove_file(&tmp_path);
-            return Ok(None);
+            return Err(ReplayProcessError::Filtered(reason));
         }
 
-        let (main_name, main_race, opp_name, opp_race) =
-            extract_players(&overview, &self.request.toon).ok_or_else(|| {
-                ReplayProcessError::Other(anyhow!("failed to parse players from screp"))
-            })?;
-
-        let date_prefix = replay_date_prefix(best.create_time)
-            .or_else(|| replay_date_prefix(replay.create_time as u64));
-        let file_name = build_filename(
-            date_prefix.as_deref(),
-            &main_name,
-            &main_race,
-            &opp_name,
-            &opp_race,
-        );
-        let mut final_path = ctx.target_dir.join(&file_name);
-        let mut counter = 1;
-        while final_path.exists() {
-            let alt = format!("{}-{}.rep", file_name.trim_end_matches(".rep"), counter);
-            final_path = ctx.target_dir.join(alt);
-            counter += 1;
-        }
+        let played_at = if best.create_time > 0 && best.create_time != u64::from(u32::MAX) {
+            best.create_time
+        } else {
+            replay.create_time
+        };
+        let mut fields =
+            ReplayNameFields::from_summary(&summary, &self.request.toon, Some(played_at))
+                .ok_or_else(|| {
+                    ReplayProcessError::Other(anyhow!("failed to parse players from replay"))
+                })?;
+        fields.profile = ctx.profile.clone();
+        fields.matchup = ctx.matchup.clone();
+        fields.gateway = Some(self.request.gateway);
+        fields.rating = rating;
+        let md5 = fs::read(&tmp_path)
+            .map(|data| content_hash(&data))
+            .map_err(|e| ReplayProcessError::Other(anyhow!(e).context("hash replay")))?;
+
+        // Held while naming and moving the file so two workers cannot pick the
+        // same free path.
+        let mut manifest = lock(manifest);
+        let final_path = self
+            .storage
+            .prepare_path(
+                &ctx.folder
+                    .join(self.cfg.replay_layout.relative_path(&fields)),
+            )
+            .map_err(|e| ReplayProcessError::Other(anyhow!(e).context("prepare replay folder")))?;
 
         fs::rename(&tmp_path, &final_path)
             .map_err(|e| ReplayProcessError::Other(anyhow!(e).context("finalize replay")))?;
 
-        manifest.entries.insert(
-            identifier,
-            ManifestEntry {
-                path: final_path.to_string_lossy().into_owned(),
-                saved_at: current_timestamp(),
-            },
-        );
+        let entry = ManifestEntry::new(&final_path, md5, &self.request.toon, &summary, &fields);
+        manifest.entries.insert(identifier.clone(), entry.clone());
+        ReplayManifest::update(&self.storage.manifest_path(), |current| {
+            current.entries.insert(identifier, entry);
+        })
+        .map_err(|e| ReplayProcessError::Other(e.context("failed to write replay manifest")))?;
 
-        Ok(Some(final_path))
+        Ok((final_path, builds_from_summary(&summary, played_at)))
     }
 }
 
+// Copies a finished game `toon` played into the library where `layout` puts
+// it. The manifest is keyed by the content hash, so a replay that is already
+// stored (archived or downloaded) is not copied again and its existing path is
+// returned.
+pub fn archive_replay(
+    storage: &ReplayStorage,
+    layout: &ReplayLayout,
+    source: &Path,
+    summary: &ReplaySummary,
+    toon: &str,
+    fields: &ReplayNameFields,
+) -> Result<PathBuf> {
+    let data = fs::read(source).with_context(|| format!("read replay {}", source.display()))?;
+    let identifier = content_hash(&data);
+    let manifest_path = storage.manifest_path();
+    if let Some(entry) = ReplayManifest::load(&manifest_path)
+        .entries
+        .get(&identifier)
+        && Path::new(&entry.path).is_file()
+    {
+        return Ok(PathBuf::from(&entry.path));
+    }
+
+    let final_path = storage
+        .prepare_path(&layout.relative_path(fields))
+        .with_context(|| format!("failed to prepare replay directory for {}", fields.p1))?;
+    fs::write(&final_path, &data)
+        .with_context(|| format!("write replay {}", final_path.display()))?;
+
+    let entry = ManifestEntry::new(&final_path, identifier.clone(), toon, summary, fields);
+    ReplayManifest::update(&manifest_path, |manifest| {
+        manifest.entries.insert(identifier, entry);
+    })
+    .context("failed to write replay manifest")?;
+    Ok(final_path)
+}
+
 struct DownloadContext {
     client: Client,
-    target_dir: PathBuf,
+    partial_dir: PathBuf,
+    // Partial downloads left for a retry by this job.
+    kept_partials: Mutex<Vec<PathBuf>>,
+    // Library folder the layout is applied under.
+    folder: PathBuf,
+    // Values for the `{profile}` and `{matchup}` placeholders: the alias or
+    // toon, and the requested matchup.
+    profile: String,
+    matchup: String,
+}
+
+enum WorkerMessage {
+    Stage(String, ReplayStage),
+    Done(
+        ReplayCandidate,
+        Result<(PathBuf, Vec<PlayerBuild>), ReplayProcessError>,
+    ),
+}
+
+// A worker that panicked mid-replay leaves nothing half-written in these, so
+// a poisoned lock is still usable.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
 }
 
+// Runs one queued download on its own thread and drops its journal once it
+// completes or is cancelled; setting `cancel` stops it once the replays in
+// flight finish.
 pub fn spawn_download_job(
     base_url: String,
     cfg: Config,
-    request: ReplayDownloadRequest,
-) -> (thread::JoinHandle<()>, Receiver<ReplayDownloadSummary>) {
+    mut journal: DownloadJournal,
+    cancel: Arc<AtomicBool>,
+) -> (thread::JoinHandle<()>, Receiver<ReplayDownloadEvent>) {
     let (tx, rx) = mpsc::channel();
     let handle = thread::spawn(move || {
+        let request = journal.request.clone();
         let summary = match ReplayDownloadJob::new(base_url, cfg, request) {
-            Ok(job) => job.run(),
+            Ok(job) => job.run(&mut journal, &cancel, |event| {
+                let _ = tx.send(event);
+            }),
             Err(err) => {
                 let mut summary = ReplayDownloadSummary::default();
                 summary.record_error(err);
+                summary.setup_failed = true;
                 summary
             }
         };
-        let _ = tx.send(summary);
+        if !summary.setup_failed {
+            journal.remove();
+        }
+        let _ = tx.send(ReplayDownloadEvent::Finished(summary));
     });
     (handle, rx)
 }
@@ -371,96 +1155,24 @@ pub fn spawn_download_job(
 pub enum ReplayProcessError {
     #[error("replay already downloaded")]
     AlreadyExists,
+    #[error("replay filtered: {}", .0.label())]
+    Filtered(FilterReason),
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
-fn extract_players(overview: &str, target_toon: &str) -> Option<(String, String, String, String)> {
-    let (_, players) = crate::replay::parse_screp_overview(overview);
-    if players.is_empty() {
-        return None;
-    }
-    let target_lower = target_toon.to_ascii_lowercase();
-    let mut seen = std::collections::HashSet::new();
-    let mut ordered: Vec<(u8, String, String)> = Vec::new();
-
-    for (team, race, name) in players {
-        let key = name.trim();
-        if key.is_empty() {
-            continue;
-        }
-        let lower = key.to_ascii_lowercase();
-        if !seen.insert(lower.clone()) {
-            continue;
-        }
-        let race_label = race.unwrap_or_else(|| "Unknown".to_string());
-        ordered.push((team, race_label, key.to_string()));
-    }
-
-    if ordered.is_empty() {
-        return None;
-    }
-
-    let mut main_entry: Option<(u8, String, String)> = None;
-    let mut others: Vec<(u8, String, String)> = Vec::new();
-
-    for (team, race, name) in ordered.into_iter() {
-        if name.to_ascii_lowercase() == target_lower {
-            if main_entry.is_none() {
-                main_entry = Some((team, race.clone(), name.clone()));
-            }
-        } else {
-            others.push((team, race, name));
-        }
-    }
-
-    let (main_team, main_race, main_name) = main_entry?;
-    if main_team == 0 || main_team > 2 {
-        // Target toon was only observing.
-        return None;
-    }
-
-    let mut opponent: Option<(u8, String, String)> = None;
-    for (team, race, name) in others.into_iter() {
-        if team == main_team {
-            continue;
-        }
-        if team > 0 && team <= 2 {
-            opponent = Some((team, race.clone(), name.clone()));
-            break;
-        }
-        if opponent.is_none() {
-            opponent = Some((team, race, name));
-        }
-    }
-
-    let (_, opp_race, opp_name) = opponent?;
-    Some((main_name, main_race, opp_name, opp_race))
-}
-
-fn build_filename(prefix: Option<&str>, p1: &str, r1: &str, p2: &str, r2: &str) -> String {
-    let base = format!(
-        "{}({})_vs_{}({})",
-        sanitize_component(p1),
-        sanitize_component(&race_letter(r1)),
-        sanitize_component(p2),
-        sanitize_component(&race_letter(r2))
-    );
-    match prefix {
-        Some(p) => format!("{}_{}.rep", sanitize_component(p), base),
-        None => format!("{}.rep", base),
+fn unique_path(dir: &Path, file_name: &str) -> PathBuf {
+    let mut path = dir.join(file_name);
+    let mut counter = 1;
+    while path.exists() {
+        let alt = format!("{}-{}.rep", file_name.trim_end_matches(".rep"), counter);
+        path = dir.join(alt);
+        counter += 1;
     }
+    path
 }
 
-fn race_letter(race: &str) -> String {
-    let trimmed = race.trim();
-    let Some(first) = trimmed.chars().next() else {
-        return "U".to_string();
-    };
-    first.to_ascii_uppercase().to_string()
-}
-
-fn parse_matchup_filter(input: &str) -> Option<(char, char)> {
+pub(crate) fn parse_matchup_filter(input: &str) -> Option<(char, char)> {
     let s = input.trim().to_ascii_uppercase();
     let splitters = ['V', ',', '/'];
     for sep in splitters {
@@ -493,23 +1205,10 @@ fn truncate_identifier(id: &str) -> String {
     sanitize_component(short)
 }
 
-fn current_timestamp() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or_default()
-}
-
-fn replay_date_prefix(ts_secs: u64) -> Option<String> {
-    if ts_secs == 0 || ts_secs == u32::MAX as u64 {
-        return None;
-    }
-    DateTime::<Utc>::from_timestamp(ts_secs as i64, 0).map(|dt| dt.format("%Y%m%d").to_string())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{replay_player, unique_temp_path};
 
     #[test]
     fn parse_matchup_filter_accepts_common_formats() {
@@ -535,25 +1234,217 @@ mod tests {
     }
 
     #[test]
-    fn race_letter_maps_first_character_or_unknown() {
-        assert_eq!(race_letter("Protoss"), "P");
-        assert_eq!(race_letter("zerg"), "Z");
-        assert_eq!(race_letter(""), "U");
+    fn pager_orders_filters_and_dedups_candidates() {
+        let request = ReplayDownloadRequest {
+            toon: "pro".to_string(),
+            gateway: 10,
+            matchup: Some("PvZ".to_string()),
+            limit: 300,
+            alias: None,
+            filter: DownloadFilter::default(),
+            origin: RequestOrigin::Manual,
+        };
+        let candidate = |link: &str, create_time: u64, races: &str| ReplayCandidate {
+            link: link.to_string(),
+            game_id: String::new(),
+            create_time,
+            races: races.to_string(),
+        };
+        let mut pager = ReplayPager::new(&request, 20);
+        let fresh = pager.push(vec![
+            candidate("a", 100, "Protoss,Zerg"),
+            candidate("b", 300, "zerg,protoss"),
+            candidate("c", 200, "Protoss,Terran"),
+        ]);
+        assert_eq!(fresh, 3);
+        assert_eq!(pager.push(vec![candidate("b", 300, "Zerg,Protoss")]), 0);
+        let links: Vec<&str> = pager.buffered.iter().map(|c| c.link.as_str()).collect();
+        assert_eq!(links, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn download_filter_reports_why_replays_are_dropped() {
+        let input = DownloadFilterInput {
+            from: "2025-03-01".to_string(),
+            to: "2025-03-31".to_string(),
+            result: ResultFilter::Win,
+            opponent: "rival".to_string(),
+            map: "poly".to_string(),
+            duration: "5-30".to_string(),
+            min_rating: "2000".to_string(),
+        };
+        let filter = input.parse().expect("parse filter");
+        let march_10 = parse_date("2025-03-10").expect("date");
+        assert_eq!(filter.check_date(march_10), Ok(()));
+        // Unparseable listing times are left to the downloaded replay.
+        assert_eq!(filter.check_date(0), Ok(()));
+        assert_eq!(
+            filter.check_date(parse_date("2025-04-01").expect("date")),
+            Err(FilterReason::OutsideDates)
+        );
+
+        let opponent = |name: &str, rating| vec![(name.to_string(), rating)];
+        assert_eq!(
+            filter.check_listing(Some("Polypoid"), &opponent("Rival", 2100)),
+            Ok(())
+        );
+        assert_eq!(
+            filter.check_listing(Some("Polypoid"), &opponent("Other", 2100)),
+            Err(FilterReason::Opponent)
+        );
+        assert_eq!(
+            filter.check_listing(Some("Eclipse"), &opponent("Rival", 2100)),
+            Err(FilterReason::Map)
+        );
+        assert_eq!(
+            filter.check_listing(None, &opponent("Rival", 1800)),
+            Err(FilterReason::OpponentRating)
+        );
+
+        let player = |id: u8, name: &str| replay_player(id, name, None, id);
+        let mut summary = ReplaySummary {
+            frames: 24 * 60 * 10,
+            map: Some("Polypoid".to_string()),
+            played_at: None,
+            players: vec![player(1, "pro"), player(2, "Rival")],
+            winner_team: Some(1),
+            game_type: 2,
+        };
+        assert_eq!(filter.check_replay(&summary, "pro"), Ok(()));
+        summary.winner_team = Some(2);
+        assert_eq!(
+            filter.check_replay(&summary, "pro"),
+            Err(FilterReason::Result)
+        );
+        summary.frames = 24 * 60 * 3;
+        assert_eq!(
+            filter.check_replay(&summary, "pro"),
+            Err(FilterReason::TooShort)
+        );
+        assert_eq!(
+            DownloadFilter::default().check_replay(&summary, "pro"),
+            Ok(())
+        );
+
+        let bad = DownloadFilterInput {
+            from: "March".to_string(),
+            ..DownloadFilterInput::default()
+        };
+        assert!(bad.parse().is_err());
+        assert!(DownloadFilterInput::default()
+            .parse()
+            .expect("empty filter")
+            .is_empty());
     }
 
     #[test]
-    fn replay_date_prefix_filters_sentinel_and_formats_valid_epoch() {
-        assert_eq!(replay_date_prefix(0), None);
-        assert_eq!(replay_date_prefix(u32::MAX as u64), None);
+    fn resumed_pager_keeps_paging_past_a_handled_page() {
+        let request = ReplayDownloadRequest {
+            toon: "pro".to_string(),
+            gateway: 10,
+            matchup: None,
+            limit: 300,
+            alias: None,
+            filter: DownloadFilter::default(),
+            origin: RequestOrigin::Manual,
+        };
+        let page = |first: u32| -> Vec<ReplayCandidate> {
+            (first..first + HISTORY_PAGE_SIZE)
+                .map(|i| ReplayCandidate {
+                    link: format!("match-{i}"),
+                    game_id: String::new(),
+                    create_time: u64::from(10_000 - i),
+                    races: String::new(),
+                })
+                .collect()
+        };
+        let mut pager = ReplayPager::new(&request, 20);
+        // The interrupted run handled the whole first page.
+        pager
+            .done
+            .extend(page(0).into_iter().map(|candidate| candidate.link));
+        assert_eq!(pager.push(page(0)), HISTORY_PAGE_SIZE as usize);
+        assert!(pager.buffered.is_empty());
+        assert_eq!(
+            pager.push(page(HISTORY_PAGE_SIZE)),
+            HISTORY_PAGE_SIZE as usize
+        );
         assert_eq!(
-            replay_date_prefix(1_704_067_200),
-            Some("20240101".to_string())
+            pager.buffered.front().map(|c| c.link.as_str()),
+            Some(format!("match-{HISTORY_PAGE_SIZE}").as_str())
         );
     }
 
+    #[test]
+    fn journals_round_trip_and_temp_files_are_cleaned() {
+        let root = unique_temp_path("journal");
+        let storage = ReplayStorage::new(root.clone());
+        let request = ReplayDownloadRequest {
+            toon: "pro".to_string(),
+            gateway: 10,
+            matchup: None,
+            limit: 300,
+            alias: None,
+            filter: DownloadFilter::default(),
+            origin: RequestOrigin::Manual,
+        };
+        let mut journal = DownloadJournal::create(&storage, request).expect("create journal");
+        journal.saved = 120;
+        journal.done.push("match-1".to_string());
+        journal.save().expect("save journal");
+
+        let loaded = DownloadJournal::load_all(&storage);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!((loaded[0].saved, loaded[0].remaining()), (120, 180));
+        assert_eq!(loaded[0].done, vec!["match-1".to_string()]);
+        loaded[0].remove();
+        assert!(DownloadJournal::load_all(&storage).is_empty());
+
+        let dir = storage.bwtools_root().join("pro").join("PvZ");
+        fs::create_dir_all(&dir).expect("matchup dir");
+        fs::write(dir.join(".tmp-abc.rep"), b"partial").expect("write tmp");
+        fs::write(dir.join("kept.rep"), b"replay").expect("write replay");
+        assert_eq!(clean_temp_files(&storage.bwtools_root()), 1);
+        assert!(dir.join("kept.rep").exists());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
     #[test]
     fn truncate_identifier_limits_to_sixteen_chars() {
         assert_eq!(truncate_identifier("abcdefghijklmnop"), "abcdefghijklmnop");
         assert_eq!(truncate_identifier("abcdefghijklmnopq"), "abcdefghijklmnop");
     }
+
+    #[test]
+    fn archive_replay_dedups_by_content_hash() {
+        let root = unique_temp_path("archive");
+        fs::create_dir_all(&root).expect("create temp dir");
+        let source = root.join("LastReplay.rep");
+        fs::write(&source, b"replay bytes").expect("write replay");
+        let player = |id: u8, name: &str, race: &str| replay_player(id, name, Some(race), id + 1);
+        let summary = ReplaySummary {
+            frames: 10_000,
+            map: None,
+            played_at: None,
+            players: vec![player(0, "Bob", "Zerg"), player(1, "Alice", "Terran")],
+            winner_team: Some(1),
+            game_type: 2,
+        };
+        let storage = ReplayStorage::new(root.clone());
+
+        let layout = ReplayLayout::default();
+        let fields =
+            ReplayNameFields::from_summary(&summary, "alice", Some(1_700_000_000)).expect("fields");
+        let dir = storage.bwtools_root().join("Alice").join("TvZ");
+        let first = archive_replay(&storage, &layout, &source, &summary, "alice", &fields)
+            .expect("archive replay");
+        assert_eq!(first, dir.join("20231114_Alice(T)_vs_Bob(Z).rep"));
+        let second = archive_replay(&storage, &layout, &source, &summary, "alice", &fields)
+            .expect("archive again");
+        assert_eq!(first, second);
+        assert_eq!(fs::read_dir(&dir).expect("list dir").count(), 1);
+
+        let _ = fs::remove_dir_all(root);
+    }
 }
@@ -0,0 +1,299 @@
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::replay_index::{IndexedReplay, ReplayIndex};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ResultFilter {
+    #[default]
+    Any,
+    Win,
+    Loss,
+}
+
+impl ResultFilter {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Any => Self::Win,
+            Self::Win => Self::Loss,
+            Self::Loss => Self::Any,
+        }
+    }
+
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "" | "any" => Some(Self::Any),
+            "win" | "wins" => Some(Self::Win),
+            "loss" | "losses" => Some(Self::Loss),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Any => "Any",
+            Self::Win => "Win",
+            Self::Loss => "Loss",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplaySort {
+    #[default]
+    Date,
+    Duration,
+    Map,
+    Player,
+}
+
+impl ReplaySort {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Date => Self::Duration,
+            Self::Duration => Self::Map,
+            Self::Map => Self::Player,
+            Self::Player => Self::Date,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Date => "Date",
+            Self::Duration => "Duration",
+            Self::Map => "Map",
+            Self::Player => "Player",
+        }
+    }
+}
+
+// Criteria for picking replays out of the library index. Unset fields match
+// everything. Win/Loss is judged for `player`, or for `perspective` (usually
+// our own name) when no player is given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayFilter {
+    pub player: Option<String>,
+    pub matchup: Option<(char, char)>,
+    pub map: Option<String>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub min_secs: Option<u32>,
+    pub max_secs: Option<u32>,
+    pub result: ResultFilter,
+    pub perspective: Option<String>,
+}
+
+impl ReplayFilter {
+    pub fn matches(&self, entry: &IndexedReplay) -> bool {
+        if entry.unreadable {
+            return false;
+        }
+        if let Some(player) = self.player.as_deref()
+            && !entry
+                .players
+                .iter()
+                .any(|p| contains_ignore_case(&p.name, player))
+        {
+            return false;
+        }
+        if let Some((a, b)) = self.matchup {
+            let races: Vec<char> = entry
+                .players
+                .iter()
+                .map(|p| race_char(p.race.as_deref()))
+                .collect();
+            let matched = match races.as_slice() {
+                [x, y] => (*x == a && *y == b) || (*x == b && *y == a),
+                _ => false,
+            };
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(map) = self.map.as_deref()
+            && !entry
+                .map
+                .as_deref()
+                .is_some_and(|m| contains_ignore_case(m, map))
+        {
+            return false;
+        }
+        if self.from.is_some_and(|from| entry.played_at < from)
+            || self.to.is_some_and(|to| entry.played_at >= to)
+            || self.min_secs.is_some_and(|min| entry.duration_secs < min)
+            || self.max_secs.is_some_and(|max| entry.duration_secs > max)
+        {
+            return false;
+        }
+        match self.result {
+            ResultFilter::Any => true,
+            wanted => self
+                .won_by(entry)
+                .is_some_and(|won| won == (wanted == ResultFilter::Win)),
+        }
+    }
+
+    fn won_by(&self, entry: &IndexedReplay) -> Option<bool> {
+        let winner = entry.winner_team?;
+        let player = match (self.player.as_deref(), self.perspective.as_deref()) {
+            (Some(search), _) => entry
+                .players
+                .iter()
+                .find(|p| contains_ignore_case(&p.name, search))?,
+            // The perspective is an exact toon name, which may be a prefix of
+            // the opponent's.
+            (None, Some(toon)) => entry
+                .players
+                .iter()
+                .find(|p| p.name.eq_ignore_ascii_case(toon.trim()))?,
+            (None, None) => return None,
+        };
+        Some(player.team == winner)
+    }
+}
+
+pub(crate) fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack
+        .to_ascii_lowercase()
+        .contains(&needle.trim().to_ascii_lowercase())
+}
+
+fn race_char(race: Option<&str>) -> char {
+    race.map(crate::race::initial)
+        .and_then(|r| r.chars().next())
+        .unwrap_or('?')
+}
+
+// Start of a `YYYY-MM-DD` day in UTC.
+pub fn parse_date(input: &str) -> Option<u64> {
+    let date = NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d").ok()?;
+    u64::try_from(date.and_time(NaiveTime::MIN).and_utc().timestamp()).ok()
+}
+
+// End of a `YYYY-MM-DD` day, for inclusive upper bounds.
+pub fn parse_date_end(input: &str) -> Option<u64> {
+    parse_date(input).map(|start| start + SECS_PER_DAY)
+}
+
+// Minutes as "10-20", "10-", "-20" or "10" (at least ten minutes).
+pub fn parse_duration_range(input: &str) -> (Option<u32>, Option<u32>) {
+    let mins = |s: &str| s.trim().parse::<u32>().ok()?.checked_mul(60);
+    match input.split_once('-') {
+        Some((min, max)) => (mins(min), mins(max)),
+        None => (mins(input), None),
+    }
+}
+
+pub fn filter_and_sort<'a>(
+    index: &'a ReplayIndex,
+    filter: &ReplayFilter,
+    sort: ReplaySort,
+    descending: bool,
+) -> Vec<(&'a str, &'a IndexedReplay)> {
+    let mut rows: Vec<(&str, &IndexedReplay)> =
+        index.entries().filter(|(_, e)| filter.matches(e)).collect();
+    rows.sort_by(|(pa, a), (pb, b)| {
+        let ord = match sort {
+            ReplaySort::Date => a.played_at.cmp(&b.played_at),
+            ReplaySort::Duration => a.duration_secs.cmp(&b.duration_secs),
+            ReplaySort::Map => a.map.cmp(&b.map),
+            ReplaySort::Player => first_player(a).cmp(&first_player(b)),
+        };
+        ord.then_with(|| pa.cmp(pb))
+    });
+    if descending {
+        rows.reverse();
+    }
+    rows
+}
+
+fn first_player(entry: &IndexedReplay) -> String {
+    entry
+        .players
+        .first()
+        .map(|p| p.name.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay_download::parse_matchup_filter;
+    use crate::replay_index::IndexedPlayer;
+
+    fn entry(p1: (&str, &str), p2: (&str, &str), map: &str, mins: u32) -> IndexedReplay {
+        let player = |(name, race): (&str, &str), team| IndexedPlayer {
+            name: name.to_string(),
+            race: Some(race.to_string()),
+            team,
+        };
+        IndexedReplay {
+            size: 1,
+            mtime: 1,
+            players: vec![player(p1, 1), player(p2, 2)],
+            map: Some(map.to_string()),
+            duration_secs: mins * 60,
+            played_at: parse_date("2025-03-10").unwrap_or_default(),
+            winner_team: Some(1),
+            unreadable: false,
+            builds_logged: true,
+        }
+    }
+
+    #[test]
+    fn filter_matches_each_criterion() {
+        let game = entry(("Alice", "Terran"), ("Bob", "Zerg"), "Polypoid", 14);
+        let filter = |f: ReplayFilter| f.matches(&game);
+        assert!(filter(ReplayFilter::default()));
+        assert!(filter(ReplayFilter {
+            player: Some("bo".to_string()),
+            matchup: parse_matchup_filter("ZvT"),
+            map: Some("poly".to_string()),
+            ..ReplayFilter::default()
+        }));
+        assert!(!filter(ReplayFilter {
+            matchup: parse_matchup_filter("PvZ"),
+            ..ReplayFilter::default()
+        }));
+        let (min_secs, max_secs) = parse_duration_range("10-12");
+        assert!(!filter(ReplayFilter {
+            min_secs,
+            max_secs,
+            ..ReplayFilter::default()
+        }));
+        assert!(filter(ReplayFilter {
+            from: parse_date("2025-03-10"),
+            to: parse_date_end("2025-03-10"),
+            ..ReplayFilter::default()
+        }));
+        assert!(!filter(ReplayFilter {
+            player: Some("Bob".to_string()),
+            result: ResultFilter::Win,
+            ..ReplayFilter::default()
+        }));
+        assert!(filter(ReplayFilter {
+            perspective: Some("alice".to_string()),
+            result: ResultFilter::Win,
+            ..ReplayFilter::default()
+        }));
+
+        let smurf = entry(("BobSmurf", "Terran"), ("Bob", "Zerg"), "Polypoid", 14);
+        assert!(ReplayFilter {
+            perspective: Some("Bob".to_string()),
+            result: ResultFilter::Loss,
+            ..ReplayFilter::default()
+        }
+        .matches(&smurf));
+    }
+
+    #[test]
+    fn duration_range_accepts_open_bounds() {
+        assert_eq!(parse_duration_range("10-"), (Some(600), None));
+        assert_eq!(parse_duration_range("-20"), (None, Some(1200)));
+        assert_eq!(parse_duration_range("15"), (Some(900), None));
+        assert_eq!(parse_duration_range(""), (None, None));
+        assert_eq!(parse_duration_range("99999999-"), (None, None));
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::build_order::{builds_from_summary, PlayerBuild};
+use crate::config::Config;
+use crate::replay_io::{read_replay_summary, ReplaySummary};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedPlayer {
+    pub name: String,
+    pub race: Option<String>,
+    pub team: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedReplay {
+    pub size: u64,
+    pub mtime: u64,
+    #[serde(default)]
+    pub players: Vec<IndexedPlayer>,
+    #[serde(default)]
+    pub map: Option<String>,
+    #[serde(default)]
+    pub duration_secs: u32,
+    // Game start from the replay, or the file's mtime when it has none.
+    #[serde(default)]
+    pub played_at: u64,
+    #[serde(default)]
+    pub winner_team: Option<u8>,
+    // Kept so files that fail to parse are not retried until they change.
+    #[serde(default)]
+    pub unreadable: bool,
+    // Whether the players' builds went to the build order log; entries
+    // indexed before that are read again once.
+    #[serde(default)]
+    pub builds_logged: bool,
+}
+
+impl IndexedReplay {
+    fn from_summary(summary: &ReplaySummary, size: u64, mtime: u64) -> Self {
+        Self {
+            size,
+            mtime,
+            players: summary
+                .players
+                .iter()
+                .map(|p| IndexedPlayer {
+                    name: p.name.clone(),
+                    race: p.race.clone(),
+                    team: p.team,
+                })
+                .collect(),
+            map: summary.map.clone(),
+            duration_secs: summary.duration_secs(),
+            played_at: summary.played_at.unwrap_or(mtime),
+            winner_team: summary.winner_team,
+            unreadable: false,
+            builds_logged: true,
+        }
+    }
+
+    fn unreadable(size: u64, mtime: u64) -> Self {
+        Self {
+            size,
+            mtime,
+            players: Vec::new(),
+            map: None,
+            duration_secs: 0,
+            played_at: mtime,
+            winner_team: None,
+            unreadable: true,
+            builds_logged: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexScan {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unreadable: usize,
+}
+
+// Every replay under `replay_library_root`, keyed by path. Entries are only
+// re-parsed when a file's size or mtime changes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayIndex {
+    entries: BTreeMap<String, IndexedReplay>,
+    #[serde(skip)]
+    pub dirty: bool,
+    // Builds from replays parsed by the last refresh, for the build order log.
+    #[serde(skip)]
+    pub new_builds: Vec<PlayerBuild>,
+}
+
+impl ReplayIndex {
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &IndexedReplay)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_str(), entry))
+    }
+
+    pub fn get(&self, path: &str) -> Option<&IndexedReplay> {
+        self.entries.get(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn refresh(&mut self, cfg: &Config, root: &Path) -> IndexScan {
+        let mut scan = IndexScan::default();
+        let mut files = Vec::new();
+        collect_replays(root, &mut files);
+
+        let present: std::collections::HashSet<String> = files
+            .iter()
+            .map(|(path, _, _)| path.to_string_lossy().into_owned())
+            .collect();
+        let before = self.entries.len();
+        self.entries.retain(|path, _| present.contains(path));
+        scan.removed = before - self.entries.len();
+
+        for (path, size, mtime) in files {
+            let key = path.to_string_lossy().into_owned();
+            let existing = self.entries.get(&key);
+            if existing.is_some_and(|e| {
+                e.size == size && e.mtime == mtime && (e.builds_logged || e.unreadable)
+            }) {
+                continue;
+            }
+            let entry = match read_replay_summary(cfg, &path) {
+                Ok(summary) => {
+                    let entry = IndexedReplay::from_summary(&summary, size, mtime);
+                    self.new_builds
+                        .extend(builds_from_summary(&summary, entry.played_at));
+                    entry
+                }
+                Err(err) => {
+                    tracing::debug!(error = %err, path = %path.display(), "unreadable replay");
+                    scan.unreadable += 1;
+                    IndexedReplay::unreadable(size, mtime)
+                }
+            };
+            if existing.is_some() {
+                scan.updated += 1;
+            } else {
+                scan.added += 1;
+            }
+            self.entries.insert(key, entry);
+        }
+        if scan.added + scan.updated + scan.removed > 0 {
+            self.dirty = true;
+        }
+        scan
+    }
+}
+
+// Recursively lists `.rep` files with their size and mtime, skipping hidden
+// entries such as `.meta` and in-flight `.tmp-*` downloads.
+pub(crate) fn collect_replays(dir: &Path, out: &mut Vec<(PathBuf, u64, u64)>) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            collect_replays(&path, out);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("rep"))
+        {
+            let mtime = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            out.push((path, meta.len(), mtime));
+        }
+    }
+}
+
+pub struct ReplayIndexStore {
+    path: PathBuf,
+}
+
+impl ReplayIndexStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn load(&self) -> Result<ReplayIndex> {
+        match fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("deserialize replay index {}", self.path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(ReplayIndex::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("read replay index {}", self.path.display()))
+            }
+        }
+    }
+
+    pub fn save(&self, index: &ReplayIndex) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create replay index directory {}", parent.display()))?;
+        }
+        let data = serde_json::to_vec(index).context("serialize replay index")?;
+        fs::write(&self.path, data)
+            .with_context(|| format!("write replay index {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+// Refreshes a copy of `index` off the UI thread; the caller swaps it in when
+// the scan finishes.
+pub fn spawn_index_refresh(
+    cfg: Config,
+    mut index: ReplayIndex,
+) -> (thread::JoinHandle<()>, Receiver<(ReplayIndex, IndexScan)>) {
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        let root = cfg.replay_library_root.clone();
+        let scan = index.refresh(&cfg, &root);
+        let _ = tx.send((index, scan));
+    });
+    (handle, rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::unique_temp_path;
+
+    #[test]
+    fn refresh_indexes_new_changed_and_removed_files() {
+        let root = unique_temp_path("index");
+        let nested = root.join("Saved").join("Old");
+        fs::create_dir_all(&nested).expect("create dirs");
+        fs::create_dir_all(root.join(".meta")).expect("create meta");
+        fs::write(nested.join("a.rep"), b"not a replay").expect("write a");
+        fs::write(root.join("b.REP"), b"also not a replay").expect("write b");
+        fs::write(root.join(".meta").join("c.rep"), b"hidden").expect("write c");
+        fs::write(root.join("notes.txt"), b"ignored").expect("write txt");
+
+        let cfg = Config {
+            screp_cmd: "bwtools-missing-screp".to_string(),
+            ..Config::default()
+        };
+        let mut index = ReplayIndex::default();
+        let scan = index.refresh(&cfg, &root);
+        assert_eq!((scan.added, scan.unreadable), (2, 2));
+        assert_eq!(index.len(), 2);
+        assert!(index.entries().all(|(_, e)| e.unreadable));
+        assert!(index.dirty);
+
+        assert_eq!(index.refresh(&cfg, &root), IndexScan::default());
+
+        fs::remove_file(root.join("b.REP")).expect("remove b");
+        fs::write(nested.join("a.rep"), b"a longer non-replay").expect("rewrite a");
+        let scan = index.refresh(&cfg, &root);
+        assert_eq!((scan.updated, scan.removed), (1, 1));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn refresh_describes_parsed_replays_and_logs_their_builds() {
+        let root = unique_temp_path("index-parsed");
+        fs::create_dir_all(&root).expect("create dir");
+        let path = root.join("game.rep");
+        fs::write(&path, crate::replay_parser::tests::sample_replay()).expect("write replay");
+
+        let cfg = Config {
+            screp_cmd: "bwtools-missing-screp".to_string(),
+            ..Config::default()
+        };
+        let mut index = ReplayIndex::default();
+        let scan = index.refresh(&cfg, &root);
+        assert_eq!((scan.added, scan.unreadable), (1, 0));
+
+        let entry = index.get(&path.to_string_lossy()).expect("indexed replay");
+        let players: Vec<&str> = entry.players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(players, ["Alice", "Bob"]);
+        assert_eq!(entry.map.as_deref(), Some("Polypoid"));
+        assert_eq!(entry.winner_team, Some(1));
+        assert!(entry.builds_logged);
+        assert!(!index.new_builds.is_empty());
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -4,27 +4,358 @@ use std::path::Path;
 use std::process::Command;
 
 use anyhow::{anyhow, Context, Result};
+use chrono::DateTime;
 use reqwest::blocking::Client;
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use which::which;
 
+use crate::build_order::{build_order_from_actions, build_order_from_replay, BuildStep};
 use crate::config::Config;
+use crate::maps::normalize_map_name;
+use crate::replay_parser::{self, Replay, CMD_LEAVE_GAME};
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayPlayerSummary {
+    pub player_id: u8,
+    pub name: String,
+    pub race: Option<String>,
+    pub team: u8,
+    pub apm: Option<u32>,
+    pub eapm: Option<u32>,
+    pub build_order: Vec<BuildStep>,
+}
+
+// What the app needs from a replay, whether it was decoded by the built-in
+// parser or by screp.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    pub frames: u32,
+    pub map: Option<String>,
+    // Unix time the game started, when the replay records it.
+    pub played_at: Option<u64>,
+    pub players: Vec<ReplayPlayerSummary>,
+    pub winner_team: Option<u8>,
+    // Lobby game type id; 0 when unknown.
+    pub game_type: u16,
+}
+
+// Capture the Flag, Greed, Slaughter, Sudden Death, Use Map Settings, Team CTF
+// and Iron Man games; melee, FFA, ladder and team melee modes are not listed.
+const CUSTOM_GAME_TYPES: [u16; 7] = [0x05, 0x06, 0x07, 0x08, 0x0A, 0x0D, 0x10];
+
+// How a replay relates to one of our players. Only `OneVsOne` games count
+// towards opponent records and profile history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameKind {
+    OneVsOne,
+    Team,
+    Observer,
+    Custom,
+}
+
+impl GameKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            GameKind::OneVsOne => "1v1",
+            GameKind::Team => "team game",
+            GameKind::Observer => "observed game",
+            GameKind::Custom => "custom game",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameSides<'a> {
+    pub kind: GameKind,
+    pub me: Option<&'a ReplayPlayerSummary>,
+    pub teammates: Vec<&'a ReplayPlayerSummary>,
+    pub opponents: Vec<&'a ReplayPlayerSummary>,
+}
+
+impl<'a> GameSides<'a> {
+    // Us and our opponent, for 1v1 games only.
+    pub fn one_vs_one(&self) -> Option<(&'a ReplayPlayerSummary, &'a ReplayPlayerSummary)> {
+        match (self.kind, self.me, self.opponents.as_slice()) {
+            (GameKind::OneVsOne, Some(me), [opponent]) => Some((me, *opponent)),
+            _ => None,
+        }
+    }
+}
+
+impl ReplaySummary {
+    pub fn duration_secs(&self) -> u32 {
+        (u64::from(self.frames) * replay_parser::FRAME_MS / 1000) as u32
+    }
+
+    pub fn player(&self, name: &str) -> Option<&ReplayPlayerSummary> {
+        self.players
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn is_custom(&self) -> bool {
+        CUSTOM_GAME_TYPES.contains(&self.game_type)
+    }
+
+    // Splits the players into `self_name`, teammates and opponents. Games with
+    // more than two sides are treated as custom.
+    pub fn sides(&self, self_name: &str) -> GameSides<'_> {
+        let Some(me) = self.player(self_name) else {
+            return GameSides {
+                kind: GameKind::Observer,
+                me: None,
+                teammates: Vec::new(),
+                opponents: Vec::new(),
+            };
+        };
+        let teammates: Vec<&ReplayPlayerSummary> = self
+            .players
+            .iter()
+            .filter(|p| p.team == me.team && p.player_id != me.player_id)
+            .collect();
+        let opponents: Vec<&ReplayPlayerSummary> =
+            self.players.iter().filter(|p| p.team != me.team).collect();
+        let opposing_teams: std::collections::BTreeSet<u8> =
+            opponents.iter().map(|p| p.team).collect();
+        let kind = if self.is_custom() || opposing_teams.len() != 1 {
+            GameKind::Custom
+        } else if teammates.is_empty() && opponents.len() == 1 {
+            GameKind::OneVsOne
+        } else {
+            GameKind::Team
+        };
+        GameSides {
+            kind,
+            me: Some(me),
+            teammates,
+            opponents,
+        }
+    }
+}
+
+impl From<&Replay> for ReplaySummary {
+    fn from(replay: &Replay) -> Self {
+        Self {
+            frames: replay.header.frames,
+            map: normalize_map_name(&replay.header.map_name),
+            played_at: Some(u64::from(replay.header.start_time)).filter(|t| *t > 0),
+            players: replay
+                .header
+                .players
+                .iter()
+                .map(|p| ReplayPlayerSummary {
+                    player_id: p.player_id,
+                    name: p.name.clone(),
+                    race: p.race.name().map(str::to_string),
+                    team: p.team,
+                    apm: replay.apm(p.player_id),
+                    eapm: replay.eapm(p.player_id),
+                    build_order: build_order_from_replay(replay, p.player_id, p.race.name()),
+                })
+                .collect(),
+            winner_team: replay.winner_team,
+            game_type: replay.header.game_type,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct ScrepJson {
+    header: ScrepHeader,
+    computed: ScrepComputed,
+    commands: ScrepCommands,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct ScrepHeader {
+    frames: u32,
+    start_time: String,
+    map: String,
+    #[serde(rename = "Type")]
+    kind: ScrepNamed,
+    players: Vec<ScrepPlayer>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct ScrepPlayer {
+    #[serde(rename = "ID")]
+    id: u8,
+    name: String,
+    race: ScrepNamed,
+    team: u8,
+    #[serde(rename = "Type")]
+    kind: ScrepNamed,
+    observer: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct ScrepNamed {
+    name: String,
+    #[serde(rename = "ID")]
+    id: u16,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct ScrepCommands {
+    cmds: Vec<ScrepCommand>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct ScrepCommand {
+    frame: u32,
+    #[serde(rename = "PlayerID")]
+    player_id: u8,
+    #[serde(rename = "Type")]
+    kind: ScrepNamed,
+    unit: Option<ScrepNamed>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct ScrepComputed {
+    winner_team: u8,
+    player_descs: Vec<ScrepPlayerDesc>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct ScrepPlayerDesc {
+    #[serde(rename = "PlayerID")]
+    player_id: u8,
+    #[serde(rename = "APM")]
+    apm: u32,
+    #[serde(rename = "EAPM")]
+    eapm: u32,
+}
+
+fn summary_from_screp_json(text: &str) -> Result<ReplaySummary> {
+    let parsed: ScrepJson = serde_json::from_str(text).context("deserialize screp output")?;
+    let descs = &parsed.computed.player_descs;
+    let cmds = &parsed.commands.cmds;
+    let mut players: Vec<ReplayPlayerSummary> = parsed
+        .header
+        .players
+        .into_iter()
+        .filter(|p| !p.observer && !p.name.trim().is_empty())
+        .filter(|p| matches!(p.kind.name.as_str(), "Human" | "Computer"))
+        .map(|p| ReplayPlayerSummary {
+            build_order: build_order_from_actions(
+                Some(p.race.name.as_str()),
+                cmds.iter()
+                    .filter(|c| c.player_id == p.id)
+                    .filter_map(|c| Some((c.frame, c.kind.id as u8, c.unit.as_ref()?.id))),
+            ),
+            player_id: p.id,
+            apm: descs.iter().find(|d| d.player_id == p.id).map(|d| d.apm),
+            eapm: descs.iter().find(|d| d.player_id == p.id).map(|d| d.eapm),
+            race: match p.race.name.as_str() {
+                "Zerg" | "Terran" | "Protoss" | "Random" => Some(p.race.name),
+                _ => None,
+            },
+            name: p.name,
+            team: p.team,
+        })
+        .collect();
+    let mut winner_team = Some(parsed.computed.winner_team).filter(|team| *team != 0);
+    if players.len() > 1 && players.windows(2).all(|w| w[0].team == w[1].team) {
+        for (idx, player) in players.iter_mut().enumerate() {
+            player.team = idx as u8 + 1;
+        }
+        // The shared team says nothing about who won, so the last player
+        // left in the game is taken as the winner, as the built-in parser does.
+        let mut remaining: Vec<&ReplayPlayerSummary> = players.iter().collect();
+        winner_team = cmds
+            .iter()
+            .filter(|c| c.kind.id == u16::from(CMD_LEAVE_GAME))
+            .find_map(|leave| {
+                remaining.retain(|p| p.player_id != leave.player_id);
+                match remaining.as_slice() {
+                    [winner] => Some(winner.team),
+                    _ => None,
+                }
+            });
+    }
+    Ok(ReplaySummary {
+        frames: parsed.header.frames,
+        map: normalize_map_name(&parsed.header.map),
+        played_at: DateTime::parse_from_rfc3339(&parsed.header.start_time)
+            .ok()
+            .and_then(|t| u64::try_from(t.timestamp()).ok()),
+        players,
+        winner_team,
+        game_type: parsed.header.kind.id,
+    })
+}
+
+// Reads a replay with the built-in parser, falling back to the screp binary
+// when the replay cannot be decoded and screp is installed.
+pub fn read_replay_summary(cfg: &Config, path: &Path) -> Result<ReplaySummary> {
+    match replay_parser::parse_file(path) {
+        Ok(replay) => Ok(ReplaySummary::from(&replay)),
+        Err(err) if which(&cfg.screp_cmd).is_ok() => {
+            tracing::debug!(error = %err, path = %path.display(), "falling back to screp");
+            summary_from_screp_json(&run_screp_json(cfg, path)?)
+        }
+        Err(err) => Err(anyhow!(err).context(format!("parse replay {}", path.display()))),
+    }
+}
+
+// Downloads `url` to `path`, resuming with a range request when a partial
+// file from an earlier attempt is already there.
 pub fn download_replay(client: &Client, url: &str, path: &Path) -> Result<()> {
-    let mut response = client
-        .get(url)
+    let existing = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut request = client.get(url);
+    if existing > 0 {
+        request = request.header(RANGE, format!("bytes={existing}-"));
+    }
+    let response = request
         .send()
-        .with_context(|| format!("send replay download request: {}", url))?
+        .with_context(|| format!("send replay download request: {}", url))?;
+    if existing > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file already holds the whole replay.
+        return Ok(());
+    }
+    let mut response = response
         .error_for_status()
         .with_context(|| format!("replay download HTTP status: {}", url))?;
-    let mut file =
-        fs::File::create(path).with_context(|| format!("create replay file at {:?}", path))?;
+    let mut file = if response.status() == StatusCode::PARTIAL_CONTENT {
+        fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open partial replay file at {:?}", path))?
+    } else {
+        fs::File::create(path).with_context(|| format!("create replay file at {:?}", path))?
+    };
     io::copy(&mut response, &mut file)
         .with_context(|| format!("write replay data to {:?}", path))?;
     Ok(())
 }
 
-pub fn run_screp_overview(cfg: &Config, path: &Path) -> Result<String> {
+// Whether a failed `download_replay` can be resumed from its partial file:
+// the transfer broke off rather than the server refusing the replay.
+pub fn is_network_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| !e.is_status())
+            || cause.downcast_ref::<io::Error>().is_some()
+    })
+}
+
+// screp prints JSON unless -overview is given; -map and -cmds add the map
+// and command sections.
+fn run_screp_json(cfg: &Config, path: &Path) -> Result<String> {
     let output = Command::new(&cfg.screp_cmd)
-        .arg("-overview")
+        .arg("-map")
+        .arg("-cmds")
         .arg(path)
         .output()
         .with_context(|| format!("failed to run screp on {:?}", path))?;
@@ -65,3 +396,112 @@ pub fn sanitize_component(input: &str) -> String {
         cleaned.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::replay_player;
+
+    #[test]
+    fn broken_transfers_count_as_network_errors() {
+        let broken = anyhow::Error::new(io::Error::from(io::ErrorKind::ConnectionReset))
+            .context("write replay data");
+        assert!(is_network_error(&broken));
+        assert!(!is_network_error(&anyhow!("empty replay url")));
+    }
+
+    #[test]
+    fn screp_json_becomes_summary() {
+        let json = r#"{
+            "Header": {
+                "Frames": 14400,
+                "StartTime": "2025-01-02T03:04:05Z",
+                "Map": "\u0003Polypoid \u00041.65",
+                "Type": {"Name": "Melee", "ID": 2},
+                "Players": [
+                    {"ID": 0, "Name": "Alice Smith", "Race": {"Name": "Protoss"}, "Team": 0,
+                     "Type": {"Name": "Human"}, "Observer": false},
+                    {"ID": 1, "Name": "Bob", "Race": {"Name": "Zerg"}, "Team": 0,
+                     "Type": {"Name": "Human"}, "Observer": false},
+                    {"ID": 2, "Name": "Watcher", "Race": {"Name": "Terran"}, "Team": 0,
+                     "Type": {"Name": "Human"}, "Observer": true}
+                ]
+            },
+            "Commands": {
+                "Cmds": [
+                    {"Frame": 500, "PlayerID": 0, "Type": {"Name": "Build", "ID": 12},
+                     "Unit": {"Name": "Pylon", "ID": 156}},
+                    {"Frame": 520, "PlayerID": 1, "Type": {"Name": "Hotkey", "ID": 19}}
+                ]
+            },
+            "Computed": {
+                "WinnerTeam": 2,
+                "PlayerDescs": [{"PlayerID": 0, "APM": 180, "EAPM": 150},
+                                {"PlayerID": 1, "APM": 240, "EAPM": 170}]
+            }
+        }"#;
+        let summary = summary_from_screp_json(json).expect("summary");
+        assert_eq!(summary.duration_secs(), 604);
+        assert_eq!(summary.map.as_deref(), Some("Polypoid"));
+        assert_eq!(summary.played_at, Some(1_735_787_045));
+        assert_eq!(summary.players.len(), 2);
+        let alice = summary.player("alice smith").expect("alice");
+        assert_eq!(
+            (alice.team, alice.apm, alice.eapm),
+            (1, Some(180), Some(150))
+        );
+        assert_eq!(alice.build_order[0].unit, "Pylon");
+        assert_eq!(alice.build_order[0].secs, 21);
+        assert!(summary.players[1].build_order.is_empty());
+        assert_eq!(summary.players[1].race.as_deref(), Some("Zerg"));
+        // Teams were renumbered and nobody left, so the winner is unknown.
+        assert_eq!(summary.winner_team, None);
+        assert_eq!(summary.game_type, 2);
+
+        let bob_leaves = json.replacen(
+            r#""Cmds": ["#,
+            r#""Cmds": [{"Frame": 900, "PlayerID": 1, "Type": {"Name": "Leave Game", "ID": 87}},"#,
+            1,
+        );
+        let summary = summary_from_screp_json(&bob_leaves).expect("summary");
+        assert_eq!(summary.winner_team, Some(1));
+
+        let split = json.replacen(r#""Team": 0"#, r#""Team": 1"#, 1).replacen(
+            r#""Team": 0"#,
+            r#""Team": 2"#,
+            1,
+        );
+        let summary = summary_from_screp_json(&split).expect("summary");
+        assert_eq!(summary.winner_team, Some(2));
+    }
+
+    #[test]
+    fn sides_classify_team_observer_and_custom_games() {
+        let player = |id: u8, name: &str, team: u8| replay_player(id, name, None, team);
+        let mut summary = ReplaySummary {
+            players: vec![player(0, "Me", 1), player(1, "Rival", 2)],
+            game_type: 2,
+            ..ReplaySummary::default()
+        };
+        let sides = summary.sides("me");
+        assert_eq!(sides.kind, GameKind::OneVsOne);
+        assert_eq!(
+            sides.one_vs_one().map(|(_, opp)| opp.name.as_str()),
+            Some("Rival")
+        );
+        assert_eq!(summary.sides("Watcher").kind, GameKind::Observer);
+
+        summary.players.push(player(2, "Ally", 1));
+        summary.players.push(player(3, "Rival2", 2));
+        let sides = summary.sides("Me");
+        assert_eq!(sides.kind, GameKind::Team);
+        assert_eq!((sides.teammates.len(), sides.opponents.len()), (1, 2));
+        assert!(sides.one_vs_one().is_none());
+
+        summary.players[3].team = 3;
+        assert_eq!(summary.sides("Me").kind, GameKind::Custom);
+        summary.players.truncate(2);
+        summary.game_type = 0x0A;
+        assert_eq!(summary.sides("Me").kind, GameKind::Custom);
+    }
+}
@@ -0,0 +1,529 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::config::Config;
+use crate::replay_download::{ReplayStorage, SCOUTING_FOLDER};
+use crate::replay_io::{
+    read_replay_summary, sanitize_component, ReplayPlayerSummary, ReplaySummary,
+};
+use crate::replay_manifest::ReplayManifest;
+
+pub const DEFAULT_FOLDER_TEMPLATE: &str = "{profile}/{matchup}";
+pub const DEFAULT_FILE_TEMPLATE: &str = "{date}_{p1}({r1})_vs_{p2}({r2})";
+
+const PLACEHOLDERS: &[&str] = &[
+    "profile", "matchup", "date", "time", "p1", "r1", "p2", "r2", "map", "result", "duration",
+    "rating", "gateway",
+];
+
+// Folder and file name templates for replays stored in the library, relative
+// to its `bwtools` root. Placeholders are written as `{name}`; the folder
+// template separates folders with `/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayLayout {
+    folder: String,
+    file: String,
+}
+
+impl Default for ReplayLayout {
+    fn default() -> Self {
+        Self {
+            folder: DEFAULT_FOLDER_TEMPLATE.to_string(),
+            file: DEFAULT_FILE_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl ReplayLayout {
+    pub fn new(folder: &str, file: &str) -> Result<Self> {
+        check_template(folder)?;
+        check_template(file)?;
+        if file.trim().is_empty() {
+            bail!("the file name template is empty");
+        }
+        if file.contains(['/', '\\']) {
+            bail!("the file name template cannot contain folders: {file}");
+        }
+        Ok(Self {
+            folder: folder.trim().trim_matches('/').to_string(),
+            file: file.trim().trim_end_matches(".rep").to_string(),
+        })
+    }
+
+    pub fn folder(&self) -> &str {
+        &self.folder
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    // Path of a replay under the library root. Empty folder segments are
+    // dropped and an empty file name falls back to "replay".
+    pub fn relative_path(&self, fields: &ReplayNameFields) -> PathBuf {
+        let mut path: PathBuf = self
+            .folder
+            .split('/')
+            .filter_map(|segment| render(segment, fields))
+            .collect();
+        let file = render(&self.file, fields).unwrap_or_else(|| "replay".to_string());
+        path.push(format!("{file}.rep"));
+        path
+    }
+}
+
+fn check_template(template: &str) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let end = after
+            .find('}')
+            .with_context(|| format!("unclosed {{ in template {template}"))?;
+        let name = &after[..end];
+        if !PLACEHOLDERS.contains(&name) {
+            bail!(
+                "unknown placeholder {{{name}}} in template {template}; expected one of {}",
+                PLACEHOLDERS.join(", ")
+            );
+        }
+        rest = &after[end + 1..];
+    }
+    Ok(())
+}
+
+// One folder or file name with its placeholders filled in, or `None` when
+// nothing but separators is left.
+fn render(template: &str, fields: &ReplayNameFields) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            rest = after;
+            break;
+        };
+        let value = fields.value(&after[..end]);
+        if !value.trim().is_empty() {
+            out.push_str(&sanitize_component(&value));
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    // Unknown values leave doubled spaces and dangling separators behind.
+    let out = out
+        .split(' ')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let trimmed = out.trim_matches(|c: char| matches!(c, '_' | '-' | ' ' | '.'));
+    (!trimmed.is_empty()).then(|| sanitize_component(trimmed))
+}
+
+// Values the layout templates can use. Names and races are from our side
+// first; anything unknown renders empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayNameFields {
+    pub profile: String,
+    pub matchup: String,
+    pub played_at: Option<u64>,
+    pub p1: String,
+    pub r1: String,
+    pub p2: String,
+    pub r2: String,
+    pub map: Option<String>,
+    // Whether our side won.
+    pub won: Option<bool>,
+    pub duration_secs: Option<u32>,
+    pub rating: Option<i32>,
+    pub gateway: Option<u16>,
+}
+
+impl ReplayNameFields {
+    // Fields for `summary` as seen by `perspective`. Team games join the names
+    // on each side; replays `perspective` only observed yield `None`.
+    pub fn from_summary(
+        summary: &ReplaySummary,
+        perspective: &str,
+        played_at: Option<u64>,
+    ) -> Option<Self> {
+        let sides = summary.sides(perspective);
+        let me = sides.me?;
+        let first = sides.opponents.first()?;
+        let race = |p: &ReplayPlayerSummary| race_letter(p.race.as_deref().unwrap_or_default());
+        let join = |players: &mut dyn Iterator<Item = &ReplayPlayerSummary>| {
+            players.map(|p| p.name.trim()).collect::<Vec<_>>().join("+")
+        };
+        let p1 = join(&mut std::iter::once(me).chain(sides.teammates.iter().copied()));
+        let (r1, r2) = (race(me), race(first));
+        Some(Self {
+            profile: p1.clone(),
+            matchup: format!("{r1}v{r2}"),
+            played_at: played_at.or(summary.played_at),
+            p2: join(&mut sides.opponents.iter().copied()),
+            p1,
+            r1,
+            r2,
+            map: summary.map.clone(),
+            won: summary.winner_team.map(|winner| winner == me.team),
+            duration_secs: Some(summary.duration_secs()),
+            rating: None,
+            gateway: None,
+        })
+    }
+
+    fn value(&self, name: &str) -> String {
+        let time = |format: &str| {
+            self.played_at
+                .and_then(valid_time)
+                .map(|dt| dt.format(format).to_string())
+                .unwrap_or_default()
+        };
+        match name {
+            "profile" => self.profile.clone(),
+            "matchup" => self.matchup.clone(),
+            "date" => time("%Y%m%d"),
+            "time" => time("%H%M"),
+            "p1" => self.p1.clone(),
+            "r1" => self.r1.clone(),
+            "p2" => self.p2.clone(),
+            "r2" => self.r2.clone(),
+            "map" => self.map.clone().unwrap_or_default(),
+            "result" => match self.won {
+                Some(true) => "W".to_string(),
+                Some(false) => "L".to_string(),
+                None => String::new(),
+            },
+            "duration" => self
+                .duration_secs
+                .map(|secs| format!("{}m{:02}s", secs / 60, secs % 60))
+                .unwrap_or_default(),
+            "rating" => self.rating.map(|r| r.to_string()).unwrap_or_default(),
+            "gateway" => self
+                .gateway
+                .map(|gw| crate::gateway::label(gw).to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+}
+
+pub(crate) fn race_letter(race: &str) -> String {
+    let trimmed = race.trim();
+    let Some(first) = trimmed.chars().next() else {
+        return "U".to_string();
+    };
+    first.to_ascii_uppercase().to_string()
+}
+
+// Ladder timestamps of 0 or u32::MAX mean "unknown".
+fn valid_time(ts_secs: u64) -> Option<DateTime<Utc>> {
+    if ts_secs == 0 || ts_secs == u32::MAX as u64 {
+        return None;
+    }
+    DateTime::<Utc>::from_timestamp(ts_secs as i64, 0)
+}
+
+#[derive(Debug, Default)]
+pub struct RelayoutReport {
+    pub moved: usize,
+    pub unchanged: usize,
+    pub missing: usize,
+    pub unreadable: usize,
+    pub errors: Vec<String>,
+}
+
+impl std::fmt::Display for RelayoutReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Replays: {} moved, {} already in place, {} missing, {} unreadable",
+            self.moved, self.unchanged, self.missing, self.unreadable
+        )?;
+        for err in &self.errors {
+            write!(f, "\nError: {err}")?;
+        }
+        Ok(())
+    }
+}
+
+// Moves every replay in the manifest to where `layout` puts it and records
+// the new paths. The toon a replay was saved for is treated as our side, then
+// the first of `perspectives` found in it, then its first player. With
+// `dry_run` nothing is moved and each planned move is passed to `planned`.
+pub fn relayout(
+    cfg: &Config,
+    storage: &ReplayStorage,
+    layout: &ReplayLayout,
+    perspectives: &[String],
+    dry_run: bool,
+    mut planned: impl FnMut(&Path, &Path),
+) -> Result<RelayoutReport> {
+    let mut report = RelayoutReport::default();
+    let manifest_path = storage.manifest_path();
+    let mut manifest = ReplayManifest::load(&manifest_path);
+    let root = storage.bwtools_root();
+
+    let mut ids: Vec<String> = manifest.entries.keys().cloned().collect();
+    ids.sort_by(|a, b| manifest.entries[a].path.cmp(&manifest.entries[b].path));
+    let mut moves = Vec::new();
+    for id in ids {
+        let Some(entry) = manifest.entries.get_mut(&id) else {
+            continue;
+        };
+        let current = PathBuf::from(&entry.path);
+        if !current.is_file() {
+            report.missing += 1;
+            continue;
+        }
+        let summary = match read_replay_summary(cfg, &current) {
+            Ok(summary) => summary,
+            Err(err) => {
+                tracing::debug!(error = %err, path = %current.display(), "relayout skipped replay");
+                report.unreadable += 1;
+                continue;
+            }
+        };
+        let perspective = entry
+            .toon
+            .iter()
+            .chain(perspectives)
+            .find(|name| summary.player(name).is_some())
+            .map(String::as_str)
+            .or_else(|| summary.players.first().map(|p| p.name.as_str()))
+            .unwrap_or_default();
+        let mtime = fs::metadata(&current)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        let Some(mut fields) =
+            ReplayNameFields::from_summary(&summary, perspective, summary.played_at.or(mtime))
+        else {
+            report.unreadable += 1;
+            continue;
+        };
+        if let Some(profile) = entry.profile.clone() {
+            fields.profile = profile;
+        }
+        fields.gateway = entry.gateway;
+        fields.rating = entry.rating;
+
+        // Scouting downloads are laid out again inside their own folder.
+        let mut relative = layout.relative_path(&fields);
+        if current.starts_with(storage.scouting_dir()) {
+            relative = Path::new(SCOUTING_FOLDER).join(relative);
+        }
+        let wanted = root.join(&relative);
+        if already_in_place(&current, &wanted) {
+            report.unchanged += 1;
+            continue;
+        }
+        if dry_run {
+            planned(&current, &wanted);
+            report.moved += 1;
+            continue;
+        }
+        let moved = storage
+            .prepare_path(&relative)
+            .with_context(|| format!("create folder for {}", wanted.display()))
+            .and_then(|target| {
+                fs::rename(&current, &target).with_context(|| {
+                    format!("move {} to {}", current.display(), target.display())
+                })?;
+                Ok(target)
+            });
+        match moved {
+            Ok(path) => {
+                planned(&current, &path);
+                entry.path = path.to_string_lossy().into_owned();
+                moves.push((id, entry.path.clone()));
+                report.moved += 1;
+            }
+            Err(err) => report.errors.push(format!("{err:#}")),
+        }
+    }
+
+    if !dry_run && report.moved > 0 {
+        ReplayManifest::update(&manifest_path, |current| {
+            for (id, path) in moves {
+                if let Some(entry) = current.entries.get_mut(&id) {
+                    entry.path = path;
+                }
+            }
+        })
+        .context("failed to write replay manifest")?;
+        remove_empty_dirs(&root);
+    }
+    Ok(report)
+}
+
+// Whether `current` has the wanted name, or the `-N` variant it was given
+// because another replay already had that name.
+fn already_in_place(current: &Path, wanted: &Path) -> bool {
+    if current == wanted {
+        return true;
+    }
+    if current.parent() != wanted.parent() {
+        return false;
+    }
+    let name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().trim_end_matches(".rep").to_string())
+            .unwrap_or_default()
+    };
+    let wanted = name(wanted);
+    name(current)
+        .strip_prefix(&wanted)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+// Removes folders a relayout emptied, leaving `.meta` alone.
+fn remove_empty_dirs(dir: &Path) -> bool {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return false;
+    };
+    let mut empty = true;
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if !is_dir || entry.file_name() == ".meta" {
+            empty = false;
+        } else if remove_empty_dirs(&path) {
+            empty &= fs::remove_dir(&path).is_ok();
+        } else {
+            empty = false;
+        }
+    }
+    empty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> ReplayNameFields {
+        ReplayNameFields {
+            profile: "Alice".to_string(),
+            matchup: "TvZ".to_string(),
+            played_at: Some(1_704_067_200),
+            p1: "Alice".to_string(),
+            r1: "T".to_string(),
+            p2: "Bob".to_string(),
+            r2: "Z".to_string(),
+            map: Some("Polypoid 1.65".to_string()),
+            won: Some(false),
+            duration_secs: Some(754),
+            rating: Some(2150),
+            gateway: Some(30),
+        }
+    }
+
+    #[test]
+    fn replay_date_prefix_filters_sentinel_and_formats_valid_epoch() {
+        assert_eq!(valid_time(0), None);
+        assert_eq!(valid_time(u32::MAX as u64), None);
+        assert_eq!(
+            valid_time(1_704_067_200).map(|t| t.format("%Y%m%d").to_string()),
+            Some("20240101".to_string())
+        );
+    }
+
+    #[test]
+    fn default_layout_keeps_the_classic_names() {
+        let layout = ReplayLayout::default();
+        assert_eq!(
+            layout.relative_path(&fields()),
+            PathBuf::from("Alice/TvZ/20240101_Alice(T)_vs_Bob(Z).rep")
+        );
+        let undated = ReplayNameFields {
+            played_at: Some(u32::MAX as u64),
+            ..fields()
+        };
+        assert_eq!(
+            layout.relative_path(&undated),
+            PathBuf::from("Alice/TvZ/Alice(T)_vs_Bob(Z).rep")
+        );
+        assert_eq!(race_letter("zerg"), "Z");
+        assert_eq!(race_letter(""), "U");
+    }
+
+    #[test]
+    fn custom_templates_fill_and_sanitize_placeholders() {
+        let layout = ReplayLayout::new(
+            "{gateway}/{map}/{rating}",
+            "{date}-{time} {result} {p2} {duration}.rep",
+        )
+        .expect("valid layout");
+        let mut values = fields();
+        values.map = Some("Fighting Spirit: 1.3".to_string());
+        assert_eq!(
+            layout.relative_path(&values),
+            PathBuf::from("Korea/Fighting Spirit_ 1.3/2150/20240101-0000 L Bob 12m34s.rep")
+        );
+        values.rating = None;
+        values.won = None;
+        assert_eq!(
+            layout.relative_path(&values),
+            PathBuf::from("Korea/Fighting Spirit_ 1.3/20240101-0000 Bob 12m34s.rep")
+        );
+
+        assert!(ReplayLayout::new("{profile}", "{winner}").is_err());
+        assert!(ReplayLayout::new("{profile", "{p1}").is_err());
+        assert!(ReplayLayout::new("", "{p1}/{p2}").is_err());
+        assert!(ReplayLayout::new("", "").is_err());
+    }
+
+    #[test]
+    fn relayout_leaves_numbered_duplicates_in_place() {
+        use crate::replay_manifest::ManifestEntry;
+        use crate::replay_parser::tests::sample_replay;
+        use crate::test_support::unique_temp_path;
+
+        let root = unique_temp_path("relayout");
+        let storage = ReplayStorage::new(root.clone());
+        fs::create_dir_all(storage.bwtools_root()).expect("create library");
+        // Two different files of the same game both want the same name.
+        let mut manifest = ReplayManifest::default();
+        for (id, extra) in [("first", 0u8), ("second", 1)] {
+            let path = storage.bwtools_root().join(format!("{id}.rep"));
+            let mut data = sample_replay();
+            data.push(extra);
+            fs::write(&path, data).expect("write replay");
+            let entry = ManifestEntry {
+                path: path.to_string_lossy().into_owned(),
+                saved_at: 1,
+                md5: None,
+                toon: Some("Alice".to_string()),
+                profile: None,
+                gateway: None,
+                rating: None,
+                played_at: None,
+                players: Vec::new(),
+                map: None,
+                duration_secs: None,
+            };
+            manifest.entries.insert(id.to_string(), entry);
+        }
+        manifest
+            .save(&storage.manifest_path())
+            .expect("save manifest");
+
+        let cfg = Config {
+            screp_cmd: "bwtools-missing-screp".to_string(),
+            ..Config::default()
+        };
+        let layout = ReplayLayout::default();
+        let run = || relayout(&cfg, &storage, &layout, &[], false, |_, _| {}).expect("relayout");
+        assert_eq!(run().moved, 2);
+        let again = run();
+        assert_eq!((again.moved, again.unchanged), (0, 2));
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::replay_download::ReplayStorage;
+use crate::replay_index::collect_replays;
+use crate::replay_io::{read_replay_summary, ReplaySummary};
+use crate::replay_layout::ReplayNameFields;
+use crate::staged_write::StagedWrite;
+
+// Held across each reload, change and save of the manifest so download
+// workers and the replay watcher never write a stale copy over each other.
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+// Every replay the library saved, keyed by the ladder's replay hash (or the
+// file's own MD5 for archived games).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReplayManifest {
+    pub entries: HashMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub saved_at: u64,
+    // MD5 of the file as saved, used to find it again after a move.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub md5: Option<String>,
+    // Toon the replay was downloaded or archived for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub toon: Option<String>,
+    // Naming values the replay file itself cannot supply, kept so the library
+    // can be laid out again later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub played_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub players: Vec<ManifestPlayer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestPlayer {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub race: Option<String>,
+    pub team: u8,
+}
+
+impl ReplayManifest {
+    pub fn load(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<ReplayManifest>(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    // Swapped in whole so a reader never sees a half-written manifest.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self).context("serialize replay manifest")?;
+        StagedWrite::new(path, &data)?.commit()
+    }
+
+    // Applies `change` to the manifest as it is on disk now, keeping entries
+    // other writers added since it was last loaded.
+    pub fn update<T>(path: &Path, change: impl FnOnce(&mut Self) -> T) -> Result<T> {
+        let _guard = MANIFEST_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut manifest = Self::load(path);
+        let out = change(&mut manifest);
+        manifest.save(path)?;
+        Ok(out)
+    }
+
+    // Whether `identifier` is stored and its file is still there. Entries for
+    // deleted files do not block the replay from being saved again.
+    pub fn contains_file(&self, identifier: &str) -> bool {
+        self.entries
+            .get(identifier)
+            .is_some_and(|entry| Path::new(&entry.path).is_file())
+    }
+}
+
+impl ManifestEntry {
+    pub fn new(
+        path: &Path,
+        md5: String,
+        toon: &str,
+        summary: &ReplaySummary,
+        fields: &ReplayNameFields,
+    ) -> Self {
+        let mut entry = Self {
+            path: path.to_string_lossy().into_owned(),
+            saved_at: current_timestamp(),
+            md5: Some(md5),
+            toon: Some(toon.to_string()),
+            profile: Some(fields.profile.clone()),
+            gateway: fields.gateway,
+            rating: fields.rating,
+            played_at: fields.played_at,
+            players: Vec::new(),
+            map: None,
+            duration_secs: None,
+        };
+        entry.describe(summary);
+        entry
+    }
+
+    fn describe(&mut self, summary: &ReplaySummary) {
+        self.players = summary
+            .players
+            .iter()
+            .map(|p| ManifestPlayer {
+                name: p.name.clone(),
+                race: p.race.clone(),
+                team: p.team,
+            })
+            .collect();
+        self.map = summary.map.clone();
+        self.duration_secs = Some(summary.duration_secs());
+        self.played_at = self.played_at.or(summary.played_at);
+    }
+}
+
+// Hex MD5 of the replay file, the same digest the ladder API reports.
+pub fn content_hash(data: &[u8]) -> String {
+    format!("{:x}", md5::compute(data))
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|data| content_hash(&data))
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub intact: usize,
+    pub relocated: usize,
+    pub rehashed: usize,
+    pub removed: usize,
+    pub described: usize,
+}
+
+impl std::fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Manifest: {} checked, {} intact, {} relocated, {} rehashed, {} removed, {} described",
+            self.checked, self.intact, self.relocated, self.rehashed, self.removed, self.described
+        )
+    }
+}
+
+// Re-hashes every file in the manifest. Entries whose file moved inside the
+// library are pointed at it again by hash; entries whose file is gone are
+// dropped so the replay can be downloaded again. A file that changed in place
+// keeps its entry under the new hash. Old entries without a hash or metadata
+// get them filled in.
+pub fn verify(cfg: &Config, storage: &ReplayStorage) -> Result<VerifyReport> {
+    let manifest_path = storage.manifest_path();
+    let mut manifest = ReplayManifest::load(&manifest_path);
+    let mut report = VerifyReport::default();
+    // Built on first need: hashing the whole library is slow.
+    let mut by_hash: Option<HashMap<String, PathBuf>> = None;
+    let mut changed = Vec::new();
+    let mut removed = Vec::new();
+
+    let mut ids: Vec<String> = manifest.entries.keys().cloned().collect();
+    ids.sort();
+    for id in ids {
+        let Some(entry) = manifest.entries.get_mut(&id) else {
+            continue;
+        };
+        report.checked += 1;
+        let path = PathBuf::from(&entry.path);
+        let actual = hash_file(&path);
+        // Older entries only have the ladder's hash as their key, which is
+        // not known to match the file, so a file still in place is trusted.
+        let expected = entry.md5.clone();
+        match (expected, actual) {
+            (Some(expected), Some(actual)) if expected == actual => report.intact += 1,
+            (None, Some(actual)) => {
+                entry.md5 = Some(actual);
+                report.intact += 1;
+                changed.push(id.clone());
+            }
+            (expected, actual) => {
+                let wanted = expected.unwrap_or_else(|| id.clone());
+                let index = by_hash.get_or_insert_with(|| hash_library(&storage.bwtools_root()));
+                match index.get(&wanted) {
+                    Some(found) => {
+                        tracing::info!(from = %path.display(), to = %found.display(), "relocated replay");
+                        entry.path = found.to_string_lossy().into_owned();
+                        entry.md5 = Some(wanted);
+                        report.relocated += 1;
+                        changed.push(id.clone());
+                    }
+                    None if actual.is_some() => {
+                        tracing::info!(path = %path.display(), "replay changed in place");
+                        entry.md5 = actual;
+                        report.rehashed += 1;
+                        changed.push(id.clone());
+                    }
+                    None => {
+                        tracing::info!(path = %path.display(), "dropping missing replay from manifest");
+                        manifest.entries.remove(&id);
+                        report.removed += 1;
+                        removed.push(id);
+                        continue;
+                    }
+                }
+            }
+        }
+
+        if entry.players.is_empty() {
+            match read_replay_summary(cfg, Path::new(&entry.path)) {
+                Ok(summary) => {
+                    entry.describe(&summary);
+                    report.described += 1;
+                    changed.push(id.clone());
+                }
+                Err(err) => {
+                    tracing::debug!(error = %err, path = %entry.path, "could not describe replay");
+                }
+            }
+        }
+    }
+
+    // Only the entries checked here are written back; anything saved while
+    // the library was being hashed is kept.
+    ReplayManifest::update(&manifest_path, |current| {
+        for id in removed {
+            current.entries.remove(&id);
+        }
+        for id in changed {
+            if let Some(entry) = manifest.entries.remove(&id) {
+                current.entries.insert(id, entry);
+            }
+        }
+    })
+    .context("failed to write replay manifest")?;
+    Ok(report)
+}
+
+fn hash_library(root: &Path) -> HashMap<String, PathBuf> {
+    let mut files = Vec::new();
+    collect_replays(root, &mut files);
+    files
+        .into_iter()
+        .filter_map(|(path, _, _)| Some((hash_file(&path)?, path)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::unique_temp_path;
+
+    fn entry(path: &Path, md5: Option<String>) -> ManifestEntry {
+        ManifestEntry {
+            path: path.to_string_lossy().into_owned(),
+            saved_at: 1,
+            md5,
+            toon: None,
+            profile: None,
+            gateway: None,
+            rating: None,
+            played_at: None,
+            players: Vec::new(),
+            map: None,
+            duration_secs: None,
+        }
+    }
+
+    #[test]
+    fn updates_keep_entries_written_since_the_last_load() {
+        let path = unique_temp_path("manifest").join("manifest.json");
+        ReplayManifest::update(&path, |m| {
+            m.entries
+                .insert("job".to_string(), entry(Path::new("a.rep"), None));
+        })
+        .expect("first update");
+        // A job started earlier still holds a snapshot without the archive.
+        let snapshot = ReplayManifest::load(&path);
+        ReplayManifest::update(&path, |m| {
+            m.entries
+                .insert("archived".to_string(), entry(Path::new("b.rep"), None));
+        })
+        .expect("archive");
+        assert_eq!(snapshot.entries.len(), 1);
+        ReplayManifest::update(&path, |m| {
+            m.entries
+                .insert("job-2".to_string(), entry(Path::new("c.rep"), None));
+        })
+        .expect("job update");
+        assert_eq!(ReplayManifest::load(&path).entries.len(), 3);
+
+        let _ = fs::remove_dir_all(path.parent().expect("parent"));
+    }
+
+    #[test]
+    fn verify_relocates_moved_files_rehashes_edited_ones_and_drops_missing_ones() {
+        let root = unique_temp_path("verify");
+        let storage = ReplayStorage::new(root.clone());
+        let dir = storage.bwtools_root().join("Alice").join("TvZ");
+        fs::create_dir_all(&dir).expect("create dir");
+
+        let kept = dir.join("kept.rep");
+        fs::write(&kept, b"kept replay").expect("write kept");
+        let moved = dir.join("renamed.rep");
+        fs::write(&moved, b"moved replay").expect("write moved");
+        let moved_hash = content_hash(b"moved replay");
+        let edited = dir.join("edited.rep");
+        fs::write(&edited, b"edited replay").expect("write edited");
+
+        let mut manifest = ReplayManifest::default();
+        manifest
+            .entries
+            .insert("kept".to_string(), entry(&kept, None));
+        manifest.entries.insert(
+            "moved".to_string(),
+            entry(&dir.join("original.rep"), Some(moved_hash.clone())),
+        );
+        manifest
+            .entries
+            .insert("gone".to_string(), entry(&dir.join("gone.rep"), None));
+        manifest.entries.insert(
+            "edited".to_string(),
+            entry(&edited, Some(content_hash(b"original replay"))),
+        );
+        manifest
+            .save(&storage.manifest_path())
+            .expect("save manifest");
+        assert!(!manifest.contains_file("gone"));
+        assert!(manifest.contains_file("kept"));
+
+        let report = verify(&Config::default(), &storage).expect("verify");
+        assert_eq!(
+            (
+                report.checked,
+                report.intact,
+                report.relocated,
+                report.rehashed,
+                report.removed
+            ),
+            (4, 1, 1, 1, 1)
+        );
+        let manifest = ReplayManifest::load(&storage.manifest_path());
+        assert_eq!(
+            manifest.entries["kept"].md5.as_deref(),
+            Some(content_hash(b"kept replay").as_str())
+        );
+        assert_eq!(manifest.entries["moved"].path, moved.to_string_lossy());
+        assert!(!manifest.entries.contains_key("gone"));
+        assert_eq!(
+            manifest.entries["edited"].md5.as_deref(),
+            Some(content_hash(b"edited replay").as_str())
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,601 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::ZlibDecoder;
+use thiserror::Error;
+
+const REPLAY_ID_LEN: usize = 4;
+const HEADER_LEN: usize = 0x279;
+const CHUNK_LEN: usize = 8192;
+// The header has 12 slots, but Remastered only uses the last four for
+// observers, who are left out of the player list.
+const PLAYER_SLOTS: usize = 8;
+const PLAYER_SLOT_LEN: usize = 36;
+const PLAYERS_OFFSET: usize = 0xA1;
+// Fastest game speed runs one frame every 42ms.
+pub const FRAME_MS: u64 = 42;
+
+pub(crate) const CMD_LEAVE_GAME: u8 = 0x57;
+
+#[derive(Debug, Error)]
+pub enum ReplayParseError {
+    #[error("read replay {path}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("not a StarCraft replay")]
+    NotAReplay,
+    #[error("replay data ends early")]
+    Truncated,
+    #[error("inflate replay section")]
+    Inflate(#[source] io::Error),
+    #[error("explode replay section")]
+    Explode(#[source] explode::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayFormat {
+    // "reRS" replays: PKWARE-imploded sections before 1.18, zlib from 1.18
+    // until the header moved in 1.21.
+    Legacy,
+    // Remastered 1.21+ replays with zlib-compressed sections.
+    Modern,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerKind {
+    Human,
+    Computer,
+    Other(u8),
+}
+
+impl PlayerKind {
+    fn from_id(id: u8) -> Self {
+        match id {
+            1 => Self::Computer,
+            2 => Self::Human,
+            other => Self::Other(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayRace {
+    Zerg,
+    Terran,
+    Protoss,
+    Random,
+    Unknown(u8),
+}
+
+impl ReplayRace {
+    fn from_id(id: u8) -> Self {
+        match id {
+            0 => Self::Zerg,
+            1 => Self::Terran,
+            2 => Self::Protoss,
+            6 => Self::Random,
+            other => Self::Unknown(other),
+        }
+    }
+
+    // Race names as the rest of the app spells them.
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            Self::Zerg => Some("Zerg"),
+            Self::Terran => Some("Terran"),
+            Self::Protoss => Some("Protoss"),
+            Self::Random => Some("Random"),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayPlayer {
+    pub slot_id: u16,
+    pub player_id: u8,
+    pub kind: PlayerKind,
+    pub race: ReplayRace,
+    pub team: u8,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayHeader {
+    pub format: ReplayFormat,
+    pub engine: u8,
+    pub frames: u32,
+    pub start_time: u32,
+    pub title: String,
+    pub map_name: String,
+    pub map_width: u16,
+    pub map_height: u16,
+    pub game_type: u16,
+    pub host: String,
+    pub players: Vec<ReplayPlayer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayCommand {
+    pub frame: u32,
+    pub player_id: u8,
+    pub code: u8,
+    pub params: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub commands: Vec<ReplayCommand>,
+    pub winner_team: Option<u8>,
+}
+
+impl Replay {
+    // Actions per minute up to the player's last action, ignoring
+    // keep-alives, lobby traffic, chat and leaving.
+    pub fn apm(&self, player_id: u8) -> Option<u32> {
+        let mut count = 0u64;
+        let mut last_frame = 0u32;
+        for cmd in self
+            .commands
+            .iter()
+            .filter(|c| c.player_id == player_id && is_action(c.code))
+        {
+            count += 1;
+            last_frame = cmd.frame;
+        }
+        let minutes = (u64::from(last_frame) * FRAME_MS) as f64 / 60_000.0;
+        if count == 0 || minutes <= 0.0 {
+            return None;
+        }
+        Some((count as f64 / minutes).round() as u32)
+    }
+
+    // Like `apm`, but skips actions that had no effect: an identical command
+    // repeated within `REPEAT_FRAMES`, or a selection replaced by another
+    // before it was used.
+    pub fn eapm(&self, player_id: u8) -> Option<u32> {
+        let actions: Vec<&ReplayCommand> = self
+            .commands
+            .iter()
+            .filter(|c| c.player_id == player_id && is_action(c.code))
+            .collect();
+        let last_frame = actions.last()?.frame;
+        let mut count = 0u64;
+        for (idx, cmd) in actions.iter().enumerate() {
+            let prev = idx.checked_sub(1).map(|i| actions[i]);
+            let repeated = prev.is_some_and(|p| {
+                p.code == cmd.code
+                    && p.params == cmd.params
+                    && cmd.frame.abs_diff(p.frame) < REPEAT_FRAMES
+            });
+            let reselected = actions.get(idx + 1).is_some_and(|next| {
+                is_selection(cmd)
+                    && is_selection(next)
+                    && next.frame.abs_diff(cmd.frame) < REPEAT_FRAMES
+            });
+            if !repeated && !reselected {
+                count += 1;
+            }
+        }
+        let minutes = (u64::from(last_frame) * FRAME_MS) as f64 / 60_000.0;
+        if count == 0 || minutes <= 0.0 {
+            return None;
+        }
+        Some((count as f64 / minutes).round() as u32)
+    }
+}
+
+// About a third of a second at fastest speed.
+const REPEAT_FRAMES: u32 = 8;
+
+fn is_selection(cmd: &ReplayCommand) -> bool {
+    match cmd.code {
+        0x09 | 0x63 => true,
+        // Hotkey recall; the first parameter is 0 for assign.
+        0x13 => cmd.params.first() == Some(&1),
+        _ => false,
+    }
+}
+
+fn is_action(code: u8) -> bool {
+    !matches!(code, 0x05..=0x08 | 0x37..=0x48 | 0x54..=0x57 | 0x5C)
+}
+
+pub fn parse_file(path: &Path) -> Result<Replay, ReplayParseError> {
+    let data = fs::read(path).map_err(|source| ReplayParseError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse(&data)
+}
+
+pub fn parse(data: &[u8]) -> Result<Replay, ReplayParseError> {
+    let format = match data.get(12..16) {
+        Some(b"reRS") => ReplayFormat::Legacy,
+        Some(b"seRS") => ReplayFormat::Modern,
+        _ => return Err(ReplayParseError::NotAReplay),
+    };
+    let mut reader = SectionReader {
+        data,
+        pos: 0,
+        format,
+    };
+    reader.section(REPLAY_ID_LEN)?;
+    let header = reader.header_section()?;
+    let header = parse_header(&header, format)?;
+
+    // The command and map sections are optional for our purposes; a replay
+    // cut short still has a usable header.
+    let commands = match reader.sized_section() {
+        Ok(bytes) => parse_commands(&bytes),
+        Err(err) => {
+            tracing::debug!(error = %err, "replay command section unavailable");
+            Vec::new()
+        }
+    };
+    let winner_team = winner_from_leaves(&header.players, &commands);
+    Ok(Replay {
+        header,
+        commands,
+        winner_team,
+    })
+}
+
+struct SectionReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    format: ReplayFormat,
+}
+
+impl SectionReader<'_> {
+    fn u32(&mut self) -> Result<u32, ReplayParseError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8], ReplayParseError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .ok_or(ReplayParseError::Truncated)?;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or(ReplayParseError::Truncated)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    // Modern replays may carry an extra 32-bit field ahead of the header
+    // section, so both layouts are tried.
+    fn header_section(&mut self) -> Result<Vec<u8>, ReplayParseError> {
+        let start = self.pos;
+        match self.section(HEADER_LEN) {
+            Ok(bytes) => Ok(bytes),
+            Err(err) if self.format == ReplayFormat::Modern => {
+                self.pos = start + 4;
+                self.section(HEADER_LEN).map_err(|_| err)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    // A section whose length is stored in a preceding 4-byte section.
+    fn sized_section(&mut self) -> Result<Vec<u8>, ReplayParseError> {
+        let len = self.section(4)?;
+        let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        self.section(len)
+    }
+
+    fn section(&mut self, size: usize) -> Result<Vec<u8>, ReplayParseError> {
+        let _checksum = self.u32()?;
+        let chunks = self.u32()?;
+        let mut out = Vec::with_capacity(size);
+        for _ in 0..chunks {
+            let len = self.u32()? as usize;
+            let chunk = self.take(len)?;
+            let expected = size.saturating_sub(out.len()).min(CHUNK_LEN);
+            if len >= expected {
+                out.extend_from_slice(chunk);
+            } else if chunk.first() == Some(&0x78) {
+                // Imploded chunks start with a 0 or 1 literal-mode byte, so a
+                // zlib header is unambiguous in either format.
+                let mut inflated = Vec::with_capacity(expected);
+                ZlibDecoder::new(chunk)
+                    .read_to_end(&mut inflated)
+                    .map_err(ReplayParseError::Inflate)?;
+                out.extend_from_slice(&inflated);
+            } else {
+                let exploded = explode::explode(chunk).map_err(ReplayParseError::Explode)?;
+                out.extend_from_slice(&exploded);
+            }
+        }
+        if out.len() < size {
+            return Err(ReplayParseError::Truncated);
+        }
+        out.truncate(size);
+        Ok(out)
+    }
+}
+
+fn parse_header(bytes: &[u8], format: ReplayFormat) -> Result<ReplayHeader, ReplayParseError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(ReplayParseError::Truncated);
+    }
+    let u16_at = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+    let u32_at = |offset: usize| {
+        u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    };
+
+    let mut players: Vec<ReplayPlayer> = (0..PLAYER_SLOTS)
+        .filter_map(|slot| {
+            let start = PLAYERS_OFFSET + slot * PLAYER_SLOT_LEN;
+            let raw = &bytes[start..start + PLAYER_SLOT_LEN];
+            let kind = PlayerKind::from_id(raw[8]);
+            let name = c_string(&raw[11..36]);
+            if name.is_empty() || matches!(kind, PlayerKind::Other(_)) {
+                return None;
+            }
+            Some(ReplayPlayer {
+                slot_id: u16::from_le_bytes([raw[0], raw[1]]),
+                player_id: raw[4],
+                kind,
+                race: ReplayRace::from_id(raw[9]),
+                team: raw[10],
+                name,
+            })
+        })
+        .collect();
+
+    // Melee games leave every player on the same (often zero) team; number
+    // them in slot order so each side is distinguishable.
+    if players.windows(2).all(|w| w[0].team == w[1].team) && players.len() > 1 {
+        for (idx, player) in players.iter_mut().enumerate() {
+            player.team = idx as u8 + 1;
+        }
+    }
+
+    Ok(ReplayHeader {
+        format,
+        engine: bytes[0x00],
+        frames: u32_at(0x01),
+        start_time: u32_at(0x08),
+        title: c_string(&bytes[0x18..0x34]),
+        map_width: u16_at(0x34),
+        map_height: u16_at(0x36),
+        game_type: u16_at(0x3C),
+        host: c_string(&bytes[0x48..0x60]),
+        map_name: c_string(&bytes[0x61..0x7B]),
+        players,
+    })
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+// Parameter length of each command after its id byte, or `None` for ids we
+// cannot size (the rest of that frame block is skipped).
+fn command_len(code: u8, rest: &[u8]) -> Option<usize> {
+    let len = match code {
+        0x05 | 0x08 | 0x10 | 0x11 | 0x18 | 0x19 | 0x1B | 0x1C | 0x1D | 0x27 | 0x2A | 0x2E
+        | 0x31 | 0x33 | 0x34 | 0x36 | 0x38 | 0x39 | 0x3C | 0x54 | 0x5A | 0x5B => 0,
+        0x0F | 0x1A | 0x1E | 0x21 | 0x22 | 0x25 | 0x26 | 0x28 | 0x2B | 0x2C | 0x2D | 0x30
+        | 0x32 | 0x3A | 0x3B | 0x3D | 0x42 | 0x43 | 0x55 | 0x57 => 1,
+        0x0D | 0x13 | 0x1F | 0x20 | 0x23 | 0x29 | 0x35 | 0x41 | 0x44 | 0x45 => 2,
+        0x0E | 0x12 | 0x2F | 0x58 | 0x62 => 4,
+        0x3E => 5,
+        0x37 => 6,
+        0x0C | 0x3F => 7,
+        0x14 | 0x56 => 9,
+        0x15 => 10,
+        0x60 => 11,
+        0x48 | 0x61 => 12,
+        0x40 => 17,
+        0x5C => 81,
+        0x09..=0x0B => 1 + usize::from(*rest.first()?) * 2,
+        0x63..=0x65 => 1 + usize::from(*rest.first()?) * 4,
+        0x06 | 0x07 => {
+            let name_len = rest.get(4..)?.iter().position(|b| *b == 0)?;
+            4 + name_len + 1
+        }
+        _ => return None,
+    };
+    Some(len)
+}
+
+// The command stream is a sequence of frame blocks: frame number, block
+// length, then player id / command id / parameters until the block ends.
+fn parse_commands(bytes: &[u8]) -> Vec<ReplayCommand> {
+    let mut commands = Vec::new();
+    let mut pos = 0;
+    while pos + 5 <= bytes.len() {
+        let frame =
+            u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]);
+        let block_len = usize::from(bytes[pos + 4]);
+        pos += 5;
+        let Some(block) = bytes.get(pos..pos + block_len) else {
+            break;
+        };
+        pos += block_len;
+
+        let mut cursor = 0;
+        while cursor + 2 <= block.len() {
+            let player_id = block[cursor];
+            let code = block[cursor + 1];
+            let rest = &block[cursor + 2..];
+            let Some(len) = command_len(code, rest) else {
+                break;
+            };
+            let Some(params) = rest.get(..len) else {
+                break;
+            };
+            commands.push(ReplayCommand {
+                frame,
+                player_id,
+                code,
+                params: params.to_vec(),
+            });
+            cursor += 2 + len;
+        }
+    }
+    commands
+}
+
+// The winner is the last team standing once leave-game commands remove the
+// other teams' players. Without leaves (e.g. a desync) it stays unknown.
+fn winner_from_leaves(players: &[ReplayPlayer], commands: &[ReplayCommand]) -> Option<u8> {
+    let mut remaining: Vec<&ReplayPlayer> = players
+        .iter()
+        .filter(|p| p.kind == PlayerKind::Human || p.kind == PlayerKind::Computer)
+        .collect();
+    for cmd in commands.iter().filter(|c| c.code == CMD_LEAVE_GAME) {
+        remaining.retain(|p| p.player_id != cmd.player_id);
+        let first_team = remaining.first()?.team;
+        if remaining.iter().all(|p| p.team == first_team) {
+            return Some(first_team);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn section(payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn zlib_section(payload: &[u8]) -> Vec<u8> {
+        use flate2::write::ZlibEncoder;
+        use std::io::Write;
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(payload).expect("compress");
+        section(&encoder.finish().expect("finish"))
+    }
+
+    fn player_slot(header: &mut [u8], slot: usize, id: u8, race: u8, name: &str) {
+        let start = PLAYERS_OFFSET + slot * PLAYER_SLOT_LEN;
+        header[start] = slot as u8;
+        header[start + 4] = id;
+        header[start + 8] = 2;
+        header[start + 9] = race;
+        header[start + 11..start + 11 + name.len()].copy_from_slice(name.as_bytes());
+    }
+
+    fn sample_header() -> Vec<u8> {
+        let mut header = vec![0u8; HEADER_LEN];
+        header[0] = 1;
+        header[0x01..0x05].copy_from_slice(&(24 * 60 * 10u32).to_le_bytes());
+        header[0x3C] = 2;
+        header[0x61..0x61 + 8].copy_from_slice(b"Polypoid");
+        player_slot(&mut header, 0, 0, 2, "Alice");
+        player_slot(&mut header, 1, 1, 0, "Bob");
+        player_slot(&mut header, 8, 8, 1, "Watcher");
+        header
+    }
+
+    // A 10-minute 1v1 on Polypoid: Alice (P) beats Bob (Z), who leaves.
+    pub(crate) fn sample_replay() -> Vec<u8> {
+        let header = sample_header();
+
+        // Frame 100: Bob trains (0x1F) then leaves (0x57).
+        let mut commands = Vec::new();
+        commands.extend_from_slice(&100u32.to_le_bytes());
+        commands.push(7);
+        commands.extend_from_slice(&[1, 0x1F, 0x25, 0x00, 1, 0x57, 0x01]);
+
+        let mut data = section(b"seRS");
+        data.extend(zlib_section(&header));
+        data.extend(section(&(commands.len() as u32).to_le_bytes()));
+        data.extend(section(&commands));
+        data
+    }
+
+    #[test]
+    fn parses_modern_header_commands_and_winner() {
+        let replay = parse(&sample_replay()).expect("parse replay");
+        let header = &replay.header;
+        assert_eq!(header.format, ReplayFormat::Modern);
+        assert_eq!(header.map_name, "Polypoid");
+        assert_eq!(header.frames, 14_400);
+        assert_eq!(header.players.len(), 2);
+        assert_eq!(header.players[0].name, "Alice");
+        assert_eq!(header.players[0].race, ReplayRace::Protoss);
+        assert_eq!(header.players[1].race.name(), Some("Zerg"));
+        assert_eq!((header.players[0].team, header.players[1].team), (1, 2));
+
+        assert_eq!(replay.commands.len(), 2);
+        assert_eq!(replay.commands[0].params, vec![0x25, 0x00]);
+        assert_eq!(replay.winner_team, Some(1));
+    }
+
+    #[test]
+    fn eapm_skips_repeats_and_replaced_selections() {
+        let mut replay = parse(&sample_replay()).expect("parse replay");
+        let cmd = |frame: u32, code: u8, params: &[u8]| ReplayCommand {
+            frame,
+            player_id: 0,
+            code,
+            params: params.to_vec(),
+        };
+        // One minute of play: a selection replaced 2 frames later, then a
+        // train command spammed three times.
+        replay.commands = vec![
+            cmd(10, 0x09, &[1, 1, 0]),
+            cmd(12, 0x09, &[1, 2, 0]),
+            cmd(100, 0x1F, &[0x25, 0x00]),
+            cmd(103, 0x1F, &[0x25, 0x00]),
+            cmd(106, 0x1F, &[0x25, 0x00]),
+            cmd(1429, 0x1F, &[0x25, 0x00]),
+        ];
+        assert_eq!(replay.apm(0), Some(6));
+        assert_eq!(replay.eapm(0), Some(3));
+    }
+
+    #[test]
+    fn parses_zlib_sections_in_pre_121_replays() {
+        let mut data = section(b"reRS");
+        data.extend(zlib_section(&sample_header()));
+        let replay = parse(&data).expect("parse replay");
+        assert_eq!(replay.header.format, ReplayFormat::Legacy);
+        assert_eq!(replay.header.players[1].name, "Bob");
+    }
+
+    #[test]
+    fn parses_modern_replays_with_a_field_before_the_header() {
+        // Newer clients write an extra 4-byte field after the identifier.
+        let mut data = section(b"seRS");
+        data.extend_from_slice(&[0x00, 0x04, 0x00, 0x00]);
+        data.extend(zlib_section(&sample_header()));
+        let replay = parse(&data).expect("parse replay");
+        assert_eq!(replay.header.format, ReplayFormat::Modern);
+        assert_eq!(replay.header.map_name, "Polypoid");
+        assert_eq!(replay.header.players[0].name, "Alice");
+    }
+
+    #[test]
+    fn rejects_non_replay_data() {
+        assert!(matches!(
+            parse(b"definitely not a replay file"),
+            Err(ReplayParseError::NotAReplay)
+        ));
+    }
+}
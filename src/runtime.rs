@@ -1,26 +1,38 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
-use crate::app::{App, View};
+use crate::app::{App, LibraryAction, ReplayCancel, View};
+use crate::build_order::BuildOrderStore;
 use crate::cache::CacheReader;
 use crate::config::Config;
+use crate::desktop::{copy_to_clipboard, reveal_in_file_manager};
 use crate::detect::DetectionService;
-use crate::error::AppError;
+use crate::error::{render_error_message, AppError};
 use crate::history::{FileHistorySource, HistoryService};
 use crate::interaction::Intent;
 use crate::overlay::OverlayService;
 use crate::profile::ProfileService;
 use crate::profile_history::ProfileHistoryService;
 use crate::replay::ReplayService;
-use crate::replay_download::{ReplayDownloadRequest, ReplayStorage};
+use crate::replay_download::{
+    clean_temp_files, DownloadJournal, ReplayDownloadRequest, ReplayStorage, RequestOrigin,
+};
+use crate::replay_index::{spawn_index_refresh, ReplayIndexStore};
+use crate::replay_io::read_replay_summary;
+use crate::scout::scout_matchup;
+use crate::session::{now_secs, SessionStart, SessionStore};
 use crate::tui::{restore_terminal, setup_terminal};
 use crate::ui::render;
-use which::which;
+use crate::watch_list::WatchListStore;
 
 pub struct AppRuntime {
     cfg: Config,
@@ -31,12 +43,17 @@ pub struct AppRuntime {
     detection: DetectionEngine,
     history: Option<HistoryService<FileHistorySource>>,
     profile_history: ProfileHistoryService,
+    sessions: SessionStore,
+    builds: BuildOrderStore,
+    library: ReplayIndexStore,
+    watch_store: WatchListStore,
 }
 
 impl AppRuntime {
     pub fn new(cfg: Config) -> Result<Self, AppError> {
         let terminal = setup_terminal().map_err(AppError::TerminalSetup)?;
-        let app = App::new(cfg.debug_window_secs);
+        let mut app = App::new(cfg.debug_window_secs);
+        app.self_profile.stats_window = cfg.stats_window;
 
         let detection = DetectionEngine::new(cfg.cache_dir.clone(), cfg.refresh_interval);
 
@@ -46,7 +63,19 @@ impl AppRuntime {
                 tracing::error!(error = %err, "failed to load profile history; starting empty");
                 ProfileHistoryService::empty(cfg.profile_history_path.clone())
             }
+        }
+        .with_retention(cfg.history_retention, cfg.history_archive_dir.clone());
+
+        let sessions = match SessionStore::new(cfg.session_history_path.clone()) {
+            Ok(store) => store,
+            Err(err) => {
+                tracing::error!(error = %err, "failed to load sessions; starting empty");
+                SessionStore::empty(cfg.session_history_path.clone())
+            }
         };
+        let builds = BuildOrderStore::new(cfg.build_orders_path.clone());
+        let library = ReplayIndexStore::new(cfg.replay_index_path.clone());
+        let watch_store = WatchListStore::new(cfg.watch_list_path.clone());
 
         let mut runtime = Self {
             tick_rate: cfg.tick_rate,
@@ -57,6 +86,10 @@ impl AppRuntime {
             detection,
             history: None,
             profile_history,
+            sessions,
+            builds,
+            library,
+            watch_store,
         };
         runtime.bootstrap()?;
         Ok(runtime)
@@ -97,6 +130,18 @@ impl AppRuntime {
             tracing::error!(error = %err, "failed to ensure replay directories");
             self.app.status.last_profile_text = some_text("Replay dir error", &err);
         }
+        // Downloads interrupted by the last exit go back in the queue; with none
+        // to resume, partial files they left are no longer needed.
+        let resumed = DownloadJournal::load_all(&storage);
+        if resumed.is_empty() {
+            let removed = clean_temp_files(&storage.bwtools_root());
+            if removed > 0 {
+                tracing::info!(removed, "removed partial replay downloads");
+            }
+        } else {
+            tracing::info!(jobs = resumed.len(), "resuming replay downloads");
+        }
+        self.app.replay.queue.extend(resumed);
         self.app.replay_watch.storage = Some(storage);
 
         let history = HistoryService::new(FileHistorySource::new(
@@ -112,10 +157,32 @@ impl AppRuntime {
         }
         self.history = Some(history);
 
+        match self.builds.load() {
+            Ok(log) => self.app.builds = log,
+            Err(err) => tracing::error!(error = %err, "failed to load build orders"),
+        }
+        match self.library.load() {
+            Ok(index) => {
+                self.app.library.index = index;
+                self.app.library.generation += 1;
+            }
+            Err(err) => tracing::error!(error = %err, "failed to load replay index"),
+        }
+        match self.watch_store.load() {
+            Ok(list) => self.app.watch_list = list,
+            Err(err) => tracing::error!(error = %err, "failed to load watch list"),
+        }
+
         self.app.known_players = load_known_players_from_cfg(&self.cfg);
 
-        self.app.detection.screp_available =
-            which(&self.cfg.screp_cmd).is_ok() && Path::new(&self.cfg.last_replay_path).exists();
+        let now = now_secs();
+        self.app.session.current = self.sessions.resume(now, self.cfg.session_idle_gap);
+        self.app.session.last_finished = self.sessions.last_finished().cloned();
+        if self.cfg.session_start == SessionStart::AppStart {
+            self.app.session.start(now, None);
+        }
+
+        self.app.detection.replay_watch_enabled = Path::new(&self.cfg.last_replay_path).exists();
         if let Ok(meta) = std::fs::metadata(&self.cfg.last_replay_path) {
             self.app.replay_watch.last_mtime = meta.modified().ok();
             self.app.replay_watch.last_processed_mtime = self.app.replay_watch.last_mtime;
@@ -129,26 +196,18 @@ impl AppRuntime {
             return Ok(());
         }
         self.app.replay.should_start = false;
-        if self.app.replay.in_progress {
-            self.app.replay.last_error = Some("Replay download already running".to_string());
-            return Ok(());
-        }
         let toon = self.app.replay.toon_input.trim();
         if toon.is_empty() {
             self.app.replay.last_error = Some("Enter a profile name first".to_string());
             return Ok(());
         }
-        let port = self
-            .app
-            .detection
-            .port
-            .or(self.app.detection.last_port_used)
-            .unwrap_or_default();
-        if port == 0 {
-            self.app.replay.last_error = Some("No API port detected".to_string());
-            return Ok(());
-        }
-        let base_url = format!("http://127.0.0.1:{port}");
+        let filter = match self.app.replay.filters.parse() {
+            Ok(filter) => filter,
+            Err(err) => {
+                self.app.replay.last_error = Some(render_error_message(&err));
+                return Ok(());
+            }
+        };
         let request = ReplayDownloadRequest {
             toon: toon.to_string(),
             gateway: self.app.replay.input_gateway,
@@ -161,27 +220,305 @@ impl AppRuntime {
                 "" => None,
                 other => Some(other.to_string()),
             },
+            filter,
+            origin: RequestOrigin::Manual,
         };
+        let storage = ReplayStorage::new(self.cfg.replay_library_root.clone());
+        match DownloadJournal::create(&storage, request) {
+            Ok(journal) => {
+                self.app.replay.last_error = None;
+                self.app.replay.queue.push_back(journal);
+            }
+            Err(err) => {
+                tracing::error!(error = %err, "failed to queue replay download");
+                self.app.replay.last_error = Some(render_error_message(&err));
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_pending_replay_cancel(&mut self) {
+        let Some(scope) = self.app.replay.pending_cancel.take() else {
+            return;
+        };
+        if scope == ReplayCancel::Queue {
+            for journal in self.app.replay.queue.drain(..) {
+                journal.remove();
+            }
+        }
+        if let Some(cancel) = self.app.replay.cancel.as_ref() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Starts the next queued request once the previous job has finished.
+    fn start_next_replay_download(&mut self) {
+        if self.app.replay.in_progress || self.app.replay.queue.is_empty() {
+            return;
+        }
+        let port = self
+            .app
+            .detection
+            .port
+            .or(self.app.detection.last_port_used)
+            .unwrap_or_default();
+        if port == 0 {
+            self.app.replay.last_error =
+                Some("Waiting for the API port to start queued downloads".to_string());
+            return;
+        }
+        let Some(journal) = self.app.replay.queue.pop_front() else {
+            return;
+        };
+        let base_url = format!("http://127.0.0.1:{port}");
 
         if let Some(handle) = self.app.replay.job_handle.take() {
             let _ = handle.join();
         }
         self.app.replay.last_error = None;
-        self.app.replay.last_summary = None;
-        self.app.replay.last_request = Some(request.clone());
+        self.app.replay.progress = None;
+        self.app.replay.activity.clear();
+        self.app.replay.last_request = Some(journal.request.clone());
 
+        let cancel = Arc::new(AtomicBool::new(false));
         let cfg = self.cfg.clone();
-        let (handle, rx) = crate::replay_download::spawn_download_job(base_url, cfg, request);
+        let (handle, rx) =
+            crate::replay_download::spawn_download_job(base_url, cfg, journal, cancel.clone());
+        self.app.replay.cancel = Some(cancel);
         self.app.replay.job_rx = Some(rx);
         self.app.replay.job_handle = Some(handle);
         self.app.replay.in_progress = true;
-        Ok(())
+    }
+
+    fn handle_pending_note_save(&mut self) {
+        if !self.app.notes.should_save {
+            return;
+        }
+        self.app.notes.should_save = false;
+        if let Some(service) = self.history.as_ref()
+            && let Err(err) = service.save(&self.app.opponent.history)
+        {
+            tracing::error!(error = %err, "failed to persist opponent notes");
+            set_status_error(&mut self.app, "History save error", &err);
+        }
+    }
+
+    fn tick_session(&mut self) {
+        let now = now_secs();
+        self.app
+            .session
+            .observe_rating(self.app.self_profile.rating);
+        if let Some(finished) = self.app.session.expire_idle(now, self.cfg.session_idle_gap)
+            && let Err(err) = self.sessions.record(&finished)
+        {
+            tracing::error!(error = %err, "failed to persist finished session");
+        }
+        if !self.app.session.dirty {
+            return;
+        }
+        self.app.session.dirty = false;
+        if let Some(current) = self.app.session.current.as_ref()
+            && let Err(err) = self.sessions.record(current)
+        {
+            tracing::error!(error = %err, "failed to persist session");
+            set_status_error(&mut self.app, "Session save error", &err);
+        }
+    }
+
+    fn tick_build_orders(&mut self) {
+        if !self.app.builds.dirty {
+            return;
+        }
+        self.app.builds.dirty = false;
+        if let Err(err) = self.builds.save(&self.app.builds) {
+            tracing::error!(error = %err, "failed to persist build orders");
+            set_status_error(&mut self.app, "Build order save error", &err);
+        }
+    }
+
+    // Queues a download of the current opponent's recent replays, ahead of
+    // other queued work so they are ready once the game ends.
+    fn tick_scout(&mut self) {
+        if !self.cfg.scout_opponents || self.app.detection.port.is_none() {
+            return;
+        }
+        let Some((toon, gateway)) = self.app.opponent.last_identity.clone() else {
+            return;
+        };
+        let matchup = scout_matchup(
+            self.app.opponent.race.as_deref(),
+            self.app.self_profile.main_race.as_deref(),
+        );
+        let Some(request) = self.app.scout.next_request(
+            &toon,
+            gateway,
+            matchup,
+            now_secs(),
+            self.cfg.scout_cooldown,
+            self.cfg.scout_replays,
+        ) else {
+            return;
+        };
+        // A scout still waiting for an earlier opponent is no longer wanted.
+        self.app.replay.queue.retain(|journal| {
+            let stale = journal.request.origin == RequestOrigin::Scout;
+            if stale {
+                journal.remove();
+            }
+            !stale
+        });
+        let storage = ReplayStorage::new(self.cfg.replay_library_root.clone());
+        match DownloadJournal::create(&storage, request) {
+            Ok(journal) => {
+                tracing::info!(opponent = %toon, gateway, "queued opponent scouting");
+                self.app.replay.queue.push_front(journal);
+            }
+            Err(err) => tracing::error!(error = %err, "failed to queue opponent scouting"),
+        }
+    }
+
+    // Queues one due watched player at a time while the API port is live, and
+    // persists the list after a toggle or a finished check.
+    fn tick_watch_list(&mut self) {
+        if self.app.watch_list.dirty {
+            self.app.watch_list.dirty = false;
+            if let Err(err) = self.watch_store.save(&self.app.watch_list) {
+                tracing::error!(error = %err, "failed to persist watch list");
+                set_status_error(&mut self.app, "Watch list save error", &err);
+            }
+        }
+        if self.app.detection.port.is_none() {
+            return;
+        }
+        let replay = &self.app.replay;
+        let watch_pending = replay
+            .queue
+            .iter()
+            .any(|journal| journal.request.origin == RequestOrigin::Watch)
+            || (replay.in_progress
+                && replay
+                    .last_request
+                    .as_ref()
+                    .is_some_and(|r| r.origin == RequestOrigin::Watch));
+        if watch_pending {
+            return;
+        }
+        let now = now_secs();
+        let Some(entry) = self.app.watch_list.next_due(now, self.cfg.watch_interval) else {
+            return;
+        };
+        entry.last_checked = Some(now);
+        let request = entry.request();
+        self.app.watch_list.dirty = true;
+        let storage = ReplayStorage::new(self.cfg.replay_library_root.clone());
+        match DownloadJournal::create(&storage, request) {
+            Ok(journal) => self.app.replay.queue.push_back(journal),
+            Err(err) => tracing::error!(error = %err, "failed to queue watch list check"),
+        }
+    }
+
+    // Rescans the replay library in the background every
+    // `library_scan_interval`, swapping in the refreshed index when done.
+    fn tick_library(&mut self) {
+        let library = &mut self.app.library;
+        if let Some(rx) = library.job_rx.as_ref() {
+            let finished = match rx.try_recv() {
+                Ok((mut index, scan)) => {
+                    for build in std::mem::take(&mut index.new_builds) {
+                        self.app.builds.record(build);
+                    }
+                    library.index = index;
+                    library.generation += 1;
+                    library.last_scan = Some(scan);
+                    true
+                }
+                Err(TryRecvError::Empty) => false,
+                Err(TryRecvError::Disconnected) => true,
+            };
+            if !finished {
+                return;
+            }
+            library.job_rx = None;
+            if let Some(handle) = library.job_handle.take() {
+                let _ = handle.join();
+            }
+            if library.index.dirty {
+                library.index.dirty = false;
+                if let Err(err) = self.library.save(&library.index) {
+                    tracing::error!(error = %err, "failed to persist replay index");
+                    set_status_error(&mut self.app, "Replay index save error", &err);
+                }
+            }
+            return;
+        }
+        if library
+            .last_scan_at
+            .is_some_and(|at| at.elapsed() < self.cfg.library_scan_interval)
+        {
+            return;
+        }
+        library.last_scan_at = Some(Instant::now());
+        let (handle, rx) = spawn_index_refresh(self.cfg.clone(), library.index.clone());
+        library.job_handle = Some(handle);
+        library.job_rx = Some(rx);
+    }
+
+    fn tick_library_view(&mut self) {
+        let view = &mut self.app.library_view;
+        if let Some(action) = view.pending_action.take() {
+            let result = match &action {
+                LibraryAction::CopyPath(path) => copy_to_clipboard(path),
+                LibraryAction::Reveal(path) => reveal_in_file_manager(Path::new(path)),
+            };
+            view.message = Some(match (result, action) {
+                (Ok(()), LibraryAction::CopyPath(_)) => "Path copied".to_string(),
+                (Ok(()), LibraryAction::Reveal(_)) => "Opened file manager".to_string(),
+                (Err(err), _) => format!("Error: {}", render_error_message(&err)),
+            });
+        }
+        if let Some(rx) = view.detail_rx.as_ref() {
+            match rx.try_recv() {
+                Ok((path, detail)) => {
+                    if view.detail_path.as_deref() == Some(path.as_str()) {
+                        view.detail = detail;
+                    }
+                    view.detail_rx = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => view.detail_rx = None,
+            }
+        }
+        if self.app.view != View::Library || view.selected_path == view.detail_path {
+            return;
+        }
+        view.detail_path = view.selected_path.clone();
+        view.detail = None;
+        // A parse still running for an earlier selection is left to finish
+        // on its own; its result no longer matches `detail_path`.
+        view.detail_rx = view.detail_path.clone().map(|path| {
+            let (tx, rx) = mpsc::channel();
+            let cfg = self.cfg.clone();
+            thread::spawn(move || {
+                let detail = read_replay_summary(&cfg, Path::new(&path))
+                    .map_err(|err| tracing::debug!(error = %err, path, "replay detail failed"))
+                    .ok();
+                let _ = tx.send((path, detail));
+            });
+            rx
+        });
     }
 
     fn tick_services(&mut self) {
+        self.handle_pending_note_save();
         self.tick_detection();
+        self.tick_scout();
         self.tick_profile_services();
         self.tick_replay_services();
+        self.tick_watch_list();
+        self.tick_session();
+        self.tick_build_orders();
+        self.tick_library();
+        self.tick_library_view();
         self.tick_overlay_services();
         self.last_tick = Instant::now();
     }
@@ -227,7 +564,9 @@ impl AppRuntime {
             tracing::error!(error = %err, "replay download start failed");
             set_status_error(&mut self.app, "Replay error", &err);
         }
+        self.handle_pending_replay_cancel();
         self.app.poll_replay_job();
+        self.start_next_replay_download();
 
         if let Err(err) = ReplayService::tick(
             &mut self.app,
@@ -245,6 +584,10 @@ impl AppRuntime {
             tracing::error!(error = %err, "failed to update opponent overlay");
             set_status_error(&mut self.app, "Overlay error", &err);
         }
+        if let Err(err) = OverlayService::write_session(&self.cfg, &mut self.app) {
+            tracing::error!(error = %err, "failed to update session overlay");
+            set_status_error(&mut self.app, "Overlay error", &err);
+        }
     }
 }
 
@@ -304,11 +647,14 @@ fn handle_key_event(app: &mut App, key: KeyEvent) {
             KeyCode::Char('d') => Intent::ToggleDebug.apply(app),
             KeyCode::Char('m') => Intent::ShowMain.apply(app),
             KeyCode::Char('r') => Intent::ShowReplays.apply(app),
+            KeyCode::Char('o') => Intent::ShowOpponents.apply(app),
+            KeyCode::Char('l') => Intent::ShowLibrary.apply(app),
             KeyCode::Char('q') => Intent::Quit.apply(app),
             _ => {}
         }
     } else {
         match key.code {
+            KeyCode::Esc if app.notes.active => app.on_key(KeyCode::Esc),
             KeyCode::Esc => Intent::Quit.apply(app),
             other => app.on_key(other),
         }
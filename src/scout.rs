@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::replay_download::{DownloadFilter, ReplayDownloadRequest, RequestOrigin};
+use crate::replay_layout::race_letter;
+
+// Decides when a detected opponent's recent replays are fetched. Only the
+// request is built here; the runtime queues it so detection never waits on it.
+#[derive(Debug, Default)]
+pub struct ScoutState {
+    // Opponent the last decision was made for, so each game is handled once.
+    last_target: Option<(String, u16)>,
+    // When each opponent (lowercased toon, gateway) was last scouted.
+    scouted: HashMap<(String, u16), u64>,
+}
+
+impl ScoutState {
+    // A download of `limit` replays for a newly detected opponent, unless they
+    // were scouted within `cooldown`.
+    pub fn next_request(
+        &mut self,
+        toon: &str,
+        gateway: u16,
+        matchup: Option<String>,
+        now: u64,
+        cooldown: Duration,
+        limit: usize,
+    ) -> Option<ReplayDownloadRequest> {
+        let target = (toon.to_ascii_lowercase(), gateway);
+        if self.last_target.as_ref() == Some(&target) {
+            return None;
+        }
+        self.last_target = Some(target.clone());
+        if let Some(&at) = self.scouted.get(&target)
+            && now.saturating_sub(at) < cooldown.as_secs()
+        {
+            tracing::debug!(opponent = %toon, gateway, "opponent scouted recently; skipping");
+            return None;
+        }
+        self.scouted.insert(target, now);
+        Some(ReplayDownloadRequest {
+            toon: toon.to_string(),
+            gateway,
+            matchup,
+            limit,
+            alias: None,
+            filter: DownloadFilter::default(),
+            origin: RequestOrigin::Scout,
+        })
+    }
+}
+
+// The opponent's side of the game as a matchup filter, e.g. "ZvT". Random or
+// unknown races scout every matchup.
+pub fn scout_matchup(opponent_race: Option<&str>, self_race: Option<&str>) -> Option<String> {
+    let letter = |race: Option<&str>| {
+        race.filter(|r| !r.trim().is_empty() && !r.eq_ignore_ascii_case("random"))
+            .map(race_letter)
+    };
+    Some(format!("{}v{}", letter(opponent_race)?, letter(self_race)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scouts_each_opponent_once_per_cooldown() {
+        let mut scout = ScoutState::default();
+        let cooldown = Duration::from_secs(3600);
+        let request = scout
+            .next_request("Bisu", 30, Some("PvT".to_string()), 1000, cooldown, 5)
+            .expect("first sighting is scouted");
+        assert_eq!((request.origin, request.limit), (RequestOrigin::Scout, 5));
+        // Same game seen again on the next tick.
+        assert!(scout
+            .next_request("Bisu", 30, None, 1001, cooldown, 5)
+            .is_none());
+
+        assert!(scout
+            .next_request("Jaedong", 30, None, 1500, cooldown, 5)
+            .is_some());
+        assert!(scout
+            .next_request("bisu", 30, None, 2000, cooldown, 5)
+            .is_none());
+        assert!(scout
+            .next_request("Jaedong", 30, None, 2100, cooldown, 5)
+            .is_none());
+        assert!(scout
+            .next_request("Bisu", 30, None, 4600, cooldown, 5)
+            .is_some());
+    }
+
+    #[test]
+    fn matchup_needs_both_races() {
+        assert_eq!(
+            scout_matchup(Some("Zerg"), Some("Terran")).as_deref(),
+            Some("ZvT")
+        );
+        assert_eq!(scout_matchup(Some("Random"), Some("Terran")), None);
+        assert_eq!(scout_matchup(Some("Protoss"), None), None);
+    }
+}
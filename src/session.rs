@@ -0,0 +1,274 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::profile_history::MatchOutcome;
+
+const MAX_STORED_SESSIONS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStart {
+    AppStart,
+    #[default]
+    FirstGame,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub started_at: u64,
+    pub last_activity: u64,
+    pub wins: u32,
+    pub losses: u32,
+    pub dodges: u32,
+    pub rating_start: Option<u32>,
+    pub rating_latest: Option<u32>,
+    #[serde(default)]
+    pub ended: bool,
+}
+
+impl Session {
+    fn new(now: u64, rating: Option<u32>) -> Self {
+        Self {
+            started_at: now,
+            last_activity: now,
+            wins: 0,
+            losses: 0,
+            dodges: 0,
+            rating_start: rating,
+            rating_latest: rating,
+            ended: false,
+        }
+    }
+
+    pub fn duration_secs(&self, now: u64) -> u64 {
+        let end = if self.ended {
+            self.last_activity
+        } else {
+            now.max(self.last_activity)
+        };
+        end.saturating_sub(self.started_at)
+    }
+
+    pub fn rating_delta(&self) -> Option<i64> {
+        match (self.rating_start, self.rating_latest) {
+            (Some(start), Some(latest)) => Some(latest as i64 - start as i64),
+            _ => None,
+        }
+    }
+
+    pub fn summary(&self, now: u64) -> String {
+        let mut out = format!("{}-{}", self.wins, self.losses);
+        if let Some(delta) = self.rating_delta() {
+            out.push_str(&format!(", {delta:+} MMR"));
+        }
+        out.push_str(&format!(", {}", format_duration(self.duration_secs(now))));
+        out
+    }
+}
+
+pub fn format_duration(secs: u64) -> String {
+    let hours = secs / 3600;
+    let mins = (secs % 3600) / 60;
+    if hours > 0 {
+        format!("{hours}h{mins:02}m")
+    } else {
+        format!("{mins}m")
+    }
+}
+
+// Live session state kept on `App`. Detection and replay processing report
+// activity here; the runtime expires idle sessions and persists changes.
+#[derive(Debug, Default)]
+pub struct SessionTracker {
+    pub current: Option<Session>,
+    pub last_finished: Option<Session>,
+    pub dirty: bool,
+}
+
+impl SessionTracker {
+    pub fn start(&mut self, now: u64, rating: Option<u32>) {
+        if self.current.is_none() {
+            self.current = Some(Session::new(now, rating));
+            self.dirty = true;
+        }
+    }
+
+    pub fn touch(&mut self, now: u64, rating: Option<u32>) {
+        self.start(now, rating);
+        if let Some(session) = self.current.as_mut()
+            && now > session.last_activity
+        {
+            session.last_activity = now;
+            self.dirty = true;
+        }
+    }
+
+    pub fn record_game(&mut self, now: u64, outcome: MatchOutcome, rating_before: Option<u32>) {
+        self.touch(now, rating_before);
+        let Some(session) = self.current.as_mut() else {
+            return;
+        };
+        if session.rating_start.is_none() {
+            session.rating_start = rating_before;
+        }
+        match outcome {
+            MatchOutcome::Win => session.wins += 1,
+            MatchOutcome::Loss => session.losses += 1,
+            MatchOutcome::SelfDodged | MatchOutcome::OpponentDodged => session.dodges += 1,
+        }
+        self.dirty = true;
+    }
+
+    pub fn observe_rating(&mut self, rating: Option<u32>) {
+        let Some(session) = self.current.as_mut() else {
+            return;
+        };
+        if rating.is_some() && session.rating_latest != rating {
+            if session.rating_start.is_none() {
+                session.rating_start = rating;
+            }
+            session.rating_latest = rating;
+            self.dirty = true;
+        }
+    }
+
+    // Ends the session once nothing has happened for longer than `idle_gap`.
+    pub fn expire_idle(&mut self, now: u64, idle_gap: Duration) -> Option<Session> {
+        let idle = self
+            .current
+            .as_ref()
+            .is_some_and(|s| now.saturating_sub(s.last_activity) > idle_gap.as_secs());
+        if !idle {
+            return None;
+        }
+        let mut finished = self.current.take()?;
+        finished.ended = true;
+        self.last_finished = Some(finished.clone());
+        self.dirty = true;
+        Some(finished)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionData {
+    sessions: Vec<Session>,
+}
+
+pub struct SessionStore {
+    path: PathBuf,
+    data: SessionData,
+}
+
+impl SessionStore {
+    pub fn new(path: PathBuf) -> Result<Self> {
+        let data = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<SessionData>(&bytes)
+                .with_context(|| format!("deserialize sessions {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => SessionData::default(),
+            Err(err) => {
+                return Err(anyhow!(err).context(format!("read sessions {}", path.display())));
+            }
+        };
+        Ok(Self { path, data })
+    }
+
+    pub fn empty(path: PathBuf) -> Self {
+        Self {
+            path,
+            data: SessionData::default(),
+        }
+    }
+
+    pub fn last_finished(&self) -> Option<&Session> {
+        self.data.sessions.iter().rev().find(|s| s.ended)
+    }
+
+    // An unfinished session from a previous run is resumed when it was active
+    // within the idle gap; otherwise it is closed at its last activity.
+    pub fn resume(&mut self, now: u64, idle_gap: Duration) -> Option<Session> {
+        let last = self.data.sessions.last_mut()?;
+        if last.ended {
+            return None;
+        }
+        if now.saturating_sub(last.last_activity) <= idle_gap.as_secs() {
+            return Some(last.clone());
+        }
+        last.ended = true;
+        None
+    }
+
+    pub fn record(&mut self, session: &Session) -> Result<()> {
+        match self
+            .data
+            .sessions
+            .iter_mut()
+            .find(|s| s.started_at == session.started_at)
+        {
+            Some(existing) => *existing = session.clone(),
+            None => self.data.sessions.push(session.clone()),
+        }
+        self.data.sessions.sort_by_key(|s| s.started_at);
+        let overflow = self.data.sessions.len().saturating_sub(MAX_STORED_SESSIONS);
+        self.data.sessions.drain(..overflow);
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create sessions directory {}", parent.display()))?;
+        }
+        let data = serde_json::to_vec_pretty(&self.data).context("serialize sessions")?;
+        fs::write(&self.path, data)
+            .with_context(|| format!("write sessions {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracker_counts_games_and_expires_after_idle_gap() {
+        let mut tracker = SessionTracker::default();
+        tracker.touch(1_000, Some(2000));
+        tracker.record_game(1_600, MatchOutcome::Win, Some(2000));
+        tracker.observe_rating(Some(2032));
+        tracker.record_game(2_400, MatchOutcome::Loss, Some(2032));
+        tracker.record_game(2_500, MatchOutcome::OpponentDodged, Some(2032));
+        tracker.observe_rating(Some(2018));
+
+        let session = tracker.current.clone().expect("session active");
+        assert_eq!(session.summary(7_120), "1-1, +18 MMR, 1h42m");
+        assert_eq!(session.dodges, 1);
+
+        let gap = Duration::from_secs(30 * 60);
+        assert!(tracker.expire_idle(2_500 + 60, gap).is_none());
+        let finished = tracker.expire_idle(2_500 + 31 * 60, gap).expect("expired");
+        assert!(finished.ended);
+        assert_eq!(finished.duration_secs(99_999), 1_500);
+        assert!(tracker.current.is_none());
+    }
+
+    #[test]
+    fn store_resumes_recent_unfinished_session() {
+        let mut store = SessionStore::empty(PathBuf::from("unused.json"));
+        store.data.sessions.push(Session::new(100, None));
+        let gap = Duration::from_secs(600);
+        assert!(store.resume(500, gap).is_some());
+        assert!(store.resume(10_000, gap).is_none());
+        assert_eq!(store.last_finished().map(|s| s.started_at), Some(100));
+    }
+}
@@ -0,0 +1,44 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+// A file written next to its destination under a temporary name. The
+// destination is only replaced by `commit`, so several files can be written
+// first and swapped in together; dropping it uncommitted removes the temp file.
+pub struct StagedWrite {
+    tmp: Option<PathBuf>,
+    path: PathBuf,
+}
+
+impl StagedWrite {
+    pub fn new(path: &Path, data: &[u8]) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create directory {}", parent.display()))?;
+        }
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".tmp");
+        let tmp = path.with_file_name(name);
+        fs::write(&tmp, data).with_context(|| format!("write {}", tmp.display()))?;
+        Ok(Self {
+            tmp: Some(tmp),
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        let Some(tmp) = self.tmp.take() else {
+            return Ok(());
+        };
+        fs::rename(&tmp, &self.path).with_context(|| format!("replace {}", self.path.display()))
+    }
+}
+
+impl Drop for StagedWrite {
+    fn drop(&mut self) {
+        if let Some(tmp) = self.tmp.take() {
+            let _ = fs::remove_file(tmp);
+        }
+    }
+}
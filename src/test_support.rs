@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::replay_io::ReplayPlayerSummary;
+
+// A path under the system temp dir that no other test run uses, e.g.
+// `bwtools-index-<nanos>`. Nothing is created there.
+pub fn unique_temp_path(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!("bwtools-{name}-{nanos}"))
+}
+
+// A replay player with no APM or build order.
+pub fn replay_player(
+    player_id: u8,
+    name: &str,
+    race: Option<&str>,
+    team: u8,
+) -> ReplayPlayerSummary {
+    ReplayPlayerSummary {
+        player_id,
+        name: name.to_string(),
+        race: race.map(str::to_string),
+        team,
+        ..ReplayPlayerSummary::default()
+    }
+}
@@ -0,0 +1,490 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::history::{
+    merge_imported_record, FileHistorySource, HistoryService, OpponentGame, OpponentHistory,
+    OpponentRecord,
+};
+use crate::profile_history::{MatchOutcome, ProfileHistoryKey, ProfileHistoryService, StoredMatch};
+
+pub const JSON_FILE: &str = "history.json";
+pub const OPPONENTS_CSV: &str = "opponents.csv";
+pub const OPPONENT_GAMES_CSV: &str = "opponent_games.csv";
+pub const PROFILE_MATCHES_CSV: &str = "profile_matches.csv";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryBundle {
+    #[serde(default)]
+    opponents: Vec<OpponentRecord>,
+    #[serde(default)]
+    profiles: Vec<ProfileMatches>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProfileMatches {
+    toon: String,
+    gateway: u16,
+    matches: Vec<StoredMatch>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpponentRow {
+    name: String,
+    gateway: u16,
+    aurora_id: Option<u32>,
+    race: Option<String>,
+    current_rating: Option<u32>,
+    previous_rating: Option<u32>,
+    wins: u32,
+    losses: u32,
+    last_match_ts: Option<u64>,
+    tags: String,
+    notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpponentGameRow {
+    opponent: String,
+    gateway: u16,
+    timestamp: u64,
+    self_race: Option<String>,
+    opponent_race: Option<String>,
+    result: MatchOutcome,
+    duration_secs: Option<u32>,
+    map: Option<String>,
+    rating_before: Option<u32>,
+    rating_after: Option<u32>,
+    replay_path: Option<String>,
+    provisional: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProfileMatchRow {
+    toon: String,
+    gateway: u16,
+    timestamp: u64,
+    opponent: String,
+    opponent_race: Option<String>,
+    main_race: Option<String>,
+    result: MatchOutcome,
+    season: Option<u32>,
+    map: Option<String>,
+    rating_before: Option<u32>,
+    rating_after: Option<u32>,
+    rating_delta: Option<i32>,
+    opponent_rating: Option<u32>,
+    apm: Option<u32>,
+    eapm: Option<u32>,
+    opponent_apm: Option<u32>,
+    opponent_eapm: Option<u32>,
+    replay_path: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub opponents_added: usize,
+    pub opponents_merged: usize,
+    pub matches_added: usize,
+    pub matches_updated: usize,
+    pub conflicts: Vec<String>,
+}
+
+impl std::fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Opponents: {} added, {} merged",
+            self.opponents_added, self.opponents_merged
+        )?;
+        writeln!(
+            f,
+            "Profile matches: {} added, {} updated",
+            self.matches_added, self.matches_updated
+        )?;
+        if self.conflicts.is_empty() {
+            write!(f, "No conflicts")
+        } else {
+            writeln!(f, "{} conflict(s):", self.conflicts.len())?;
+            for conflict in self.conflicts.iter() {
+                writeln!(f, "  {conflict}")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn export(
+    dir: &Path,
+    format: ExportFormat,
+    history: &OpponentHistory,
+    profiles: &ProfileHistoryService,
+) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("create export dir {}", dir.display()))?;
+    let mut opponents: Vec<&OpponentRecord> = history.values().collect();
+    opponents.sort_by_key(|rec| (rec.name.to_ascii_lowercase(), rec.gateway));
+
+    match format {
+        ExportFormat::Json => {
+            let bundle = HistoryBundle {
+                opponents: opponents.into_iter().cloned().collect(),
+                profiles: profiles
+                    .profiles()
+                    .into_iter()
+                    .map(|(key, matches)| ProfileMatches {
+                        toon: key.name().to_string(),
+                        gateway: key.gateway(),
+                        matches: matches.to_vec(),
+                    })
+                    .collect(),
+            };
+            let path = dir.join(JSON_FILE);
+            let data = serde_json::to_vec_pretty(&bundle).context("serialize history export")?;
+            fs::write(&path, data).with_context(|| format!("write {}", path.display()))?;
+        }
+        ExportFormat::Csv => {
+            let mut opponent_rows = Vec::new();
+            let mut game_rows = Vec::new();
+            for rec in opponents {
+                opponent_rows.push(OpponentRow {
+                    name: rec.name.clone(),
+                    gateway: rec.gateway,
+                    aurora_id: rec.aurora_id,
+                    race: rec.race.clone(),
+                    current_rating: rec.current_rating,
+                    previous_rating: rec.previous_rating,
+                    wins: rec.wins,
+                    losses: rec.losses,
+                    last_match_ts: rec.last_match_ts,
+                    tags: rec.tags.join(";"),
+                    notes: rec.notes.clone(),
+                });
+                game_rows.extend(rec.games.iter().map(|g| OpponentGameRow {
+                    opponent: rec.name.clone(),
+                    gateway: rec.gateway,
+                    timestamp: g.timestamp,
+                    self_race: g.self_race.clone(),
+                    opponent_race: g.opponent_race.clone(),
+                    result: g.result,
+                    duration_secs: g.duration_secs,
+                    map: g.map.clone(),
+                    rating_before: g.rating_before,
+                    rating_after: g.rating_after,
+                    replay_path: g.replay_path.clone(),
+                    provisional: g.provisional,
+                }));
+            }
+            let match_rows: Vec<ProfileMatchRow> = profiles
+                .profiles()
+                .into_iter()
+                .flat_map(|(key, matches)| {
+                    matches.iter().map(move |m| ProfileMatchRow {
+                        toon: key.name().to_string(),
+                        gateway: key.gateway(),
+                        timestamp: m.timestamp,
+                        opponent: m.opponent.clone(),
+                        opponent_race: m.opponent_race.clone(),
+                        main_race: m.main_race.clone(),
+                        result: m.result,
+                        season: m.season,
+                        map: m.map.clone(),
+                        rating_before: m.rating_before,
+                        rating_after: m.rating_after,
+                        rating_delta: m.rating_delta,
+                        opponent_rating: m.opponent_rating,
+                        apm: m.apm,
+                        eapm: m.eapm,
+                        opponent_apm: m.opponent_apm,
+                        opponent_eapm: m.opponent_eapm,
+                        replay_path: m.replay_path.clone(),
+                    })
+                })
+                .collect();
+            write_csv(&dir.join(OPPONENTS_CSV), &opponent_rows)?;
+            write_csv(&dir.join(OPPONENT_GAMES_CSV), &game_rows)?;
+            write_csv(&dir.join(PROFILE_MATCHES_CSV), &match_rows)?;
+        }
+    }
+    Ok(())
+}
+
+// Accepts either a JSON export file or a directory holding a JSON or CSV export.
+// Nothing is written; `save_import` persists the merged histories.
+pub fn import(
+    path: &Path,
+    history: &mut OpponentHistory,
+    profiles: &mut ProfileHistoryService,
+) -> Result<ImportReport> {
+    let bundle = if path.is_dir() {
+        let json = path.join(JSON_FILE);
+        if json.is_file() {
+            read_json(&json)?
+        } else if path.join(OPPONENTS_CSV).is_file() || path.join(PROFILE_MATCHES_CSV).is_file() {
+            read_csv_bundle(path)?
+        } else {
+            bail!("no history export found in {}", path.display());
+        }
+    } else {
+        read_json(path)?
+    };
+
+    let mut report = ImportReport::default();
+    for rec in bundle.opponents {
+        let merge = merge_imported_record(history, rec);
+        if merge.added {
+            report.opponents_added += 1;
+        } else if merge.merged {
+            report.opponents_merged += 1;
+        }
+        report.conflicts.extend(merge.conflicts);
+    }
+    for profile in bundle.profiles {
+        let key = ProfileHistoryKey::new(&profile.toon, profile.gateway);
+        let merge = profiles.merge_imported_matches(&key, profile.matches)?;
+        report.matches_added += merge.added;
+        report.matches_updated += merge.updated;
+        report.conflicts.extend(
+            merge
+                .conflicts
+                .into_iter()
+                .map(|c| format!("{}: {c}", key.storage_key())),
+        );
+    }
+    Ok(report)
+}
+
+// Both histories are written out before either replaces its file, so a failed
+// write leaves the import entirely unapplied.
+pub fn save_import(
+    history: &HistoryService<FileHistorySource>,
+    hist: &OpponentHistory,
+    profiles: &ProfileHistoryService,
+) -> Result<()> {
+    let opponents = history.stage(hist)?;
+    let matches = profiles.stage()?;
+    opponents.commit()?;
+    matches.commit()
+}
+
+fn read_json(path: &Path) -> Result<HistoryBundle> {
+    let bytes = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("deserialize {}", path.display()))
+}
+
+fn read_csv_bundle(dir: &Path) -> Result<HistoryBundle> {
+    let mut records: Vec<OpponentRecord> = Vec::new();
+    let mut index: HashMap<(String, u16), usize> = HashMap::new();
+    for row in read_csv::<OpponentRow>(&dir.join(OPPONENTS_CSV))? {
+        index.insert((row.name.to_ascii_lowercase(), row.gateway), records.len());
+        records.push(OpponentRecord {
+            name: row.name,
+            gateway: row.gateway,
+            aurora_id: row.aurora_id,
+            race: row.race,
+            current_rating: row.current_rating,
+            previous_rating: row.previous_rating,
+            wins: row.wins,
+            losses: row.losses,
+            last_match_ts: row.last_match_ts,
+            notes: row.notes.filter(|n| !n.trim().is_empty()),
+            tags: crate::history::parse_tags(&row.tags.replace(';', ",")),
+            games: Vec::new(),
+            legacy_wins: 0,
+            legacy_losses: 0,
+        });
+    }
+    for row in read_csv::<OpponentGameRow>(&dir.join(OPPONENT_GAMES_CSV))? {
+        let Some(&idx) = index.get(&(row.opponent.to_ascii_lowercase(), row.gateway)) else {
+            return Err(anyhow!(
+                "game for unknown opponent {}#{} in {}",
+                row.opponent,
+                row.gateway,
+                OPPONENT_GAMES_CSV
+            ));
+        };
+        records[idx].games.push(OpponentGame {
+            timestamp: row.timestamp,
+            self_race: row.self_race,
+            opponent_race: row.opponent_race,
+            result: row.result,
+            duration_secs: row.duration_secs,
+            map: row.map,
+            rating_before: row.rating_before,
+            rating_after: row.rating_after,
+            replay_path: row.replay_path,
+            provisional: row.provisional,
+        });
+    }
+
+    let mut profiles: Vec<ProfileMatches> = Vec::new();
+    for row in read_csv::<ProfileMatchRow>(&dir.join(PROFILE_MATCHES_CSV))? {
+        let stored = StoredMatch {
+            timestamp: row.timestamp,
+            opponent: row.opponent,
+            opponent_race: row.opponent_race,
+            main_race: row.main_race,
+            result: row.result,
+            season: row.season,
+            map: row.map,
+            rating_before: row.rating_before,
+            rating_after: row.rating_after,
+            rating_delta: row.rating_delta,
+            opponent_rating: row.opponent_rating,
+            apm: row.apm,
+            eapm: row.eapm,
+            opponent_apm: row.opponent_apm,
+            opponent_eapm: row.opponent_eapm,
+            replay_path: row.replay_path,
+        };
+        match profiles
+            .iter_mut()
+            .find(|p| p.gateway == row.gateway && p.toon.eq_ignore_ascii_case(&row.toon))
+        {
+            Some(profile) => profile.matches.push(stored),
+            None => profiles.push(ProfileMatches {
+                toon: row.toon,
+                gateway: row.gateway,
+                matches: vec![stored],
+            }),
+        }
+    }
+
+    Ok(HistoryBundle {
+        opponents: records,
+        profiles,
+    })
+}
+
+fn write_csv<T: Serialize>(path: &Path, rows: &[T]) -> Result<()> {
+    let mut writer =
+        csv::Writer::from_path(path).with_context(|| format!("create {}", path.display()))?;
+    for row in rows {
+        writer
+            .serialize(row)
+            .with_context(|| format!("write {}", path.display()))?;
+    }
+    writer
+        .flush()
+        .with_context(|| format!("flush {}", path.display()))?;
+    Ok(())
+}
+
+fn read_csv<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Vec<T>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let mut reader =
+        csv::Reader::from_path(path).with_context(|| format!("open {}", path.display()))?;
+    let mut rows = Vec::new();
+    for (line, row) in reader.deserialize().enumerate() {
+        rows.push(row.with_context(|| format!("parse {} row {}", path.display(), line + 1))?);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::unique_temp_path;
+
+    fn sample_history() -> OpponentHistory {
+        let mut rec = OpponentRecord::new("Bob", 10);
+        rec.aurora_id = Some(7);
+        rec.race = Some("Zerg".to_string());
+        rec.tags = vec!["rival".to_string()];
+        rec.notes = Some("likes 2 gate".to_string());
+        rec.merge_game(OpponentGame {
+            timestamp: 100,
+            self_race: Some("Protoss".to_string()),
+            opponent_race: Some("Zerg".to_string()),
+            result: MatchOutcome::Win,
+            duration_secs: Some(600),
+            map: Some("Polypoid".to_string()),
+            rating_before: None,
+            rating_after: None,
+            replay_path: None,
+            provisional: false,
+        });
+        let mut hist = OpponentHistory::new();
+        hist.insert("bob".to_string(), rec);
+        hist
+    }
+
+    #[test]
+    fn csv_round_trip_merges_without_duplicates() {
+        let dir = unique_temp_path("export-csv");
+        let mut profiles = ProfileHistoryService::empty(dir.join("profile_history.json"));
+        profiles
+            .import_matches(
+                &ProfileHistoryKey::new("Alice", 10),
+                vec![StoredMatch {
+                    timestamp: 100,
+                    opponent: "Bob".to_string(),
+                    opponent_race: Some("Zerg".to_string()),
+                    result: MatchOutcome::Win,
+                    season: Some(20),
+                    map: Some("Polypoid".to_string()),
+                    ..Default::default()
+                }],
+            )
+            .expect("seed profile");
+        let mut hist = sample_history();
+        export(&dir, ExportFormat::Csv, &hist, &profiles).expect("export csv");
+
+        let report = import(&dir, &mut hist, &mut profiles).expect("import csv");
+        assert_eq!(report.opponents_added, 0);
+        assert_eq!(report.matches_added, 0);
+        assert!(report.conflicts.is_empty(), "{:?}", report.conflicts);
+        let rec = hist.get("bob").expect("record kept");
+        assert_eq!((rec.wins, rec.losses), (1, 0));
+        assert_eq!(rec.games.len(), 1);
+        assert_eq!(rec.tags, vec!["rival".to_string()]);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn json_import_adds_and_reports_conflicts() {
+        let dir = unique_temp_path("export-json");
+        let profiles = ProfileHistoryService::empty(dir.join("profile_history.json"));
+        export(&dir, ExportFormat::Json, &sample_history(), &profiles).expect("export json");
+
+        let mut local = OpponentHistory::new();
+        let mut other = OpponentRecord::new("Bobby", 20);
+        other.aurora_id = Some(7);
+        other.wins = 2;
+        other.losses = 1;
+        other.race = Some("Terran".to_string());
+        local.insert("bobby".to_string(), other);
+        let mut local_profiles = ProfileHistoryService::empty(dir.join("local_profiles.json"));
+
+        let report =
+            import(&dir.join(JSON_FILE), &mut local, &mut local_profiles).expect("import json");
+        assert_eq!(report.opponents_merged, 1);
+        assert_eq!(report.conflicts.len(), 1);
+        let rec = local.get("bobby").expect("merged by aurora id");
+        assert_eq!((rec.wins, rec.losses), (3, 1));
+        assert_eq!(rec.race.as_deref(), Some("Terran"));
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
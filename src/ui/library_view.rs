@@ -0,0 +1,279 @@
+use chrono::{DateTime, Utc};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+
+use crate::app::{App, LibraryFocus};
+use crate::build_order::opening_label;
+use crate::replay_filter::filter_and_sort;
+use crate::replay_index::IndexedReplay;
+
+const DETAIL_OPENING_STEPS: usize = 8;
+
+pub fn render_library(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Min(0)])
+        .split(area);
+    render_filters(frame, rows[0], app);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(rows[1]);
+
+    let filter = app.library_filter();
+    let view = &mut app.library_view;
+    let index = &app.library.index;
+    let key = (
+        filter.clone(),
+        view.sort,
+        view.ascending,
+        app.library.generation,
+    );
+    if view.rows_key.as_ref() != Some(&key) {
+        view.rows = filter_and_sort(index, &filter, view.sort, !view.ascending)
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .collect();
+        view.rows_key = Some(key);
+    }
+    let total = view.rows.len();
+    view.selected = view.selected.min(total.saturating_sub(1));
+    view.selected_path = view.rows.get(view.selected).cloned();
+
+    let list_block = Block::default().borders(Borders::ALL).title(Span::styled(
+        format!(
+            "Replays ({}) • {} {}",
+            total,
+            view.sort.label(),
+            if view.ascending { "↑" } else { "↓" }
+        ),
+        Style::default()
+            .fg(Color::Magenta)
+            .add_modifier(Modifier::BOLD),
+    ));
+    let visible = list_block.inner(columns[0]).height.max(1) as usize;
+    let offset = view.selected.saturating_sub(visible - 1);
+
+    let perspective = filter.player.as_deref().or(filter.perspective.as_deref());
+    let mut lines: Vec<Line> = Vec::new();
+    if total == 0 {
+        lines.push(Line::from(Span::styled(
+            if index.len() == 0 {
+                "No replays indexed yet."
+            } else {
+                "No replays match the filters."
+            },
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for (idx, path) in view.rows.iter().enumerate().skip(offset).take(visible) {
+        let Some(entry) = index.get(path) else {
+            continue;
+        };
+        let style = if idx == view.selected {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(
+            row_text(entry, perspective),
+            style,
+        )));
+    }
+    frame.render_widget(Paragraph::new(lines).block(list_block), columns[0]);
+
+    let selected = view
+        .selected_path
+        .as_deref()
+        .and_then(|path| index.get(path));
+    let detail = Paragraph::new(detail_lines(app, selected))
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                "Details",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    frame.render_widget(detail, columns[1]);
+}
+
+fn render_filters(frame: &mut ratatui::Frame, area: Rect, app: &App) {
+    let view = &app.library_view;
+    let field = |label: &str, value: &str, focus: LibraryFocus| -> Vec<Span<'static>> {
+        let focused = view.focus == focus;
+        vec![
+            Span::raw(if focused { "→ " } else { "  " }),
+            Span::raw(format!("{label}: ")),
+            Span::styled(
+                if value.is_empty() && !focused {
+                    "-".to_string()
+                } else {
+                    value.to_string()
+                },
+                if focused {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Gray)
+                },
+            ),
+            Span::raw("  "),
+        ]
+    };
+
+    let mut first = field("Player", &view.player, LibraryFocus::Player);
+    first.extend(field("Matchup", &view.matchup, LibraryFocus::Matchup));
+    first.extend(field("Map", &view.map, LibraryFocus::Map));
+    let mut second = field("From", &view.from, LibraryFocus::From);
+    second.extend(field("To", &view.to, LibraryFocus::To));
+    second.extend(field("Minutes", &view.duration, LibraryFocus::Duration));
+    second.extend(field("Result", view.result.label(), LibraryFocus::Result));
+
+    let mut lines = vec![
+        Line::from(Span::raw(
+            "Ctrl+M Main  •  Tab Filter  •  ↑/↓ Select  •  F2 Sort  •  F3 Order  •  F5 Copy path  •  F6 Reveal",
+        )),
+        Line::from(first),
+        Line::from(second),
+    ];
+    if let Some(message) = view.message.as_deref() {
+        lines[0].spans.push(Span::styled(
+            format!("  —  {message}"),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    frame.render_widget(
+        Paragraph::new(lines).alignment(Alignment::Left).block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                "Replay Library",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        ),
+        area,
+    );
+}
+
+fn format_date(ts: u64, fmt: &str) -> String {
+    DateTime::<Utc>::from_timestamp(ts as i64, 0)
+        .map(|dt| dt.format(fmt).to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn row_text(entry: &IndexedReplay, perspective: Option<&str>) -> String {
+    let players = entry
+        .players
+        .iter()
+        .map(|p| {
+            format!(
+                "{}({})",
+                p.name,
+                p.race.as_deref().map(crate::race::initial).unwrap_or("?")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" vs ");
+    let result = perspective
+        .and_then(|name| {
+            let player = entry.players.iter().find(|p| {
+                p.name
+                    .to_ascii_lowercase()
+                    .contains(&name.to_ascii_lowercase())
+            })?;
+            Some(if Some(player.team) == entry.winner_team {
+                "W"
+            } else if entry.winner_team.is_some() {
+                "L"
+            } else {
+                "-"
+            })
+        })
+        .unwrap_or("-");
+    format!(
+        "{} {:>2}:{:02} {} {:<32} {}",
+        format_date(entry.played_at, "%Y-%m-%d"),
+        entry.duration_secs / 60,
+        entry.duration_secs % 60,
+        result,
+        players,
+        entry.map.as_deref().unwrap_or("?"),
+    )
+}
+
+fn detail_lines(app: &App, selected: Option<&IndexedReplay>) -> Vec<Line<'static>> {
+    let view = &app.library_view;
+    let (Some(path), Some(entry)) = (view.selected_path.as_deref(), selected) else {
+        return vec![Line::from(Span::styled(
+            "Select a replay.",
+            Style::default().fg(Color::DarkGray),
+        ))];
+    };
+    let label = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            path.to_string(),
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(vec![
+            Span::styled("Played: ", label),
+            Span::raw(format_date(entry.played_at, "%Y-%m-%d %H:%M UTC")),
+        ]),
+        Line::from(vec![
+            Span::styled("Length: ", label),
+            Span::raw(format!(
+                "{}:{:02}",
+                entry.duration_secs / 60,
+                entry.duration_secs % 60
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled("Map: ", label),
+            Span::raw(entry.map.clone().unwrap_or_else(|| "?".to_string())),
+        ]),
+        Line::raw(String::new()),
+    ];
+
+    let detail = view
+        .detail
+        .as_ref()
+        .filter(|_| view.detail_path.as_deref() == Some(path));
+    for player in &entry.players {
+        let won = entry.winner_team == Some(player.team);
+        let mut spans = vec![Span::styled(
+            format!(
+                "{} ({}) team {}",
+                player.name,
+                player.race.as_deref().unwrap_or("?"),
+                player.team
+            ),
+            Style::default().fg(Color::Cyan),
+        )];
+        if won {
+            spans.push(Span::styled("  winner", Style::default().fg(Color::Green)));
+        }
+        let parsed = detail.and_then(|d| d.player(&player.name));
+        if let Some(apm) = parsed.and_then(|p| p.apm) {
+            spans.push(Span::styled(
+                format!("  APM {apm}"),
+                Style::default().fg(Color::Gray),
+            ));
+        }
+        lines.push(Line::from(spans));
+        if let Some(p) = parsed
+            && !p.build_order.is_empty()
+        {
+            lines.push(Line::from(Span::raw(format!(
+                "  {}",
+                opening_label(&p.build_order, DETAIL_OPENING_STEPS)
+            ))));
+        }
+    }
+    lines
+}
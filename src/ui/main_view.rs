@@ -1,25 +1,35 @@
+use chrono::{DateTime, Utc};
 use ratatui::layout::{Alignment, Constraint, Direction, Layout};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 
 use crate::app::App;
-use crate::history::aggregate_record_for_aurora_id;
+use crate::app::NoteField;
+use crate::build_order::{opening_label, PlayerBuild, OPENINGS_SHOWN};
+use crate::history::{aggregate_record_for_aurora_id, annotations_for, OpponentGame};
+use crate::maps::MapStat;
 use crate::player_list::display_name_for_opponent;
-use crate::ui::profile_stats::profile_stat_lines;
+use crate::profile_history::MatchOutcome;
+use crate::session::{format_duration, now_secs};
+use crate::ui::profile_stats::{apm_line, form_lines, profile_stat_lines, rating_split_line};
+
+const RECENT_GAMES_SHOWN: usize = 5;
+const OPPONENT_MAPS_SHOWN: usize = 3;
+const OPENING_STEPS_SHOWN: usize = 6;
 
 pub fn render_main(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, app: &mut App) {
     let segments = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(9),
+            Constraint::Length(13),
             Constraint::Min(0),
             Constraint::Length(1),
         ])
         .split(area);
 
     let stats_block = Block::default().borders(Borders::ALL).title(Span::styled(
-        "Profile Stats",
+        format!("Profile Stats ({})", app.self_profile.stats_window.label()),
         Style::default()
             .fg(Color::Green)
             .add_modifier(Modifier::BOLD),
@@ -28,10 +38,14 @@ pub fn render_main(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, app:
     let stats_inner = stats_block.inner(stats_area);
     let cols = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(65), Constraint::Min(10)])
+        .constraints([
+            Constraint::Percentage(45),
+            Constraint::Percentage(33),
+            Constraint::Min(10),
+        ])
         .split(stats_inner);
 
-    let stats_lines = profile_stat_lines(
+    let mut stats_lines = profile_stat_lines(
         app.self_profile.rating,
         app.self_profile.main_race.as_deref(),
         &app.self_profile.matchups,
@@ -40,15 +54,25 @@ pub fn render_main(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, app:
             app.self_profile.opponent_dodged,
         )),
     );
+    if let Some(form) = app.self_profile.form.as_ref() {
+        stats_lines.extend(form_lines(form));
+    }
+    stats_lines.extend(rating_split_line(&app.self_profile.rating_split));
+    stats_lines.extend(apm_line(&app.self_profile.apm));
     frame.render_widget(stats_block, stats_area);
     frame.render_widget(
         Paragraph::new(stats_lines).alignment(Alignment::Left),
         cols[0],
     );
     frame.render_widget(
-        Paragraph::new(Line::from(Span::raw(""))).alignment(Alignment::Left),
+        Paragraph::new(map_lines(&app.self_profile.maps, cols[1].height))
+            .alignment(Alignment::Left),
         cols[1],
     );
+    frame.render_widget(
+        Paragraph::new(session_lines(app)).alignment(Alignment::Left),
+        cols[2],
+    );
 
     let opponent_block = Block::default().borders(Borders::ALL).title(Span::styled(
         "Opponent Info",
@@ -122,6 +146,115 @@ pub fn render_main(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, app:
             ]));
         }
 
+        if let Some(form) = app.opponent.form.as_ref() {
+            opponent_profile_lines.extend(form_lines(form));
+        }
+        if !app.opponent.maps.is_empty() {
+            let mut spans = vec![Span::styled(
+                "Maps: ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )];
+            for (i, stat) in app
+                .opponent
+                .maps
+                .iter()
+                .take(OPPONENT_MAPS_SHOWN)
+                .enumerate()
+            {
+                if i > 0 {
+                    spans.push(Span::raw(", "));
+                }
+                spans.push(Span::raw(format!(
+                    "{} {}-{} ({}%)",
+                    stat.map,
+                    stat.wins,
+                    stat.games - stat.wins,
+                    stat.win_pct()
+                )));
+            }
+            opponent_profile_lines.push(Line::from(spans));
+        }
+
+        if let Some((apm, eapm, games)) = app.builds.average_apm(name) {
+            let mut text = format!("{apm}");
+            if let Some(eapm) = eapm {
+                text.push_str(&format!(" (EAPM {eapm})"));
+            }
+            text.push_str(&format!(" over {games} replays"));
+            opponent_profile_lines.push(Line::from(vec![
+                Span::styled(
+                    "APM: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(text),
+            ]));
+        }
+
+        let annotations = annotations_for(&app.opponent.history, app.opponent.aurora_id, name);
+        if !annotations.tags.is_empty() {
+            opponent_profile_lines.push(Line::from(vec![
+                Span::styled(
+                    "Tags: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    annotations.tags.join(", "),
+                    Style::default().fg(Color::LightRed),
+                ),
+            ]));
+        }
+        if let Some(notes) = annotations.notes.as_deref() {
+            opponent_profile_lines.push(Line::from(vec![
+                Span::styled(
+                    "Notes: ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(notes.to_string()),
+            ]));
+        }
+
+        if let Some(rec) = app.opponent.history.get(&name.to_ascii_lowercase())
+            && !rec.games.is_empty()
+        {
+            opponent_profile_lines.push(Line::raw(String::new()));
+            opponent_profile_lines.push(Line::from(Span::styled(
+                "Recent games",
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            for game in rec.recent_games(RECENT_GAMES_SHOWN) {
+                opponent_profile_lines.push(recent_game_line(game));
+            }
+        }
+
+        let openings = app.builds.openings(
+            name,
+            known_race(app.opponent.race.as_deref()),
+            known_race(app.self_profile.main_race.as_deref()),
+            OPENINGS_SHOWN,
+        );
+        if !openings.is_empty() {
+            opponent_profile_lines.push(Line::raw(String::new()));
+            opponent_profile_lines.push(Line::from(Span::styled(
+                "Openings",
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            for build in openings {
+                opponent_profile_lines.push(opening_line(build));
+            }
+        }
+
         let mut matchup_lines = profile_stat_lines(
             rating,
             app.opponent.race.as_deref(),
@@ -191,14 +324,29 @@ pub fn render_main(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, app:
         }
     }
 
+    let profile_area = if app.notes.active {
+        let split = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(4)])
+            .split(columns[0]);
+        render_note_editor(frame, split[1], app);
+        split[0]
+    } else {
+        columns[0]
+    };
+
     let opponent_profile = Paragraph::new(opponent_profile_lines).wrap(Wrap { trim: true });
-    frame.render_widget(opponent_profile, columns[0]);
+    frame.render_widget(opponent_profile, profile_area);
 
     let other_toons = Paragraph::new(other_toons_lines).wrap(Wrap { trim: true });
     frame.render_widget(other_toons, columns[1]);
 
     let hotkey_line = Line::from(Span::styled(
-        "Ctrl+D Debug  •  Ctrl+R Replays  •  Ctrl+Q/Esc Quit",
+        if app.notes.active {
+            "Enter Save  •  Tab Switch field  •  Esc Cancel"
+        } else {
+            "N Notes  •  W Stats window  •  Ctrl+O Opponents  •  Ctrl+D Debug  •  Ctrl+R Replays  •  Ctrl+L Library  •  Ctrl+Q/Esc Quit"
+        },
         Style::default()
             .fg(Color::DarkGray)
             .add_modifier(Modifier::DIM),
@@ -208,3 +356,180 @@ pub fn render_main(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, app:
         .wrap(Wrap { trim: true });
     frame.render_widget(hotkeys, segments[2]);
 }
+
+fn render_note_editor(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, app: &App) {
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        "Edit notes",
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    ));
+    let inner = block.inner(area);
+    let field_style = |field: NoteField| {
+        if app.notes.field == field {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        }
+    };
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("Notes: "),
+            Span::styled(app.notes.notes_input.clone(), field_style(NoteField::Notes)),
+        ]),
+        Line::from(vec![
+            Span::raw("Tags: "),
+            Span::styled(app.notes.tags_input.clone(), field_style(NoteField::Tags)),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+
+    let (label, cursor, row) = match app.notes.field {
+        NoteField::Notes => ("Notes: ", app.notes.notes_cursor, 0),
+        NoteField::Tags => ("Tags: ", app.notes.tags_cursor, 1),
+    };
+    let cursor_x = inner.x + label.len() as u16 + cursor as u16;
+    frame.set_cursor_position((cursor_x.min(inner.x + inner.width), inner.y + row));
+}
+
+fn map_lines(maps: &[MapStat], height: u16) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(Span::styled(
+        "Maps",
+        Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+    ))];
+    if maps.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No map stats.",
+            Style::default().fg(Color::DarkGray),
+        )));
+        return lines;
+    }
+    let rows = usize::from(height).saturating_sub(1);
+    for stat in maps.iter().take(rows) {
+        let mut spans = vec![
+            Span::styled(
+                stat.map.clone(),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" {}% ({})", stat.win_pct(), stat.games)),
+        ];
+        for mu in stat.matchups.iter() {
+            spans.push(Span::styled(
+                format!("  v{} ", mu.opponent_race),
+                Style::default().fg(Color::Gray),
+            ));
+            spans.push(Span::raw(format!("{}-{}", mu.wins, mu.games - mu.wins)));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+fn session_lines(app: &App) -> Vec<Line<'static>> {
+    let label = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::from(Span::styled(
+        "Session",
+        Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+    ))];
+    let Some(session) = app.session.current.as_ref() else {
+        lines.push(Line::from(Span::styled(
+            "No active session.",
+            Style::default().fg(Color::DarkGray),
+        )));
+        if let Some(last) = app.session.last_finished.as_ref() {
+            lines.push(Line::from(vec![
+                Span::styled("Last: ", label),
+                Span::raw(last.summary(now_secs())),
+            ]));
+        }
+        return lines;
+    };
+
+    lines.push(Line::from(vec![
+        Span::styled("W-L: ", label),
+        Span::raw(format!("{}-{}", session.wins, session.losses)),
+    ]));
+    if let Some(delta) = session.rating_delta() {
+        let color = if delta >= 0 { Color::Green } else { Color::Red };
+        lines.push(Line::from(vec![
+            Span::styled("MMR: ", label),
+            Span::styled(format!("{delta:+}"), Style::default().fg(color)),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("Time: ", label),
+        Span::raw(format_duration(session.duration_secs(now_secs()))),
+    ]));
+    if session.dodges > 0 {
+        lines.push(Line::from(vec![
+            Span::styled("Dodges: ", label),
+            Span::raw(session.dodges.to_string()),
+        ]));
+    }
+    lines
+}
+
+// Random is not a race a build can be filtered on.
+fn known_race(race: Option<&str>) -> Option<&str> {
+    race.filter(|r| !r.eq_ignore_ascii_case("random"))
+}
+
+fn opening_line(build: &PlayerBuild) -> Line<'static> {
+    let date = DateTime::<Utc>::from_timestamp(build.timestamp as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let mut spans = vec![Span::styled(
+        format!("{} ", date),
+        Style::default().fg(Color::Gray),
+    )];
+    if let Some(map) = build.map.as_deref() {
+        spans.push(Span::styled(
+            format!("{} • ", map),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    spans.push(Span::raw(opening_label(&build.steps, OPENING_STEPS_SHOWN)));
+    Line::from(spans)
+}
+
+fn recent_game_line(game: &OpponentGame) -> Line<'static> {
+    let date = DateTime::<Utc>::from_timestamp(game.timestamp as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let (label, color) = match game.result {
+        MatchOutcome::Win => ("W", Color::Green),
+        MatchOutcome::Loss => ("L", Color::Red),
+        MatchOutcome::OpponentDodged => ("Dodge (opp)", Color::Yellow),
+        MatchOutcome::SelfDodged => ("Dodge (you)", Color::Yellow),
+    };
+    let mut detail = format!(" {}", game.matchup_label());
+    if let Some(secs) = game.duration_secs {
+        detail.push_str(&format!(" • {}:{:02}", secs / 60, secs % 60));
+    }
+    if let Some(map) = game.map.as_deref() {
+        detail.push_str(&format!(" • {}", map));
+    }
+    let mut spans = vec![
+        Span::styled(format!("{} ", date), Style::default().fg(Color::Gray)),
+        Span::styled(label, Style::default().fg(color)),
+        Span::raw(detail),
+    ];
+    if let (Some(before), Some(after)) = (game.rating_before, game.rating_after) {
+        let delta = after as i64 - before as i64;
+        let delta_color = if delta >= 0 { Color::Green } else { Color::Red };
+        spans.push(Span::raw(" • "));
+        spans.push(Span::styled(
+            format!("{delta:+}"),
+            Style::default().fg(delta_color),
+        ));
+    }
+    Line::from(spans)
+}
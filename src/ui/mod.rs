@@ -3,7 +3,9 @@ use ratatui::layout::{Constraint, Direction, Layout};
 mod debug_view;
 mod display;
 mod footer;
+mod library_view;
 pub mod main_view;
+mod opponents_view;
 mod profile_stats;
 mod replays_view;
 mod status;
@@ -28,6 +30,8 @@ pub fn render(frame: &mut ratatui::Frame, app: &mut App) {
         View::Main => main_view::render_main(frame, layout[1], app),
         View::Debug => debug_view::render_debug(frame, layout[1], app),
         View::Replays => replays_view::render_replays(frame, layout[1], app),
+        View::Opponents => opponents_view::render_opponents(frame, layout[1], app),
+        View::Library => library_view::render_library(frame, layout[1], app),
     }
 
     footer::render_footer(frame, layout[2]);
@@ -0,0 +1,105 @@
+use chrono::{DateTime, Utc};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+
+use crate::app::App;
+use crate::history::{parse_tags, records_with_tags};
+
+pub fn render_opponents(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0)])
+        .split(area);
+
+    let filter_block = Block::default().borders(Borders::ALL).title(Span::styled(
+        "Opponents",
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ));
+    let filter_inner = filter_block.inner(rows[0]);
+    let filter_lines = vec![
+        Line::from(Span::raw(
+            "Ctrl+M Main  •  Type tags (comma separated) to filter  •  ↑/↓ Scroll",
+        )),
+        Line::from(vec![
+            Span::raw("Tag filter: "),
+            Span::styled(
+                app.opponent_list.tag_filter.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]),
+    ];
+    frame.render_widget(
+        Paragraph::new(filter_lines)
+            .alignment(Alignment::Left)
+            .block(filter_block),
+        rows[0],
+    );
+    frame.set_cursor_position((
+        filter_inner.x + "Tag filter: ".len() as u16 + app.opponent_list.tag_cursor as u16,
+        filter_inner.y + 1,
+    ));
+
+    let tags = parse_tags(&app.opponent_list.tag_filter);
+    let records = records_with_tags(&app.opponent.history, &tags);
+
+    let mut lines: Vec<Line> = Vec::new();
+    if records.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No matching opponents.",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for rec in records.iter() {
+        let last_played = rec
+            .last_match_ts
+            .and_then(|ts| DateTime::<Utc>::from_timestamp(ts as i64, 0))
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "—".to_string());
+        let mut spans = vec![
+            Span::styled(
+                format!("{:<16}", rec.name),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " {:<8} {:<8} {:>3}-{:<3} {}",
+                crate::gateway::label(rec.gateway),
+                rec.race.as_deref().unwrap_or("?"),
+                rec.wins,
+                rec.losses,
+                last_played,
+            )),
+        ];
+        if !rec.tags.is_empty() {
+            spans.push(Span::styled(
+                format!("  [{}]", rec.tags.join(", ")),
+                Style::default().fg(Color::LightRed),
+            ));
+        }
+        if let Some(notes) = rec.notes.as_deref() {
+            spans.push(Span::styled(
+                format!("  {}", notes),
+                Style::default().fg(Color::Gray),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let list = Paragraph::new(lines)
+        .scroll((app.opponent_list.scroll, 0))
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                format!("History ({})", records.len()),
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            )),
+        );
+    frame.render_widget(list, rows[1]);
+}
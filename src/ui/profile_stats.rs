@@ -1,6 +1,10 @@
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 
+use crate::apm_stats::ApmStats;
+use crate::form::{FormSummary, FORM_WINDOW};
+use crate::rating_stats::{RatingBucket, RatingSplit};
+
 pub fn profile_stat_lines(
     rating: Option<u32>,
     main_race: Option<&str>,
@@ -79,3 +83,117 @@ pub fn profile_stat_lines(
 
     lines
 }
+
+pub fn form_lines(form: &FormSummary) -> Vec<Line<'static>> {
+    let label = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let mut strip = vec![Span::styled("Form: ", label)];
+    for win in form.strip.iter() {
+        let (text, color) = if *win {
+            ("W", Color::Green)
+        } else {
+            ("L", Color::Red)
+        };
+        strip.push(Span::styled(text, Style::default().fg(color)));
+    }
+    let streak_color = match form.current_streak {
+        Some((true, _)) => Color::Green,
+        _ => Color::Red,
+    };
+    strip.push(Span::raw("  "));
+    strip.push(Span::styled(
+        form.streak_label(),
+        Style::default()
+            .fg(streak_color)
+            .add_modifier(Modifier::BOLD),
+    ));
+    strip.push(Span::styled(
+        format!(
+            " (best W{} / L{})",
+            form.longest_win_streak, form.longest_loss_streak
+        ),
+        Style::default().fg(Color::DarkGray),
+    ));
+
+    let mut matchups = vec![Span::styled(format!("Last {FORM_WINDOW}: "), label)];
+    for (i, m) in form.matchups.iter().enumerate() {
+        if i > 0 {
+            matchups.push(Span::raw("  "));
+        }
+        matchups.push(Span::styled(
+            format!("v{}", m.opponent_race),
+            Style::default().fg(Color::Cyan),
+        ));
+        matchups.push(Span::raw(format!(" {}-{}", m.wins, m.games - m.wins)));
+    }
+
+    vec![Line::from(strip), Line::from(matchups)]
+}
+
+pub fn rating_split_line(split: &RatingSplit) -> Option<Line<'static>> {
+    if split.is_empty() {
+        return None;
+    }
+    let bucket = |label: &str, bucket: &RatingBucket| -> Vec<Span<'static>> {
+        let color = if bucket.points >= 0 {
+            Color::Green
+        } else {
+            Color::Red
+        };
+        vec![
+            Span::styled(format!("{label} "), Style::default().fg(Color::Cyan)),
+            Span::styled(format!("{:+}", bucket.points), Style::default().fg(color)),
+            Span::styled(
+                format!(" ({}-{})", bucket.wins, bucket.games - bucket.wins),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]
+    };
+    let mut spans = vec![Span::styled(
+        "MMR: ",
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    )];
+    spans.extend(bucket("vs higher", &split.vs_higher));
+    spans.push(Span::raw("  "));
+    spans.extend(bucket("vs lower", &split.vs_lower));
+    Some(Line::from(spans))
+}
+
+pub fn apm_line(stats: &ApmStats) -> Option<Line<'static>> {
+    if stats.is_empty() {
+        return None;
+    }
+    let mut spans = vec![Span::styled(
+        "APM: ",
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    )];
+    for (i, m) in stats.matchups.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw("  "));
+        }
+        spans.push(Span::styled(
+            format!("v{} ", m.opponent_race),
+            Style::default().fg(Color::Cyan),
+        ));
+        spans.push(Span::raw(m.apm.to_string()));
+        if let Some(eapm) = m.eapm {
+            spans.push(Span::styled(
+                format!("/{eapm}"),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+    }
+    if stats.trend.len() > 1 {
+        let trend: Vec<String> = stats.trend.iter().map(u32::to_string).collect();
+        spans.push(Span::styled(
+            format!("  trend {}", trend.join(" → ")),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Some(Line::from(spans))
+}
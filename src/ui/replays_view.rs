@@ -4,11 +4,141 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 
 use crate::app::{App, ReplayFocus};
+use crate::replay_download::{ReplayDownloadProgress, ReplayStage, RequestOrigin};
+use crate::session::{format_duration, now_secs};
+
+fn progress_label(progress: &ReplayDownloadProgress) -> String {
+    let mut label = format!(
+        "Downloading replays... {}/{} saved, {} checked",
+        progress.saved, progress.target, progress.attempted
+    );
+    if let Some(season) = progress.season {
+        label.push_str(&format!(
+            " (history season {season}, {} pages)",
+            progress.pages
+        ));
+    }
+    label
+}
+
+// Live list of the running job's replays followed by the queued requests.
+fn render_progress(frame: &mut ratatui::Frame, area: Rect, app: &App) {
+    let mut lines: Vec<Line> = Vec::new();
+    for activity in &app.replay.activity {
+        let color = match activity.stage {
+            ReplayStage::Saved(_) => Color::Green,
+            ReplayStage::Skipped(_) => Color::DarkGray,
+            ReplayStage::Failed(_) => Color::Red,
+            _ => Color::Yellow,
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!("{}  ", activity.label)),
+            Span::styled(activity.stage.label(), Style::default().fg(color)),
+        ]));
+    }
+    if !app.replay.queue.is_empty() {
+        if !lines.is_empty() {
+            lines.push(Line::from(Span::raw("")));
+        }
+        lines.push(Line::from(Span::styled(
+            format!("Queued ({})", app.replay.queue.len()),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for queued in &app.replay.queue {
+            let req = &queued.request;
+            lines.push(Line::from(Span::raw(format!(
+                "- {} @ {} ({}) ×{}{}",
+                req.toon,
+                crate::gateway::label(req.gateway),
+                req.matchup.as_deref().unwrap_or("All"),
+                queued.request.limit - queued.saved.min(queued.request.limit),
+                match req.origin {
+                    RequestOrigin::Manual => "",
+                    RequestOrigin::Watch => "  [watch]",
+                    RequestOrigin::Scout => "  [scout]",
+                }
+            ))));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No downloads running.",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        "Progress",
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    ));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+// Watched players with when they were last checked and what it fetched.
+fn render_watch_list(frame: &mut ratatui::Frame, area: Rect, app: &App) {
+    let mut lines: Vec<Line> = Vec::new();
+    let now = now_secs();
+    for entry in &app.watch_list.entries {
+        let checked = match entry.last_checked {
+            Some(ts) => format!("checked {} ago", format_duration(now.saturating_sub(ts))),
+            None => "not checked yet".to_string(),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    "{} @ {} ({})  ",
+                    entry.toon,
+                    crate::gateway::label(entry.gateway),
+                    entry.matchup.as_deref().unwrap_or("All")
+                ),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(checked, Style::default().fg(Color::Gray)),
+            Span::raw(format!(
+                "  +{} new, {} total",
+                entry.last_saved, entry.total_saved
+            )),
+        ]));
+        if let Some(err) = &entry.last_error {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", err),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        for name in &entry.recent {
+            lines.push(Line::from(Span::styled(
+                format!("  - {}", name),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No players watched. F9 watches the profile above.",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        "Watch list",
+        Style::default()
+            .fg(Color::Blue)
+            .add_modifier(Modifier::BOLD),
+    ));
+    frame.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: true }).block(block),
+        area,
+    );
+}
 
 pub fn render_replays(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
     let rows = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(9), Constraint::Min(0)])
+        .constraints([Constraint::Length(17), Constraint::Min(0)])
         .split(area);
 
     let focus_indicator = |focus: ReplayFocus, target: ReplayFocus| {
@@ -21,7 +151,7 @@ pub fn render_replays(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
 
     let gw_label = crate::gateway::label(app.replay.input_gateway);
     let mut info_lines = vec![Line::from(Span::raw(
-        "Ctrl+M Main  •  Ctrl+S Search  •  Enter Start Download",
+        "Ctrl+M Main  •  Ctrl+S Search  •  Enter Queue download  •  F7 Cancel job  •  F8 Cancel queue  •  F9 Watch",
     ))];
     info_lines.push(Line::from(Span::raw("")));
 
@@ -96,6 +226,52 @@ pub fn render_replays(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
         ),
     ]));
 
+    let filters = &app.replay.filters;
+    let filter_fields = [
+        (
+            ReplayFocus::From,
+            "From: ",
+            filters.from.as_str(),
+            "YYYY-MM-DD",
+        ),
+        (ReplayFocus::To, "To: ", filters.to.as_str(), "YYYY-MM-DD"),
+        (ReplayFocus::Result, "Result: ", filters.result.label(), ""),
+        (
+            ReplayFocus::Opponent,
+            "Opponent: ",
+            filters.opponent.as_str(),
+            "any toon",
+        ),
+        (ReplayFocus::Map, "Map: ", filters.map.as_str(), "any map"),
+        (
+            ReplayFocus::Duration,
+            "Minutes: ",
+            filters.duration.as_str(),
+            "e.g. 5-30 (default over 2)",
+        ),
+        (
+            ReplayFocus::Rating,
+            "Min opponent MMR: ",
+            filters.min_rating.as_str(),
+            "any",
+        ),
+    ];
+    for (focus, label, value, placeholder) in filter_fields {
+        let focused = app.replay.focus == focus;
+        let value = if value.is_empty() && !focused {
+            Span::styled(placeholder, Style::default().fg(Color::DarkGray))
+        } else if focused {
+            Span::styled(value, Style::default().add_modifier(Modifier::BOLD))
+        } else {
+            Span::raw(value)
+        };
+        info_lines.push(Line::from(vec![
+            Span::raw(focus_indicator(app.replay.focus, focus)),
+            Span::raw(label),
+            value,
+        ]));
+    }
+
     if let Some(err) = &app.replay.last_error {
         info_lines.push(Line::from(Span::styled(
             format!("Error: {}", err),
@@ -103,7 +279,10 @@ pub fn render_replays(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
         )));
     } else if app.replay.in_progress {
         info_lines.push(Line::from(Span::styled(
-            "Downloading replays...",
+            match &app.replay.progress {
+                Some(progress) => progress_label(progress),
+                None => "Downloading replays...".to_string(),
+            },
             Style::default().fg(Color::Yellow),
         )));
     }
@@ -126,15 +305,28 @@ pub fn render_replays(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
     let mut summary_lines: Vec<Line> = Vec::new();
     if let Some(summary) = &app.replay.last_summary {
         summary_lines.push(Line::from(Span::styled(
-            "Last download",
+            if summary.cancelled {
+                "Last download (cancelled)"
+            } else {
+                "Last download"
+            },
             Style::default()
                 .fg(Color::Green)
                 .add_modifier(Modifier::BOLD),
         )));
         summary_lines.push(Line::from(Span::raw(format!(
-            "Requested: {}  Saved: {}  Skipped existing: {}  Filtered/skipped: {}",
-            summary.requested, summary.saved, summary.skipped_existing, summary.filtered_short
+            "Requested: {}  Saved: {}  Skipped existing: {}  Filtered: {}",
+            summary.requested,
+            summary.saved,
+            summary.skipped_existing,
+            summary.filtered_total()
         ))));
+        if !summary.filtered.is_empty() {
+            summary_lines.push(Line::from(Span::styled(
+                format!("Filtered by: {}", summary.filtered_label()),
+                Style::default().fg(Color::Gray),
+            )));
+        }
         if !summary.errors.is_empty() {
             summary_lines.push(Line::from(Span::styled(
                 "Errors:",
@@ -180,19 +372,50 @@ pub fn render_replays(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
         summary_lines.push(Line::from(Span::raw("")));
         summary_lines.push(Line::from(Span::styled(
             format!(
-                "Last request: {}{} @ {} ({})",
+                "Last request: {}{} @ {} ({}){}",
                 req.alias
                     .as_ref()
                     .map(|alias| format!("{} ➜ ", alias))
                     .unwrap_or_default(),
                 req.toon,
                 crate::gateway::label(req.gateway),
-                req.matchup.clone().unwrap_or_else(|| "All".to_string())
+                req.matchup.clone().unwrap_or_else(|| "All".to_string()),
+                if req.filter.is_empty() {
+                    ""
+                } else {
+                    " with filters"
+                }
             ),
             Style::default().fg(Color::Gray),
         )));
     }
 
+    let library = &app.library;
+    let unreadable = library
+        .index
+        .entries()
+        .filter(|(_, e)| e.unreadable)
+        .count();
+    let mut library_text = format!("Library: {} replays indexed", library.index.len());
+    if unreadable > 0 {
+        library_text.push_str(&format!(" ({} unreadable)", unreadable));
+    }
+    if library.job_rx.is_some() {
+        library_text.push_str("  •  scanning…");
+    } else if let Some(scan) = library.last_scan
+        && scan.added + scan.updated + scan.removed > 0
+    {
+        library_text.push_str(&format!(
+            "  •  last scan +{} ~{} -{}",
+            scan.added, scan.updated, scan.removed
+        ));
+    }
+    summary_lines.push(Line::from(Span::raw("")));
+    summary_lines.push(Line::from(Span::styled(
+        library_text,
+        Style::default().fg(Color::Gray),
+    )));
+
     let summary_block_base = Block::default().borders(Borders::ALL).title(Span::styled(
         "Status",
         Style::default()
@@ -205,7 +428,17 @@ pub fn render_replays(frame: &mut ratatui::Frame, area: Rect, app: &mut App) {
         .wrap(Wrap { trim: true })
         .block(summary_block_base);
 
-    frame.render_widget(summary_block, rows[1]);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[1]);
+    frame.render_widget(summary_block, columns[0]);
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(columns[1]);
+    render_progress(frame, right[0], app);
+    render_watch_list(frame, right[1], app);
 
     if matches!(app.replay.focus, ReplayFocus::Toon) {
         let cursor_x = input_inner.x + 2 + "Profile: ".len() as u16 + app.replay.toon_cursor as u16;
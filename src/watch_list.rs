@@ -0,0 +1,262 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::replay_download::{
+    DownloadFilter, ReplayDownloadRequest, ReplayDownloadSummary, RequestOrigin,
+};
+
+// New replays fetched per check; anything beyond waits for the next one.
+pub const WATCH_CHECK_LIMIT: usize = 10;
+// How far back the first check of a newly watched player reaches.
+const FIRST_CHECK_LOOKBACK_SECS: u64 = 7 * 24 * 60 * 60;
+// File names kept per player for the digest.
+const RECENT_KEEP: usize = 5;
+
+// Players whose new ladder replays are collected while the game is running.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WatchList {
+    pub entries: Vec<WatchEntry>,
+    #[serde(skip)]
+    pub dirty: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchEntry {
+    pub toon: String,
+    pub gateway: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matchup: Option<String>,
+    pub added_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_checked: Option<u64>,
+    // Start time of the newest game already handled; later checks only look
+    // at games after it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<u64>,
+    #[serde(default)]
+    pub last_saved: usize,
+    #[serde(default)]
+    pub total_saved: usize,
+    // File names of the latest saved replays, newest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl WatchEntry {
+    fn new(toon: &str, gateway: u16, matchup: Option<String>, now: u64) -> Self {
+        Self {
+            toon: toon.to_string(),
+            gateway,
+            matchup,
+            added_at: now,
+            last_checked: None,
+            last_seen: None,
+            last_saved: 0,
+            total_saved: 0,
+            recent: Vec::new(),
+            last_error: None,
+        }
+    }
+
+    fn matches(&self, toon: &str, gateway: u16, matchup: Option<&str>) -> bool {
+        self.toon.eq_ignore_ascii_case(toon)
+            && self.gateway == gateway
+            && match (self.matchup.as_deref(), matchup) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                (a, b) => a == b,
+            }
+    }
+
+    pub fn request(&self) -> ReplayDownloadRequest {
+        let from = match self.last_seen {
+            Some(seen) => seen + 1,
+            None => self.added_at.saturating_sub(FIRST_CHECK_LOOKBACK_SECS),
+        };
+        ReplayDownloadRequest {
+            toon: self.toon.clone(),
+            gateway: self.gateway,
+            matchup: self.matchup.clone(),
+            limit: WATCH_CHECK_LIMIT,
+            alias: None,
+            filter: DownloadFilter {
+                from: Some(from),
+                ..DownloadFilter::default()
+            },
+            origin: RequestOrigin::Watch,
+        }
+    }
+}
+
+impl WatchList {
+    // Adds the player, or removes them if already watched. Returns whether
+    // they are watched afterwards.
+    pub fn toggle(&mut self, toon: &str, gateway: u16, matchup: Option<&str>, now: u64) -> bool {
+        self.dirty = true;
+        if let Some(pos) = self
+            .entries
+            .iter()
+            .position(|e| e.matches(toon, gateway, matchup))
+        {
+            self.entries.remove(pos);
+            return false;
+        }
+        self.entries.push(WatchEntry::new(
+            toon,
+            gateway,
+            matchup.map(str::to_string),
+            now,
+        ));
+        true
+    }
+
+    // The entry checked longest ago, if its interval has passed.
+    pub fn next_due(&mut self, now: u64, interval: Duration) -> Option<&mut WatchEntry> {
+        self.entries
+            .iter_mut()
+            .filter(|e| {
+                e.last_checked
+                    .is_none_or(|checked| now.saturating_sub(checked) >= interval.as_secs())
+            })
+            .min_by_key(|e| e.last_checked.unwrap_or_default())
+    }
+
+    pub fn record_result(
+        &mut self,
+        request: &ReplayDownloadRequest,
+        summary: &ReplayDownloadSummary,
+    ) {
+        let matchup = request.matchup.as_deref();
+        let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|e| e.matches(&request.toon, request.gateway, matchup))
+        else {
+            return;
+        };
+        self.dirty = true;
+        entry.last_saved = summary.saved;
+        entry.total_saved += summary.saved;
+        let mut names: Vec<String> = summary
+            .saved_paths
+            .iter()
+            .filter_map(|p| p.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        names.append(&mut entry.recent);
+        names.truncate(RECENT_KEEP);
+        entry.recent = names;
+        entry.last_error = summary.errors.first().cloned();
+        // Failed, cancelled or limited checks keep their window so the games
+        // are looked at again next time; saved ones are skipped by the
+        // manifest.
+        if summary.reached_end && !summary.cancelled && summary.errors.is_empty() {
+            entry.last_seen = entry.last_seen.max(summary.newest_played_at);
+        }
+    }
+}
+
+pub struct WatchListStore {
+    path: PathBuf,
+}
+
+impl WatchListStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn load(&self) -> Result<WatchList> {
+        match fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("deserialize watch list {}", self.path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(WatchList::default()),
+            Err(err) => {
+                Err(anyhow!(err).context(format!("read watch list {}", self.path.display())))
+            }
+        }
+    }
+
+    pub fn save(&self, list: &WatchList) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("create watch list directory {}", parent.display()))?;
+        }
+        let data = serde_json::to_vec_pretty(list).context("serialize watch list")?;
+        fs::write(&self.path, data)
+            .with_context(|| format!("write watch list {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_advance_past_handled_games_only_when_clean() {
+        let mut list = WatchList::default();
+        assert!(list.toggle("Flash", 30, Some("TvZ"), 1_000_000));
+        let request = list.entries[0].request();
+        assert_eq!(request.origin, RequestOrigin::Watch);
+        assert_eq!(
+            request.filter.from,
+            Some(1_000_000 - FIRST_CHECK_LOOKBACK_SECS)
+        );
+
+        let mut summary = ReplayDownloadSummary {
+            saved: 2,
+            saved_paths: vec![PathBuf::from("a/one.rep"), PathBuf::from("a/two.rep")],
+            newest_played_at: Some(999_000),
+            reached_end: true,
+            cancelled: true,
+            ..ReplayDownloadSummary::default()
+        };
+        list.record_result(&request, &summary);
+        assert_eq!(list.entries[0].last_seen, None);
+        assert_eq!(list.entries[0].recent, vec!["one.rep", "two.rep"]);
+
+        summary.cancelled = false;
+        list.record_result(&request, &summary);
+        assert_eq!(list.entries[0].total_saved, 4);
+        assert_eq!(list.entries[0].request().filter.from, Some(999_001));
+
+        // Checked just now, so nothing is due until the interval passes.
+        list.entries[0].last_checked = Some(1_000_000);
+        let interval = Duration::from_secs(600);
+        assert!(list.next_due(1_000_100, interval).is_none());
+        assert!(list.next_due(1_000_600, interval).is_some());
+
+        assert!(!list.toggle("flash", 30, Some("tvz"), 1_000_700));
+        assert!(list.entries.is_empty());
+    }
+
+    #[test]
+    fn checks_stopped_by_the_limit_look_again() {
+        let mut list = WatchList::default();
+        list.toggle("Flash", 30, None, 1_000_000);
+        let request = list.entries[0].request();
+        let from = request.filter.from;
+        // Twelve new games: the check saves the newest ten and stops.
+        let mut summary = ReplayDownloadSummary {
+            saved: WATCH_CHECK_LIMIT,
+            newest_played_at: Some(999_900),
+            ..ReplayDownloadSummary::default()
+        };
+        list.record_result(&request, &summary);
+        assert_eq!(list.entries[0].last_seen, None);
+        assert_eq!(list.entries[0].request().filter.from, from);
+
+        // The next check skips the saved ten, takes the last two and runs
+        // out of history.
+        summary.saved = 2;
+        summary.reached_end = true;
+        list.record_result(&request, &summary);
+        assert_eq!(list.entries[0].last_seen, Some(999_900));
+        assert_eq!(list.entries[0].total_saved, WATCH_CHECK_LIMIT + 2);
+    }
+}